@@ -0,0 +1,744 @@
+use std::fmt::Display;
+
+use anyhow::Context;
+use chrono::{DateTime, Months, TimeZone, Utc};
+use iced::{
+    Element, Length,
+    widget::{Button, Row, Scrollable, TextInput, button, column, row, text, text_input},
+};
+use plotters_iced2::ChartWidget;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::{self, EDGE_PADDING, PADDING},
+    ledger::{
+        Account, Transaction, Transactions,
+        account::{ParseDateError, parse_date},
+        history::{Command, History, TransactionChange},
+        money::Fiat,
+    },
+};
+
+const EDIT_WIDTH: f32 = 160.0;
+
+use super::{
+    Duration, ROW_SPACING, chart::Chart, number_cell, screen::Screen, set_amount, some_or_empty,
+    text_cell,
+};
+
+/// What the window shows of an account, and what is being typed into it.
+#[derive(Clone, Debug, Default)]
+pub struct View {
+    pub check_box: bool,
+    pub duration: Duration,
+    pub tx: ToSubmit,
+    pub filter_date: Option<DateTime<Utc>>,
+    pub filter_date_year: Option<i32>,
+    pub filter_date_month: Option<u32>,
+    pub editing: Option<Editing>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ToSubmit {
+    pub amount: Option<Decimal>,
+    pub balance: Option<Decimal>,
+    pub comment: String,
+    pub date: String,
+}
+
+impl ToSubmit {
+    pub const fn new() -> Self {
+        Self {
+            amount: None,
+            balance: None,
+            comment: String::new(),
+            date: String::new(),
+        }
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.amount.is_none()
+            && self.balance.is_none()
+            && self.comment.is_empty()
+            && self.date.is_empty()
+    }
+
+    pub fn submit_commit(&self) -> String {
+        self.comment.trim().to_string()
+    }
+}
+
+/// A transaction that is being edited in place.
+#[derive(Clone, Debug)]
+pub struct Editing {
+    pub index: usize,
+    pub tx: ToSubmit,
+}
+
+impl Editing {
+    pub fn new(index: usize, tx: &Transaction) -> Self {
+        let (amount, balance) = if tx.snapshot {
+            (None, Some(tx.balance))
+        } else {
+            (Some(tx.amount), None)
+        };
+
+        Self {
+            index,
+            tx: ToSubmit {
+                amount,
+                balance,
+                comment: tx.comment.clone(),
+                date: tx.date.format("%Y-%m-%d").to_string(),
+            },
+        }
+    }
+}
+
+impl Default for ToSubmit {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Account {
+    fn clear_date(&mut self) {
+        self.view.filter_date_year = None;
+        self.view.filter_date_month = None;
+        self.view.filter_date = None;
+    }
+
+    fn get_quantity(&self, date: DateTime<Utc>) -> Option<Transaction> {
+        if let Some(txs) = &self.txs_2nd {
+            if txs.txs.is_empty() {
+                return None;
+            }
+
+            if txs.txs.len() == 1 {
+                let tx = txs.txs.first().unwrap();
+                return if tx.date <= date {
+                    Some(tx.clone())
+                } else {
+                    None
+                };
+            }
+
+            for window in txs.txs.windows(2) {
+                if window[1].date > date {
+                    return Some(window[0].clone());
+                }
+            }
+
+            let tx = txs.txs.last().unwrap();
+            if tx.date <= date {
+                return Some(tx.clone());
+            }
+
+            None
+        } else {
+            None
+        }
+    }
+
+    fn input(&self) -> Row<'_, super::Message> {
+        row![
+            balance_view(self.view.tx.balance.as_ref()),
+            amount_view(self.view.tx.amount.as_ref()),
+            date_view(&self.view.tx.date),
+            comment_view(&self.view.tx.comment),
+            add_view(self.view.tx.amount.as_ref(), self.view.tx.balance.as_ref()),
+            text(" ".repeat(EDGE_PADDING)),
+        ]
+        .padding(PADDING)
+        .spacing(ROW_SPACING)
+    }
+
+    fn filter_date(&self) -> Row<'_, super::Message> {
+        let year = text_input("Year", &some_or_empty(self.view.filter_date_year.as_ref()))
+            .on_input(|string| app::Message::Account(Message::ChangeFilterDateYear(string)));
+
+        let month = text_input(
+            "Month",
+            &some_or_empty(self.view.filter_date_month.as_ref()),
+        )
+        .on_input(|string| app::Message::Account(Message::ChangeFilterDateMonth(string)));
+
+        let mut filter_button = button("Filter");
+        if self.submit_filter_date().is_some() {
+            filter_button =
+                filter_button.on_press(app::Message::Account(Message::SubmitFilterDate));
+        }
+
+        let clear_button = button("Clear").on_press(app::Message::Account(Message::ClearDate));
+        row![
+            year,
+            month,
+            filter_button,
+            clear_button,
+            text(" ".repeat(EDGE_PADDING)),
+        ]
+        .padding(PADDING)
+        .spacing(ROW_SPACING)
+    }
+
+    pub(crate) fn list_transactions_2nd(&self, history: &History) -> Scrollable<'_, app::Message> {
+        let mut txs_struct = self.txs_2nd.as_ref().unwrap().clone();
+        let offset = txs_struct.month_start(self.view.filter_date);
+        txs_struct.filter_month(self.view.filter_date);
+
+        let chart = Chart {
+            txs: txs_struct.clone(),
+            duration: self.view.duration.clone(),
+        };
+        let chart = ChartWidget::new(chart).height(Length::Fixed(400.0));
+
+        let mut col_1 = column![text_cell("Balance")].align_x(iced::Alignment::End);
+        let mut col_2 = column![text_cell("Δ")].align_x(iced::Alignment::End);
+        let mut col_3 = column![text_cell("Date")];
+        let mut col_4 = column![text_cell("Comment")];
+        let mut col_5 = column![text_cell("")];
+
+        for (i, tx) in txs_struct.txs.iter().enumerate() {
+            let cells = self.tx_cells(offset + i, tx, 10);
+            col_1 = col_1.push(cells.balance);
+            col_2 = col_2.push(cells.amount);
+            col_3 = col_3.push(cells.date);
+            col_4 = col_4.push(cells.comment);
+            col_5 = col_5.push(cells.buttons);
+        }
+        let rows = row![col_1, col_2, col_3, col_4, col_5];
+
+        let error = self
+            .view
+            .error
+            .as_ref()
+            .map_or_else(|| row![], |error| row![text_cell(error)]);
+
+        let col = column![
+            text_cell(txs_struct.currency.to_string()),
+            chart,
+            change_duration(),
+            rows.spacing(ROW_SPACING),
+            row![
+                text_cell("balance: "),
+                number_cell(self.balance_2nd().unwrap()),
+                text_cell("total: "),
+                number_cell(self.total_2nd())
+            ],
+            self.input(),
+            self.filter_date(),
+            error,
+            back_exit_view(history),
+        ];
+
+        Scrollable::new(col)
+    }
+
+    fn rows(&self, txs_1st: &Transactions<Fiat>, offset: usize) -> Row<'_, super::Message> {
+        let mut col_1 = column![text_cell("Balance")].align_x(iced::Alignment::End);
+        let mut col_2 = column![text_cell("Δ")].align_x(iced::Alignment::End);
+        let mut col_3 = column![text_cell("Price")].align_x(iced::Alignment::End);
+        let mut col_3b = column![text_cell("Δ")].align_x(iced::Alignment::End);
+        let mut col_4 = column![text_cell("Quantity")].align_x(iced::Alignment::End);
+        let mut col_4b = column![text_cell("Δ")].align_x(iced::Alignment::End);
+        let mut col_5 = column![text_cell("Date")];
+        let mut col_6 = column![text_cell("Comment")];
+        let mut col_7 = column![text_cell("")];
+
+        let mut quantity = dec!(0);
+        let mut price = dec!(0);
+        for (i, tx) in txs_1st.txs.iter().enumerate() {
+            let balance = tx.balance;
+
+            if let Some(tx_quantity) = self.get_quantity(tx.date) {
+                let mut quantity_new = tx_quantity.balance;
+                let mut price_new = balance / quantity_new;
+                let mut quantity_diff = quantity_new - quantity;
+                let mut price_diff = price_new - price;
+                quantity = quantity_new;
+                price = price_new;
+
+                price_new.rescale(2);
+                price_diff.rescale(2);
+                quantity_new.rescale(8);
+                quantity_diff.rescale(8);
+                col_3 = col_3.push(number_cell(price_new));
+                col_3b = col_3b.push(number_cell(price_diff));
+                col_4 = col_4.push(number_cell(quantity_new));
+                col_4b = col_4b.push(number_cell(quantity_diff));
+            } else {
+                col_3 = col_3.push(text_cell(""));
+                col_3b = col_3b.push(text_cell(""));
+                col_4 = col_4.push(text_cell(""));
+                col_4b = col_4b.push(text_cell(""));
+            }
+
+            let cells = self.tx_cells(offset + i, tx, 2);
+            col_1 = col_1.push(cells.balance);
+            col_2 = col_2.push(cells.amount);
+            col_5 = col_5.push(cells.date);
+            col_6 = col_6.push(cells.comment);
+            col_7 = col_7.push(cells.buttons);
+        }
+
+        let rows = if self.txs_2nd.is_some() {
+            row![
+                col_1, col_2, col_3, col_3b, col_4, col_4b, col_5, col_6, col_7
+            ]
+        } else {
+            row![col_1, col_2, col_5, col_6, col_7]
+        };
+
+        rows.spacing(ROW_SPACING)
+    }
+
+    pub(crate) fn list_transactions(&self, history: &History) -> Scrollable<'_, app::Message> {
+        let mut txs_1st = self.txs_1st.clone();
+        let offset = txs_1st.month_start(self.view.filter_date);
+        txs_1st.filter_month(self.view.filter_date);
+
+        let chart = Chart {
+            txs: txs_1st.clone(),
+            duration: self.view.duration.clone(),
+        };
+        let chart: ChartWidget<_, _, _, _> = ChartWidget::new(chart).height(Length::Fixed(400.0));
+
+        let error = self
+            .view
+            .error
+            .as_ref()
+            .map_or_else(|| row![], |error| row![text_cell(error)]);
+
+        let name = if let Some(txs_2nd) = &self.txs_2nd {
+            txs_2nd.currency.to_string()
+        } else {
+            self.txs_1st.currency.to_string()
+        };
+
+        let col = column![
+            text_cell(name),
+            chart,
+            change_duration(),
+            self.rows(&txs_1st, offset),
+            row![
+                text_cell("balance: "),
+                number_cell(txs_1st.balance()),
+                text_cell("total: "),
+                number_cell(txs_1st.total()),
+            ]
+            .spacing(ROW_SPACING),
+            self.input(),
+            self.filter_date(),
+            error,
+            back_exit_view(history),
+        ];
+
+        Scrollable::new(col)
+    }
+
+    fn parse_date(&self) -> Result<DateTime<Utc>, ParseDateError> {
+        parse_date(&self.view.tx.date)
+    }
+
+    fn tx_cells(&self, index: usize, tx: &Transaction, scale: u32) -> TxCells<'_> {
+        let mut balance = tx.balance;
+        let mut amount = tx.amount;
+        balance.rescale(scale);
+        amount.rescale(scale);
+
+        if let Some(editing) = &self.view.editing
+            && editing.index == index
+        {
+            let balance = if tx.snapshot {
+                edit_balance_view(editing.tx.balance.as_ref()).into()
+            } else {
+                number_cell(balance).into()
+            };
+            let amount = if tx.snapshot {
+                number_cell(amount).into()
+            } else {
+                edit_amount_view(editing.tx.amount.as_ref()).into()
+            };
+
+            let mut save = button("Save");
+            if editing.tx.amount.is_some() || editing.tx.balance.is_some() {
+                save = save.on_press(app::Message::Account(Message::SubmitEdit));
+            }
+
+            return TxCells {
+                balance,
+                amount,
+                date: edit_date_view(&editing.tx.date).into(),
+                comment: edit_comment_view(&editing.tx.comment).into(),
+                buttons: row![
+                    save,
+                    button("Cancel").on_press(app::Message::Account(Message::CancelEdit)),
+                ]
+                .padding(PADDING)
+                .spacing(ROW_SPACING)
+                .into(),
+            };
+        }
+
+        TxCells {
+            balance: number_cell(balance).into(),
+            amount: number_cell(amount).into(),
+            date: text_cell(tx.date.format("%Y-%m-%d").to_string()).into(),
+            comment: text_cell(tx.comment.clone()).into(),
+            buttons: row![
+                button("Edit").on_press(app::Message::Account(Message::Edit(index))),
+                button("Delete").on_press(app::Message::Delete(index)),
+            ]
+            .padding(PADDING)
+            .spacing(ROW_SPACING)
+            .into(),
+        }
+    }
+
+    fn submit_edit(&mut self, account: usize, secondary: bool) -> anyhow::Result<Command> {
+        let editing = self
+            .view
+            .editing
+            .clone()
+            .context("Nothing is being edited!")?;
+        let before = self.txs(secondary)[editing.index].clone();
+
+        let mut after = before.clone();
+        if before.date.format("%Y-%m-%d").to_string() != editing.tx.date {
+            after.date = parse_date(&editing.tx.date)?;
+        }
+        if after.snapshot {
+            after.balance = editing.tx.balance.context("The balance is empty!")?;
+        } else {
+            after.amount = editing.tx.amount.context("The amount is empty!")?;
+        }
+        after.comment = editing.tx.submit_commit();
+
+        self.remove_tx(secondary, editing.index);
+        let index_after = self.insert_tx(secondary, after);
+        self.view.editing = None;
+
+        Ok(Command::EditTransaction {
+            account,
+            secondary,
+            index_before: editing.index,
+            index_after,
+            before,
+            after: self.txs(secondary)[index_after].clone(),
+        })
+    }
+
+    fn submit_filter_date(&self) -> Option<DateTime<Utc>> {
+        let year = self.view.filter_date_year?;
+        let month = self.view.filter_date_month?;
+
+        Some(TimeZone::with_ymd_and_hms(&Utc, year, month, 1, 0, 0, 0).unwrap())
+    }
+
+    fn submit_balance(&self) -> anyhow::Result<Transaction> {
+        let balance = self.view.tx.balance.unwrap();
+        let date = self.parse_date()?;
+
+        Ok(Transaction {
+            amount: dec!(0),
+            balance,
+            comment: self.view.tx.submit_commit(),
+            date,
+            snapshot: true,
+        })
+    }
+
+    fn submit_tx(&self) -> anyhow::Result<Transaction> {
+        let amount = self.view.tx.amount.unwrap();
+        let date = self.parse_date()?;
+
+        Ok(Transaction {
+            amount,
+            balance: dec!(0),
+            comment: self.view.tx.submit_commit(),
+            date,
+            snapshot: false,
+        })
+    }
+
+    fn display_error(&mut self, result: anyhow::Result<Transaction>) -> Option<Transaction> {
+        match result {
+            Ok(tx) => Some(tx),
+            Err(error) => {
+                self.view.error = Some(error.to_string());
+                None
+            }
+        }
+    }
+
+    fn update_edit(
+        &mut self,
+        account: usize,
+        secondary: bool,
+        message: Message,
+    ) -> Option<Command> {
+        match message {
+            Message::Edit(index) => {
+                let tx = &self.txs(secondary)[index];
+                self.view.editing = Some(Editing::new(index, tx));
+            }
+            Message::ChangeEditAmount(amount) => {
+                if let Some(editing) = &mut self.view.editing {
+                    set_amount(&mut editing.tx.amount, &amount);
+                }
+            }
+            Message::ChangeEditBalance(balance) => {
+                if let Some(editing) = &mut self.view.editing {
+                    set_amount(&mut editing.tx.balance, &balance);
+                }
+            }
+            Message::ChangeEditComment(comment) => {
+                if let Some(editing) = &mut self.view.editing {
+                    editing.tx.comment = comment;
+                }
+            }
+            Message::ChangeEditDate(date) => {
+                if let Some(editing) = &mut self.view.editing {
+                    editing.tx.date = date;
+                }
+            }
+            Message::CancelEdit => self.view.editing = None,
+            Message::SubmitEdit => match self.submit_edit(account, secondary) {
+                Ok(command) => return Some(command),
+                Err(error) => self.view.error = Some(error.to_string()),
+            },
+            _ => {}
+        }
+        None
+    }
+
+    /// Applies the message and returns the change made to the ledger, if any.
+    pub(crate) fn update(&mut self, screen: &Screen, message: Message) -> Option<Command> {
+        self.view.error = None;
+
+        let (account, secondary) = match screen {
+            Screen::Account(account) => (*account, false),
+            Screen::AccountSecondary(account) => (*account, true),
+            Screen::Accounts
+            | Screen::Configuration
+            | Screen::Import
+            | Screen::Locked
+            | Screen::Passphrase
+            | Screen::Recover
+            | Screen::Search
+            | Screen::StartupError => {
+                panic!("You can't change an account here!");
+            }
+        };
+
+        match message {
+            Message::ChangeBalance(balance) => {
+                set_amount(&mut self.view.tx.balance, &balance);
+            }
+            Message::ChangeComment(comment) => self.view.tx.comment = comment,
+            Message::ChangeDate(date) => self.view.tx.date = date,
+            Message::ChangeFilterDateMonth(date) => {
+                if date.is_empty() {
+                    self.view.filter_date_month = None;
+                }
+                if let Ok(date) = date.parse()
+                    && (1..13).contains(&date)
+                {
+                    self.view.filter_date_month = Some(date);
+                }
+            }
+            Message::ChangeFilterDateYear(date) => {
+                if date.is_empty() {
+                    self.view.filter_date_year = None;
+                }
+                if let Ok(date) = date.parse()
+                    && (0..3_000).contains(&date)
+                {
+                    self.view.filter_date_year = Some(date);
+                }
+            }
+            Message::ChangeTx(tx) => set_amount(&mut self.view.tx.amount, &tx),
+            Message::ChartWeek => self.view.duration = Duration::Week,
+            Message::ChartMonth => self.view.duration = Duration::Month,
+            Message::ChartYear => self.view.duration = Duration::Year,
+            Message::ChartAll => self.view.duration = Duration::All,
+            Message::ClearDate => self.clear_date(),
+            Message::CancelEdit
+            | Message::ChangeEditAmount(_)
+            | Message::ChangeEditBalance(_)
+            | Message::ChangeEditComment(_)
+            | Message::ChangeEditDate(_)
+            | Message::Edit(_)
+            | Message::SubmitEdit => return self.update_edit(account, secondary, message),
+            Message::SubmitBalance => {
+                if let Some(tx) = self.display_error(self.submit_balance()) {
+                    return Some(self.submit(account, secondary, tx));
+                }
+            }
+            Message::SubmitFilterDate => {
+                self.view.filter_date = self.submit_filter_date();
+            }
+            Message::SubmitTx => {
+                if let Some(tx) = self.display_error(self.submit_tx()) {
+                    return Some(self.submit(account, secondary, tx));
+                }
+            }
+        }
+        None
+    }
+
+    fn submit(&mut self, account: usize, secondary: bool, tx: Transaction) -> Command {
+        let index = self.insert_tx(secondary, tx);
+        self.view.tx = ToSubmit::new();
+
+        Command::InsertTransaction(TransactionChange {
+            account,
+            secondary,
+            index,
+            tx: self.txs(secondary)[index].clone(),
+        })
+    }
+}
+
+impl<T: Clone + Display> Transactions<T> {
+    fn filter_month(&mut self, filter_date: Option<DateTime<Utc>>) {
+        if let Some(date) = filter_date {
+            let mut filtered_tx = Vec::new();
+            for tx in &self.txs {
+                if tx.date >= date && tx.date < date.checked_add_months(Months::new(1)).unwrap() {
+                    filtered_tx.push(tx.clone());
+                }
+            }
+            self.txs = filtered_tx;
+        }
+    }
+
+    /// The index of the first transaction kept by `filter_month`.
+    fn month_start(&self, filter_date: Option<DateTime<Utc>>) -> usize {
+        filter_date.map_or(0, |date| self.txs.partition_point(|tx| tx.date < date))
+    }
+}
+
+fn amount_view(amount: Option<&Decimal>) -> TextInput<'_, app::Message> {
+    text_input("Amount", &some_or_empty(amount))
+        .on_input(|string| app::Message::Account(Message::ChangeTx(string)))
+}
+
+fn balance_view(balance: Option<&Decimal>) -> TextInput<'_, app::Message> {
+    text_input("Balance", &some_or_empty(balance))
+        .on_input(|string| app::Message::Account(Message::ChangeBalance(string)))
+}
+
+fn date_view(date: &str) -> TextInput<'_, app::Message> {
+    text_input("Date YYYY-MM-DD (empty for today)", date)
+        .on_input(|string| app::Message::Account(Message::ChangeDate(string)))
+}
+
+fn comment_view(comment: &str) -> TextInput<'_, app::Message> {
+    text_input("Comment", comment)
+        .on_input(|string| app::Message::Account(Message::ChangeComment(string)))
+        .on_paste(|string| app::Message::Account(Message::ChangeComment(string)))
+}
+
+fn add_view<'a>(amount: Option<&Decimal>, balance: Option<&Decimal>) -> Button<'a, app::Message> {
+    let mut add = button("Add");
+    match (amount, balance) {
+        (Some(_amount), None) => add = add.on_press(app::Message::Account(Message::SubmitTx)),
+        (None, Some(_balance)) => {
+            add = add.on_press(app::Message::Account(Message::SubmitBalance));
+        }
+        (None, None) | (Some(_), Some(_)) => {}
+    }
+    add
+}
+
+fn edit_amount_view(amount: Option<&Decimal>) -> TextInput<'_, app::Message> {
+    text_input("Amount", &some_or_empty(amount))
+        .on_input(|string| app::Message::Account(Message::ChangeEditAmount(string)))
+        .width(EDIT_WIDTH)
+}
+
+fn edit_balance_view(balance: Option<&Decimal>) -> TextInput<'_, app::Message> {
+    text_input("Balance", &some_or_empty(balance))
+        .on_input(|string| app::Message::Account(Message::ChangeEditBalance(string)))
+        .width(EDIT_WIDTH)
+}
+
+fn edit_date_view(date: &str) -> TextInput<'_, app::Message> {
+    text_input("YYYY-MM-DD", date)
+        .on_input(|string| app::Message::Account(Message::ChangeEditDate(string)))
+        .width(EDIT_WIDTH)
+}
+
+fn edit_comment_view(comment: &str) -> TextInput<'_, app::Message> {
+    text_input("Comment", comment)
+        .on_input(|string| app::Message::Account(Message::ChangeEditComment(string)))
+        .on_paste(|string| app::Message::Account(Message::ChangeEditComment(string)))
+        .width(EDIT_WIDTH * 2.0)
+}
+
+fn back_exit_view<'a>(history: &History) -> Row<'a, app::Message> {
+    let mut undo = button("Undo");
+    if history.can_undo() {
+        undo = undo.on_press(app::Message::Undo);
+    }
+
+    let mut redo = button("Redo");
+    if history.can_redo() {
+        redo = redo.on_press(app::Message::Redo);
+    }
+
+    row![
+        button("Back").on_press(app::Message::Back),
+        undo,
+        redo,
+        button("Exit").on_press(app::Message::Exit),
+    ]
+    .spacing(ROW_SPACING)
+}
+
+fn change_duration<'a>() -> Row<'a, app::Message> {
+    let col_1 = button("Week").on_press(app::Message::Account(Message::ChartWeek));
+    let col_2 = button("Month").on_press(app::Message::Account(Message::ChartMonth));
+    let col_3 = button("Year").on_press(app::Message::Account(Message::ChartYear));
+    let col_4 = button("All").on_press(app::Message::Account(Message::ChartAll));
+
+    row![col_1, col_2, col_3, col_4].spacing(ROW_SPACING)
+}
+
+/// The cells of one transaction row.
+struct TxCells<'a> {
+    balance: Element<'a, app::Message>,
+    amount: Element<'a, app::Message>,
+    date: Element<'a, app::Message>,
+    comment: Element<'a, app::Message>,
+    buttons: Element<'a, app::Message>,
+}
+
+#[derive(Clone, Debug)]
+pub enum Message {
+    ChangeBalance(String),
+    ChangeComment(String),
+    ChangeDate(String),
+    ChangeEditAmount(String),
+    ChangeEditBalance(String),
+    ChangeEditComment(String),
+    ChangeEditDate(String),
+    ChangeFilterDateMonth(String),
+    ChangeFilterDateYear(String),
+    ChangeTx(String),
+    ChartWeek,
+    ChartMonth,
+    ChartYear,
+    ChartAll,
+    ClearDate,
+    CancelEdit,
+    Edit(usize),
+    SubmitBalance,
+    SubmitEdit,
+    SubmitFilterDate,
+    SubmitTx,
+}
@@ -1,14 +1,15 @@
 use std::fmt::Display;
 
+use chrono::{TimeDelta, Utc};
 use plotters::{
     series::AreaSeries,
     style::{Color, FontTransform, IntoFont, ShapeStyle, TextStyle},
 };
 use rust_decimal::prelude::ToPrimitive;
 
-use crate::app::message::Message;
+use crate::{app::message::Message, ledger::Transactions};
 
-use super::{Duration, account::transactions::Transactions, solarized};
+use super::{Duration, solarized};
 
 pub struct Chart<T: Clone + Display> {
     pub txs: Transactions<T>,
@@ -88,3 +89,53 @@ impl<T: Clone + Display> plotters_iced2::Chart<Message> for Chart<T> {
         }
     }
 }
+
+impl<T: Clone + Display> Transactions<T> {
+    fn last_week(&self) -> Transactions<T> {
+        let last_week = Utc::now() - TimeDelta::weeks(1);
+        let mut txs = Vec::new();
+
+        for tx in &self.txs {
+            if tx.date >= last_week {
+                txs.push(tx.clone());
+            }
+        }
+
+        Transactions {
+            txs,
+            currency: self.currency.clone(),
+        }
+    }
+
+    fn last_month(&self) -> Transactions<T> {
+        let last_week = Utc::now() - TimeDelta::days(30);
+        let mut txs = Vec::new();
+
+        for tx in &self.txs {
+            if tx.date >= last_week {
+                txs.push(tx.clone());
+            }
+        }
+
+        Transactions {
+            txs,
+            currency: self.currency.clone(),
+        }
+    }
+
+    fn last_year(&self) -> Transactions<T> {
+        let last_week = Utc::now() - TimeDelta::days(365);
+        let mut txs = Vec::new();
+
+        for tx in &self.txs {
+            if tx.date >= last_week {
+                txs.push(tx.clone());
+            }
+        }
+
+        Transactions {
+            txs,
+            currency: self.currency.clone(),
+        }
+    }
+}
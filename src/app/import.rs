@@ -0,0 +1,117 @@
+use iced::{
+    Alignment,
+    widget::{Checkbox, Column, Scrollable, button, column, row, text_input},
+};
+use rust_decimal::Decimal;
+
+use crate::{
+    app::{self, COLUMN_SPACING, PADDING, ROW_SPACING, button_cell, text_cell, text_cell_red},
+    ledger::import::{Import, Row, Source},
+};
+
+impl Row {
+    fn value_is_valid(&self) -> bool {
+        self.value.trim().parse::<Decimal>().is_ok()
+    }
+
+    fn flags(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+        if let Some(decision) = &self.decision {
+            if decision.merged {
+                flags.push("merged before".to_string());
+            } else {
+                flags.push("imported before".to_string());
+            }
+        } else if let Some(tx) = &self.matched {
+            flags.push(format!(
+                "probable duplicate of {} {} \"{}\"",
+                tx.date.format("%Y-%m-%d"),
+                tx.amount,
+                tx.comment
+            ));
+        } else if self.duplicate {
+            flags.push("probable duplicate".to_string());
+        }
+        if self.backdated {
+            flags.push("backdated".to_string());
+        }
+        if !self.value_is_valid() {
+            flags.push("not a number".to_string());
+        }
+        flags
+    }
+}
+
+impl Import {
+    fn can_submit(&self) -> bool {
+        self.rows
+            .iter()
+            .filter(|row| row.checked)
+            .all(Row::value_is_valid)
+    }
+
+    pub(crate) fn update(&mut self, message: Message) {
+        match message {
+            Message::Check(i, checked) => self.rows[i].checked = checked,
+            Message::ChangeComment(i, comment) => self.rows[i].tx.comment = comment,
+            Message::ChangeValue(i, value) => self.rows[i].value = value,
+            Message::Cancel | Message::Submit => {}
+        }
+    }
+
+    #[rustfmt::skip]
+    pub(crate) fn view(&self) -> Scrollable<'_, app::Message> {
+        let value_name = match self.source {
+            Source::Boa(_) => "Amount",
+            Source::Investor360 => "Quantity",
+        };
+
+        let mut col_0 = column![text_cell(""), text_cell("---")].spacing(COLUMN_SPACING);
+        let mut col_1 = column![text_cell("Account"), text_cell("---")];
+        let mut col_2 = column![text_cell("Date"), text_cell("---")];
+        let mut col_3 = column![text_cell(value_name), text_cell("---")].align_x(Alignment::End);
+        let mut col_4 = column![text_cell("Comment"), text_cell("---")];
+        let mut col_5 = column![text_cell("Flags"), text_cell("---")];
+
+        for (i, row) in self.rows.iter().enumerate() {
+            let flags = row.flags();
+
+            col_0 = col_0.push(Checkbox::new(row.checked).on_toggle(move |b| app::Message::Import(Message::Check(i, b))).size(35));
+            col_1 = col_1.push(text_cell(&row.account));
+            col_2 = col_2.push(text_cell(row.tx.date.format("%Y-%m-%d").to_string()));
+            col_3 = col_3.push(row![text_input(value_name, &row.value)
+                .on_input(move |string| app::Message::Import(Message::ChangeValue(i, string)))]
+                .padding(PADDING));
+            col_4 = col_4.push(row![text_input("Comment", &row.tx.comment)
+                .on_input(move |string| app::Message::Import(Message::ChangeComment(i, string)))
+                .on_paste(move |string| app::Message::Import(Message::ChangeComment(i, string)))]
+                .padding(PADDING));
+            col_5 = col_5.push(text_cell_red(flags.join(", ")));
+        }
+
+        let mut submit = button("Import");
+        if self.can_submit() {
+            submit = submit.on_press(app::Message::Import(Message::Submit));
+        }
+
+        let checked = self.rows.iter().filter(|row| row.checked).count();
+        let col = Column::new()
+            .push(text_cell(format!("{checked} of {} rows will be imported.", self.rows.len())))
+            .push(row![col_0, col_1, col_2, col_3, col_4, col_5].spacing(ROW_SPACING))
+            .push(row![
+                button_cell(submit),
+                button_cell(button("Cancel").on_press(app::Message::Import(Message::Cancel))),
+            ].spacing(ROW_SPACING));
+
+        Scrollable::new(col)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Message {
+    Check(usize, bool),
+    ChangeComment(usize, String),
+    ChangeValue(usize, String),
+    Cancel,
+    Submit,
+}
@@ -0,0 +1,364 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use super::account::ToSubmit;
+use crate::ledger::{
+    backups,
+    encryption::{self, Encryption},
+    history::Command,
+    import::Import,
+};
+
+/// A line of the journal.
+#[derive(Debug, Deserialize, Serialize)]
+pub enum Entry {
+    /// A change that was made to the ledger.
+    Apply(Command),
+    /// A change that was undone.
+    Undo(Command),
+    /// What was being typed or reviewed and isn't in the ledger yet.
+    Draft(Draft),
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Draft {
+    /// The transaction forms that have something in them, by account name.
+    pub forms: Vec<(String, ToSubmit)>,
+    /// An import that is waiting for review.
+    pub import: Option<Import>,
+}
+
+impl Draft {
+    pub const fn is_empty(&self) -> bool {
+        self.forms.is_empty() && self.import.is_none()
+    }
+}
+
+/// What a session that didn't exit cleanly left in its journal.
+#[derive(Debug)]
+pub struct Recovered {
+    /// The changes that weren't saved, `Apply` or `Undo`, oldest first.
+    pub changes: Vec<Entry>,
+    pub draft: Draft,
+}
+
+impl Recovered {
+    /// Describes what was recovered, one line per kind of thing.
+    pub fn summary(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if !self.changes.is_empty() {
+            lines.push(format!(
+                "{} unsaved change(s) to the ledger",
+                self.changes.len()
+            ));
+        }
+        for (name, _) in &self.draft.forms {
+            lines.push(format!("an unfinished transaction on {name}"));
+        }
+        if let Some(import) = &self.draft.import {
+            lines.push(format!(
+                "an import of {} row(s) waiting for review",
+                import.rows.len()
+            ));
+        }
+        lines
+    }
+}
+
+/// A write-ahead journal kept next to the ledger, e.g. `.ledger.ron.journal`.
+///
+/// Every change is written to it before the ledger is saved, and dropped from
+/// it once the save succeeds. Drafts are written to it every few seconds. The
+/// journal is removed when the program exits with everything saved, so finding
+/// one when the ledger is loaded means the last session didn't.
+///
+/// The journal of an encrypted ledger is encrypted with the same key, and as it
+/// can't be appended to then, it is written whole each time.
+#[derive(Debug)]
+pub struct Journal {
+    ledger: PathBuf,
+    path: PathBuf,
+    /// The changes that haven't been saved to the ledger yet.
+    pending: Vec<String>,
+    /// The draft as it was last written.
+    draft: Option<String>,
+    encryption: Option<Encryption>,
+}
+
+impl Journal {
+    /// Starts the journal for the ledger. What a session that didn't exit
+    /// cleanly left in it is returned, and stays in the journal until it is
+    /// saved or discarded. A last line that was only partly written is ignored.
+    pub fn open(
+        ledger: &Path,
+        encryption: Option<Encryption>,
+    ) -> anyhow::Result<(Self, Option<Recovered>)> {
+        let mut journal = Self {
+            ledger: ledger.to_path_buf(),
+            path: path(ledger)?,
+            pending: Vec::new(),
+            draft: None,
+            encryption,
+        };
+
+        let bytes = match fs::read(&journal.path) {
+            Ok(bytes) => bytes,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok((journal, None)),
+            Err(error) => return Err(error.into()),
+        };
+        let was_encrypted = encryption::is_encrypted(&bytes);
+        let contents = if was_encrypted {
+            let encryption = journal.encryption.as_ref().with_context(|| {
+                format!(
+                    "{} is encrypted, but the ledger isn't.",
+                    journal.path.display()
+                )
+            })?;
+            String::from_utf8(encryption.decrypt_again(&bytes)?)?
+        } else {
+            String::from_utf8(bytes)?
+        };
+
+        let mut changes = Vec::new();
+        let mut draft = Draft::default();
+        let mut draft_line = None;
+        let lines: Vec<_> = contents.lines().collect();
+        for (i, line) in lines.iter().enumerate() {
+            let entry = match ron::from_str(line) {
+                Ok(entry) => entry,
+                Err(_) if i + 1 == lines.len() => break,
+                Err(error) => {
+                    return Err(error).with_context(|| {
+                        format!("{}: line {} is damaged", journal.path.display(), i + 1)
+                    });
+                }
+            };
+            match entry {
+                Entry::Draft(entry) => {
+                    draft = entry;
+                    draft_line = Some((*line).to_string());
+                }
+                entry => {
+                    journal.pending.push((*line).to_string());
+                    changes.push(entry);
+                }
+            }
+        }
+
+        if changes.is_empty() && draft.is_empty() {
+            remove(&journal.path)?;
+            return Ok((journal, None));
+        }
+        // Keep it as the ledger is now, e.g. encrypted since it was written.
+        if journal.encryption.is_some() != was_encrypted {
+            journal.draft = draft_line;
+            journal.rewrite()?;
+        }
+        Ok((journal, Some(Recovered { changes, draft })))
+    }
+
+    /// The ledger the journal belongs to.
+    pub fn ledger(&self) -> &Path {
+        &self.ledger
+    }
+
+    /// Appends a change and flushes it to disk.
+    pub fn append(&mut self, entry: &Entry) -> anyhow::Result<()> {
+        let line = ron::to_string(entry)?;
+        // A new journal is written whole, so it gets the ledger's permissions.
+        if self.encryption.is_some() || !self.path.exists() {
+            self.pending.push(line);
+            return self.rewrite();
+        }
+
+        let mut file = OpenOptions::new().append(true).open(&self.path)?;
+        file.write_all(line.as_bytes())?;
+        file.write_all(b"\n")?;
+        file.sync_data()?;
+
+        self.pending.push(line);
+        Ok(())
+    }
+
+    /// Writes the journal again encrypted as the ledger now is.
+    pub fn set_encryption(&mut self, encryption: Option<Encryption>) -> anyhow::Result<()> {
+        self.encryption = encryption;
+        self.rewrite()
+    }
+
+    /// The changes that haven't been saved to the ledger yet.
+    pub fn pending(&self) -> anyhow::Result<Vec<Entry>> {
+        let mut entries = Vec::new();
+        for line in &self.pending {
+            entries.push(ron::from_str(line)?);
+        }
+        Ok(entries)
+    }
+
+    /// Forgets the changes, they are in the ledger now.
+    pub fn saved(&mut self) -> anyhow::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        self.pending.clear();
+        self.rewrite()
+    }
+
+    /// Forgets the changes and the draft that were recovered.
+    pub fn discard(&mut self) -> anyhow::Result<()> {
+        self.pending.clear();
+        self.draft = None;
+        self.rewrite()
+    }
+
+    /// Writes the draft if it changed since the last time.
+    pub fn write_draft(&mut self, draft: Draft) -> anyhow::Result<()> {
+        let draft = if draft.is_empty() {
+            None
+        } else {
+            Some(ron::to_string(&Entry::Draft(draft))?)
+        };
+
+        if draft != self.draft {
+            self.draft = draft;
+            self.rewrite()?;
+        }
+        Ok(())
+    }
+
+    fn rewrite(&self) -> anyhow::Result<()> {
+        if self.pending.is_empty() && self.draft.is_none() {
+            return remove(&self.path);
+        }
+
+        let mut contents = String::new();
+        for line in self.pending.iter().chain(&self.draft) {
+            contents.push_str(line);
+            contents.push('\n');
+        }
+
+        let mut bytes = contents.into_bytes();
+        if let Some(encryption) = &self.encryption {
+            bytes = encryption.encrypt(&bytes)?;
+        }
+
+        let mut name = backups::file_name(&self.path)?;
+        name.push(".tmp");
+        let tmp_path = self.path.with_file_name(name);
+        let mut file = fs::File::create(&tmp_path)?;
+        // Only who can read the ledger can read what is in it, as for saves.
+        let result = backups::permissions(&self.ledger)
+            .and_then(|permissions| {
+                permissions.map_or(Ok(()), |permissions| file.set_permissions(permissions))
+            })
+            .and_then(|()| file.write_all(&bytes))
+            .and_then(|()| file.sync_data())
+            .and_then(|()| fs::rename(&tmp_path, &self.path));
+        if let Err(error) = result {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(error.into());
+        }
+        Ok(())
+    }
+
+    /// Removes the journal, unless there are changes that never made it into
+    /// the ledger, which are then offered the next time it is loaded.
+    pub fn close(self) -> anyhow::Result<()> {
+        if self.pending.is_empty() {
+            remove(&self.path)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn path(ledger: &Path) -> anyhow::Result<PathBuf> {
+    let mut name = std::ffi::OsString::from(".");
+    name.push(backups::file_name(ledger)?);
+    name.push(".journal");
+    Ok(ledger.with_file_name(name))
+}
+
+fn remove(path: &Path) -> anyhow::Result<()> {
+    match fs::remove_file(path) {
+        Err(error) if error.kind() != io::ErrorKind::NotFound => Err(error.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::{Group, testing};
+
+    fn change() -> Entry {
+        Entry::Apply(Command::AddGroup(Group {
+            name: "Secret savings".to_string(),
+            members: Vec::new(),
+            source: None,
+        }))
+    }
+
+    fn ledger(test: &str) -> PathBuf {
+        let ledger = testing::directory(test).join("ledger.ron");
+        fs::write(&ledger, "").unwrap();
+        ledger
+    }
+
+    #[test]
+    fn the_journal_of_an_encrypted_ledger_is_encrypted() {
+        let ledger = ledger("journal-encrypted");
+        let encryption = Encryption::new("passphrase".to_string()).unwrap();
+        let (mut journal, _) = Journal::open(&ledger, Some(encryption.clone())).unwrap();
+        journal.append(&change()).unwrap();
+        journal.append(&change()).unwrap();
+        journal.close().unwrap();
+
+        let bytes = fs::read(path(&ledger).unwrap()).unwrap();
+        assert!(encryption::is_encrypted(&bytes));
+        assert!(!String::from_utf8_lossy(&bytes).contains("Secret"));
+
+        let (_, recovered) = Journal::open(&ledger, Some(encryption)).unwrap();
+        assert_eq!(recovered.unwrap().changes.len(), 2);
+        assert!(Journal::open(&ledger, None).is_err());
+    }
+
+    #[test]
+    fn encrypting_the_ledger_encrypts_its_journal() {
+        let ledger = ledger("journal-encrypted-since");
+        let (mut journal, _) = Journal::open(&ledger, None).unwrap();
+        journal.append(&change()).unwrap();
+        journal.close().unwrap();
+
+        let encryption = Encryption::new("passphrase".to_string()).unwrap();
+        let (_, recovered) = Journal::open(&ledger, Some(encryption)).unwrap();
+        assert_eq!(recovered.unwrap().changes.len(), 1);
+        let bytes = fs::read(path(&ledger).unwrap()).unwrap();
+        assert!(encryption::is_encrypted(&bytes));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn the_journal_keeps_the_permissions_of_the_ledger() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let ledger = ledger("journal-permissions");
+        fs::set_permissions(&ledger, fs::Permissions::from_mode(0o600)).unwrap();
+        let (mut journal, _) = Journal::open(&ledger, None).unwrap();
+        journal.append(&change()).unwrap();
+        journal.append(&change()).unwrap();
+        journal.write_draft(Draft::default()).unwrap();
+
+        let mode = fs::metadata(path(&ledger).unwrap())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
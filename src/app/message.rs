@@ -1,48 +1,131 @@
-use super::{
-    account,
-    money::{Currency, Fiat},
-};
+use std::path::PathBuf;
+
+use super::{account, import};
+use crate::ledger::{Currency, Fiat};
 
 #[derive(Clone, Debug)]
 pub enum Message {
+    AcceptRecovered,
     AddCrypto,
     AddFiat,
     AddGroup,
     AddMetal,
+    AddScript,
     AddStockPlus,
     Account(account::Message),
+    Autosave,
     Back,
+    CancelOpen,
+    CheckFile,
     ChartWeek,
     ChartMonth,
     ChartYear,
     ChartAll,
     ChangeAccountName(String),
+    ChangePassphrase(String),
+    ChangePassphraseConfirm(String),
     ChangeProjectMonths(String),
+    ChangeSearch(String),
     Checkbox((usize, bool)),
     Configuration,
+    Decrypt,
+    DiscardRecovered,
     Delete(usize),
     DeleteGroup(usize),
+    Encrypt,
+    Export,
     FileLoad,
+    FileNew,
     FileSaveAs,
     GetPrice(usize),
     GetPriceAll,
+    Import(import::Message),
+    KeepMine,
+    MergeFile,
     ImportBoa(usize),
     ImportInvestor360,
+    ImportJournal,
+    IncludeFile,
+    IncludeReadOnly(bool),
     OpenBoaUrl,
+    OpenLatestBackup,
+    OpenReadOnly,
     OpenInvestor360Url,
+    ReloadFile,
+    ReloadReadWrite,
+    RemoveInclude(usize),
+    RemoveScript(usize),
+    RestoreBackup(PathBuf),
+    Redo,
+    RunScript(usize),
+    RunScripts,
+    Undo,
     UpdateAccountName(usize),
+    UpdateBackups(String),
     UpdateCurrency(Currency),
     UpdateCryptoCurrency(Fiat),
     UpdateCryptoDescription(String),
     UpdateCryptoSymbol(String),
     UpdateFiat(Fiat),
+    UpdateMatcherDays(String),
+    UpdateMatcherSimilarity(String),
     UpdateMetalCurrency(Fiat),
     UpdateMetalDescription(String),
     UpdateMetalSymbol(String),
     UpdateStockPlusDescription(String),
     UpdateStockPlusSymbol(String),
+    Search,
     SelectAccount(usize),
     SelectAccountSecondary(usize),
+    SetBackups,
+    SetMatcher,
     SubmitAccount,
+    SubmitPassphrase,
+    SubmitSearch,
     Exit,
 }
+
+impl Message {
+    /// Whether the message changes the ledger, which a ledger that is open
+    /// read-only refuses.
+    pub const fn mutates(&self) -> bool {
+        match self {
+            Self::Account(message) => matches!(
+                message,
+                account::Message::SubmitBalance
+                    | account::Message::SubmitEdit
+                    | account::Message::SubmitTx
+            ),
+            Self::Import(message) => matches!(message, import::Message::Submit),
+            Self::AcceptRecovered
+            | Self::AddCrypto
+            | Self::AddFiat
+            | Self::AddGroup
+            | Self::AddMetal
+            | Self::AddScript
+            | Self::AddStockPlus
+            | Self::Decrypt
+            | Self::Delete(_)
+            | Self::DeleteGroup(_)
+            | Self::Encrypt
+            | Self::GetPrice(_)
+            | Self::GetPriceAll
+            | Self::ImportBoa(_)
+            | Self::ImportInvestor360
+            | Self::ImportJournal
+            | Self::IncludeFile
+            | Self::Redo
+            | Self::RemoveInclude(_)
+            | Self::RemoveScript(_)
+            | Self::RestoreBackup(_)
+            | Self::RunScript(_)
+            | Self::RunScripts
+            | Self::SetBackups
+            | Self::SetMatcher
+            | Self::SubmitAccount
+            | Self::Undo
+            | Self::UpdateAccountName(_) => true,
+            _ => false,
+        }
+    }
+}
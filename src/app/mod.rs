@@ -1,26 +1,28 @@
-mod account;
-mod accounts;
+pub(crate) mod account;
 mod chart;
-pub mod command_line;
-mod crypto;
-mod import_boa;
+mod import;
+mod journal;
 mod message;
-mod metal;
-mod money;
 mod screen;
+mod scripts;
+mod search;
 pub mod solarized;
-mod stocks;
-
-use std::{cmp::Ordering, fs, mem::take, path::PathBuf, str::FromStr, sync::Arc};
+mod startup;
+
+use std::{
+    cmp::Ordering,
+    fs,
+    mem::take,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use account::{transaction::Transaction, transactions::Transactions};
-use accounts::Group;
 use anyhow::Context;
 use chart::Chart;
-use chrono::Utc;
-use crypto::Crypto;
 use iced::{
-    Alignment, Element, Length, Pixels, Task, Theme,
+    Alignment, Element, Font, Length, Pixels, Subscription, Task, Theme,
+    keyboard::{self, Key},
+    time,
     widget::{
         self, Button, Checkbox, Column, ProgressBar, Row, Scrollable, button, column,
         combo_box::{ComboBox, State},
@@ -28,23 +30,41 @@ use iced::{
         text::IntoFragment,
         text_input,
     },
+    window,
 };
-use metal::Metal;
-use money::{Currency, Fiat};
+use journal::{Draft, Entry, Journal, Recovered};
 use plotters_iced2::ChartWidget;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
-use serde::Deserialize;
-use stocks::StockPlus;
+use startup::StartupError;
 use thousands::Separable;
 
-use crate::app::{account::Account, accounts::Accounts, message::Message, screen::Screen};
+use crate::{
+    app::{message::Message, screen::Screen},
+    command_line,
+    ledger::{
+        Account, Accounts, Changed, Crypto, Currency, Event, Export, Fiat, File, Found, Group,
+        Include, Locked, Metal, NeedsPassphrase, Report, StockPlus, Transaction, backups,
+        duplicates::Matcher,
+        encryption::Encryption,
+        history::{Command, History, TransactionChange},
+        import::{Import, Source},
+        import_boa::import_boa,
+        import_investor_360::import_investor_360,
+        import_journal::import_journal,
+        lock, report, sqlite,
+    },
+};
 
 const BOA_URL: &str = "https://secure.bankofamerica.com/myaccounts/brain/redirect.go?target=portfolio&portfolio_page=transactions&request_locale=en-us&source=overview&fsd=y";
 const INVESTOR_360_URL: &str = "https://my.investor360.com/nce/Holdings";
 
+/// How often the drafts are written to the journal.
+const AUTOSAVE: std::time::Duration = std::time::Duration::from_secs(5);
+/// How often the file is checked for changes by other programs, and for being
+/// let go when it is open read-only.
+const CHECK_FILE: std::time::Duration = std::time::Duration::from_secs(2);
 const TITLE_FILE_PICKER: &str = "Financial Accounts";
-const LAST_DATE_SCALE: u32 = 4;
 const EDGE_PADDING: usize = 4;
 const PADDING: u16 = 2;
 const COLUMN_SPACING: f32 = 0.5;
@@ -57,16 +77,21 @@ pub struct App {
     accounts: Accounts,
     file: Option<File>,
     account_name: String,
+    backups: Vec<PathBuf>,
+    backups_count: String,
     crypto_currency: Option<Fiat>,
     crypto_currency_selector: State<Fiat>,
     crypto_description: String,
     crypto_symbol: String,
+    include_read_only: bool,
     fiat: Option<Fiat>,
     fiat_selector: State<Fiat>,
     metal_currency: Option<Fiat>,
     metal_currency_selector: State<Fiat>,
     metal_description: String,
     metal_symbol: String,
+    matcher_days: String,
+    matcher_similarity: String,
     progress_bar: Option<f32>,
     stock_plus_description: String,
     stock_plus_symbol: String,
@@ -75,69 +100,99 @@ pub struct App {
     duration: Duration,
     project_months: Option<u16>,
     screen: Screen,
+    import: Option<Import>,
+    history: History,
+    opening: Option<PathBuf>,
+    /// The file whose latest backup is being opened, when the passphrase is
+    /// asked for that.
+    restoring: Option<PathBuf>,
+    passphrase: String,
+    passphrase_confirm: String,
+    journal: Option<Journal>,
+    /// The file is open read-only and the other instance has let it go.
+    lock_released: bool,
+    /// Another program changed the file since it was loaded or saved.
+    changed_on_disk: bool,
+    recovered: Option<Recovered>,
+    startup_error: Option<StartupError>,
     errors: Option<Arc<Vec<anyhow::Error>>>,
+    /// The query in the search box.
+    search: String,
+    found: Vec<Found>,
+    /// What the scripts printed the last time they ran.
+    script_output: Vec<String>,
 }
 
 impl App {
     fn add_crypto(&mut self) {
         if let Some(fiat) = &self.crypto_currency {
-            self.accounts.crypto.push(Crypto {
+            let crypto = Crypto {
                 currency: fiat.clone(),
                 description: self.crypto_description.clone(),
                 symbol: self.crypto_symbol.clone(),
-            });
-            self.currency_selector = State::new(self.accounts.get_currencies());
-            self.save();
+            };
+            self.accounts.crypto.push(crypto.clone());
+            self.record(Command::AddCrypto(crypto));
         }
     }
 
     fn add_fiat(&mut self) {
-        if let Some(fiat) = &self.fiat {
+        if let Some(fiat) = self.fiat.clone() {
             self.accounts.fiats.push(fiat.clone());
-            self.fiat_selector = State::new(Fiat::all_minus_existing(&self.accounts.fiats));
-            self.currency_selector = State::new(self.accounts.get_currencies());
-            self.save();
+            self.record(Command::AddFiat(fiat));
         }
     }
 
     fn add_group(&mut self) {
-        let members = (0..)
+        let members: Vec<usize> = (0..)
             .zip(self.accounts.inner.iter())
-            .filter_map(
-                |(index, account)| {
-                    if account.check_box { Some(index) } else { None }
-                },
-            )
+            .filter_map(|(index, account)| {
+                if account.view.check_box {
+                    Some(index)
+                } else {
+                    None
+                }
+            })
             .collect();
 
+        // A group is saved in the file its accounts are in.
+        let source = members.first().and_then(|i| self.accounts[*i].source);
+        if members.iter().any(|i| self.accounts[*i].source != source) {
+            self.display_error(anyhow::Error::msg(
+                "The accounts of a group have to be in the same file.",
+            ));
+            return;
+        }
+
         let group = Group {
             name: self.account_name.clone(),
             members,
+            source,
         };
 
-        self.accounts.groups.push(group);
-        self.save();
+        self.accounts.groups.push(group.clone());
+        self.record(Command::AddGroup(group));
     }
 
     fn add_metal(&mut self) {
         if let Some(fiat) = &self.metal_currency {
-            self.accounts.metals.push(Metal {
+            let metal = Metal {
                 currency: fiat.clone(),
                 description: self.metal_description.clone(),
                 symbol: self.metal_symbol.clone(),
-            });
-            self.currency_selector = State::new(self.accounts.get_currencies());
-            self.save();
+            };
+            self.accounts.metals.push(metal.clone());
+            self.record(Command::AddMetal(metal));
         }
     }
 
     fn add_stock_plus(&mut self) {
-        self.accounts.stocks_plus.push(StockPlus {
+        let stock_plus = StockPlus {
             description: self.stock_plus_description.clone(),
             symbol: self.stock_plus_symbol.clone(),
-        });
-        self.currency_selector = State::new(self.accounts.get_currencies());
-        self.save();
+        };
+        self.accounts.stocks_plus.push(stock_plus.clone());
+        self.record(Command::AddStockPlus(stock_plus));
     }
 
     fn config(&self) -> Scrollable<'_, Message> {
@@ -201,23 +256,973 @@ impl App {
                 .on_paste(Message::UpdateMetalSymbol),
         ];
 
-        let mut stock_plus_current = Column::new();
-        for stock_plus in &self.accounts.stocks_plus {
-            let stock_plus = format!("{stock_plus:?}");
-            stock_plus_current = stock_plus_current.push(text_cell(stock_plus));
+        let mut stock_plus_current = Column::new();
+        for stock_plus in &self.accounts.stocks_plus {
+            let stock_plus = format!("{stock_plus:?}");
+            stock_plus_current = stock_plus_current.push(text_cell(stock_plus));
+        }
+
+        let add_stock_plus = row![
+            button_cell(button("Add Stock Plus").on_press(Message::AddStockPlus)),
+            text_cell("Description:"),
+            text_input("Description", &self.stock_plus_description)
+                .on_input(Message::UpdateStockPlusDescription)
+                .on_paste(Message::UpdateStockPlusDescription),
+            text_cell("Symbol:"),
+            text_input("Symbol", &self.stock_plus_symbol)
+                .on_input(Message::UpdateStockPlusSymbol)
+                .on_paste(Message::UpdateStockPlusSymbol),
+        ];
+
+        let mut column_errors = Column::new();
+        if let Some(errors) = &self.errors {
+            for error in errors.iter() {
+                column_errors = column_errors.push(text_cell_red(error.to_string()));
+            }
+        }
+
+        let cols = column![
+            crypto_current,
+            add_crypto,
+            fiats_current,
+            add_fiat,
+            metals_current,
+            add_metal,
+            stock_plus_current,
+            add_stock_plus,
+            self.config_duplicates(),
+            self.config_backups(),
+            self.config_includes(),
+            self.config_scripts(),
+            self.config_encryption(),
+            column_errors,
+            button_cell(button("Back").on_press(Message::Back)),
+        ];
+
+        Scrollable::new(cols)
+    }
+
+    fn config_duplicates(&self) -> Column<'_, Message> {
+        let matcher = &self.accounts.duplicate_matcher;
+        let duplicates_current = text_cell(format!(
+            "Duplicates: same amount within {} days, comment similarity at least {}",
+            matcher.days, matcher.similarity
+        ));
+
+        let set_duplicates = row![
+            button_cell(button("Set Duplicates").on_press(Message::SetMatcher)),
+            text_cell("Days:"),
+            text_input("Days", &self.matcher_days)
+                .on_input(Message::UpdateMatcherDays)
+                .on_paste(Message::UpdateMatcherDays),
+            text_cell("Similarity (0 to 1):"),
+            text_input("Similarity", &self.matcher_similarity)
+                .on_input(Message::UpdateMatcherSimilarity)
+                .on_paste(Message::UpdateMatcherSimilarity),
+        ];
+
+        column![duplicates_current, set_duplicates]
+    }
+
+    fn config_backups(&self) -> Column<'_, Message> {
+        let backups_current = text_cell(format!(
+            "Backups: keeping the newest {} next to the file",
+            self.accounts.backups
+        ));
+
+        let set_backups = row![
+            button_cell(button("Set Backups").on_press(Message::SetBackups)),
+            text_cell("Count:"),
+            text_input("Count", &self.backups_count)
+                .on_input(Message::UpdateBackups)
+                .on_paste(Message::UpdateBackups),
+        ];
+
+        let mut backups = Column::new();
+        for backup in &self.backups {
+            let name = backup
+                .file_name()
+                .map_or_else(String::new, |name| name.to_string_lossy().to_string());
+
+            backups = backups.push(row![
+                button_cell(button("Restore").on_press(Message::RestoreBackup(backup.clone()))),
+                text_cell(name),
+            ]);
+        }
+
+        column![backups_current, set_backups, backups]
+    }
+
+    #[rustfmt::skip]
+    fn config_includes(&self) -> Column<'_, Message> {
+        let mut includes = column![text_cell("Included files:")];
+        for (i, include) in self.accounts.includes.iter().enumerate() {
+            let mut name = include.path.display().to_string();
+            if include.read_only {
+                name.push_str(" (read-only)");
+            }
+
+            includes = includes.push(row![
+                button_cell(button("Remove").on_press(Message::RemoveInclude(i))),
+                text_cell(name),
+            ]);
+        }
+
+        let include = row![
+            button_cell(button("Include File").on_press(Message::IncludeFile)),
+            Checkbox::new(self.include_read_only).label("Read-only").on_toggle(Message::IncludeReadOnly).size(35),
+        ];
+
+        column![includes, include]
+    }
+
+    /// Adds a ledger whose accounts are shown and saved along with these.
+    fn include_file(&mut self) {
+        let Some(file) = &self.file else {
+            self.display_error(anyhow::Error::msg(
+                "Save the ledger before including other files in it.",
+            ));
+            return;
+        };
+        let directory = backups::directory(&file.path);
+
+        let result = rfd::FileDialog::new()
+            .set_title(TITLE_FILE_PICKER)
+            .add_filter("ron", &["ron"])
+            .add_filter("sqlite", &sqlite::EXTENSIONS)
+            .pick_file()
+            .context("You must choose a file to include.");
+
+        let file_path = match result {
+            Ok(file_path) => file_path,
+            Err(error) => {
+                self.display_error(error);
+                return;
+            }
+        };
+        if file_path == file.path
+            || self
+                .accounts
+                .includes
+                .iter()
+                .any(|include| include.resolve(&file.path) == file_path)
+        {
+            self.display_error(anyhow::Error::msg(format!(
+                "{} is already in the ledger.",
+                file_path.display()
+            )));
+            return;
+        }
+
+        // Kept relative when it can be, so the files can be moved together.
+        let path = file_path
+            .strip_prefix(directory)
+            .map_or(file_path.clone(), Path::to_path_buf);
+
+        let mut includes = self.accounts.includes.clone();
+        includes.push(Include {
+            path,
+            read_only: self.include_read_only,
+            settings: Accounts::default(),
+        });
+        self.set_includes(includes);
+    }
+
+    fn remove_include(&mut self, i: usize) {
+        let mut includes = self.accounts.includes.clone();
+        if i < includes.len() {
+            includes.remove(i);
+            self.set_includes(includes);
+        }
+    }
+
+    /// Saves everything, then the ledger with the new includes, and loads it
+    /// again.
+    fn set_includes(&mut self, includes: Vec<Include>) {
+        let Some(file) = &mut self.file else {
+            return;
+        };
+
+        let mut part = self.accounts.part(None);
+        part.includes = includes;
+        let result = self.accounts.save(file).and_then(|()| part.save_one(file));
+
+        match result {
+            Ok(()) => {
+                if let Some(journal) = &mut self.journal
+                    && let Err(error) = journal.saved()
+                {
+                    self.display_error(error);
+                }
+                self.reload_file();
+                self.screen = Screen::Configuration;
+            }
+            Err(error) => self.display_error(error),
+        }
+    }
+
+    fn set_backups(&mut self) {
+        match self
+            .backups_count
+            .trim()
+            .parse()
+            .context("The number of backups must be a whole number.")
+        {
+            Ok(after) => {
+                let before = std::mem::replace(&mut self.accounts.backups, after);
+                self.record(Command::SetBackups { before, after });
+            }
+            Err(error) => self.display_error(error),
+        }
+    }
+
+    /// Replaces the ledger with a backup. The file as it was is backed up first,
+    /// so a restore can itself be restored.
+    fn restore_backup(&mut self, backup: &Path) {
+        let encryption = self.file.as_ref().and_then(|file| file.encryption.as_ref());
+        match Accounts::read_backup(backup, encryption.map(Encryption::passphrase)) {
+            Ok((mut accounts, _encryption)) => {
+                // A backup is of this file alone, the included ones stay as
+                // they are.
+                accounts.includes.clone_from(&self.accounts.includes);
+                for i in 0..accounts.includes.len() {
+                    if let Err(error) = accounts.merge(i, self.accounts.part(Some(i))) {
+                        self.display_error(error);
+                        return;
+                    }
+                }
+
+                self.accounts = accounts;
+                self.history.clear();
+                self.refresh();
+                self.save();
+                self.screen = Screen::Accounts;
+            }
+            Err(error) => {
+                self.display_error(error.context(format!("restoring {}", backup.display())));
+            }
+        }
+    }
+
+    fn list_backups(&mut self) {
+        let Some(file) = &self.file else {
+            self.backups = Vec::new();
+            return;
+        };
+
+        match backups::list(&file.path) {
+            Ok(backups) => self.backups = backups,
+            Err(error) => self.display_error(error),
+        }
+    }
+
+    fn set_matcher(&mut self) {
+        let days = self
+            .matcher_days
+            .trim()
+            .parse()
+            .context("Days must be a whole number.");
+        let similarity = self
+            .matcher_similarity
+            .trim()
+            .parse()
+            .ok()
+            .filter(|similarity| (0.0..=1.0).contains(similarity))
+            .context("Similarity must be a number from 0 to 1.");
+
+        match (days, similarity) {
+            (Ok(days), Ok(similarity)) => {
+                let after = Matcher { days, similarity };
+                let before = std::mem::replace(&mut self.accounts.duplicate_matcher, after.clone());
+                self.record(Command::SetMatcher { before, after });
+            }
+            (Err(error), _) | (_, Err(error)) => self.display_error(error),
+        }
+    }
+
+    fn display_error(&mut self, error: anyhow::Error) {
+        match self.errors {
+            Some(ref mut errors) => {
+                let errors = Arc::get_mut(errors).unwrap();
+                errors.push(error);
+            }
+            None => self.errors = Some(Arc::new(vec![error])),
+        }
+    }
+
+    fn import_review(&self) -> Column<'_, Message> {
+        let mut column_errors = Column::new();
+        if let Some(errors) = &self.errors {
+            for error in errors.iter() {
+                column_errors = column_errors.push(text_cell_red(error.to_string()));
+            }
+        }
+
+        match &self.import {
+            Some(import) => column![import.view(), column_errors],
+            None => column![column_errors],
+        }
+    }
+
+    fn load_file(&mut self) {
+        let result = rfd::FileDialog::new()
+            .set_title(TITLE_FILE_PICKER)
+            .add_filter("ron", &["ron"])
+            .add_filter("sqlite", &sqlite::EXTENSIONS)
+            .pick_file()
+            .context("You must choose a file name for your configuration file.");
+
+        match result {
+            Ok(file_path) => self.open_file(file_path, None),
+            Err(error) => self.display_error(error),
+        }
+    }
+
+    /// Loads the file, or asks for its passphrase if it is encrypted, or
+    /// whether to open it read-only if another instance has it open.
+    fn open_file(&mut self, file_path: PathBuf, passphrase: Option<&str>) {
+        match Accounts::load(&mut self.file, file_path.clone(), passphrase) {
+            Ok((accounts, file)) => self.opened(accounts, file),
+            Err(error) if error.is::<NeedsPassphrase>() => {
+                self.opening = Some(file_path);
+                self.screen = Screen::Passphrase;
+            }
+            Err(error) if error.is::<Locked>() => {
+                self.opening = Some(file_path);
+                self.screen = Screen::Locked;
+                // Kept to open it read-only with.
+                self.passphrase = passphrase.unwrap_or_default().to_string();
+                return;
+            }
+            Err(error) => self.display_error(error),
+        }
+        self.passphrase = String::new();
+    }
+
+    /// Creates an empty ledger at a path that is picked.
+    fn new_file(&mut self) {
+        let result = rfd::FileDialog::new()
+            .set_title(TITLE_FILE_PICKER)
+            .add_filter("ron", &["ron"])
+            .add_filter("sqlite", &sqlite::EXTENSIONS)
+            .save_file()
+            .context("You must choose a file name for your configuration file.")
+            .and_then(|file_path| {
+                let accounts = Accounts::new();
+                let file = accounts.save_first(file_path)?;
+                Ok((accounts, file))
+            });
+
+        match result {
+            Ok((accounts, file)) => self.opened(accounts, file),
+            Err(error) => self.display_error(error),
+        }
+    }
+
+    /// Replaces the file that couldn't be opened with its newest backup, or asks
+    /// for its passphrase if it is encrypted. The file as it was is backed up
+    /// first, and the backup is saved with the encryption it had.
+    fn open_latest_backup(&mut self, passphrase: Option<&str>) {
+        let Some(startup_error) = &self.startup_error else {
+            return;
+        };
+        let file_path = startup_error.path.clone();
+
+        let result = backups::list(&file_path).and_then(|backups| {
+            let backup = backups
+                .first()
+                .context("There are no backups of the file.")?;
+            let (accounts, encryption) = Accounts::read_backup(backup, passphrase)
+                .with_context(|| format!("restoring {}", backup.display()))?;
+            let file = accounts.save_encrypted(&mut None, file_path.clone(), encryption)?;
+            Ok((accounts, file))
+        });
+
+        match result {
+            Ok((accounts, file)) => self.opened(accounts, file),
+            Err(error) if error.is::<NeedsPassphrase>() => {
+                self.restoring = Some(file_path);
+                self.screen = Screen::Passphrase;
+            }
+            Err(error) => self.display_error(error),
+        }
+        self.passphrase = String::new();
+    }
+
+    #[rustfmt::skip]
+    fn startup_error_view(&self) -> Column<'_, Message> {
+        let mut column_startup = Column::new();
+        if let Some(startup_error) = &self.startup_error {
+            column_startup = column_startup.push(text_cell(format!("{} couldn't be opened.", startup_error.path.display())));
+            for error in startup_error.error.chain() {
+                column_startup = column_startup.push(text_cell_red(error.to_string()));
+            }
+            if let Some(location) = &startup_error.location {
+                column_startup = column_startup.push(text_cell(format!("Line {}, column {}:", location.line, location.column)));
+                if let Some(text) = &location.text {
+                    let caret = format!("{}^", " ".repeat(location.column.saturating_sub(1)));
+                    column_startup = column_startup.push(column![
+                        widget::text(text.clone()).font(Font::MONOSPACE),
+                        widget::text(caret).font(Font::MONOSPACE),
+                    ].padding(PADDING));
+                }
+            }
+        }
+
+        let mut column_errors = Column::new();
+        if let Some(errors) = &self.errors {
+            for error in errors.iter() {
+                column_errors = column_errors.push(text_cell_red(error.to_string()));
+            }
+        }
+
+        column![
+            column_startup,
+            row![
+                button_cell(button("Load Another File").on_press(Message::FileLoad)),
+                button_cell(button("New Ledger").on_press(Message::FileNew)),
+                button_cell(button("Open Latest Backup").on_press(Message::OpenLatestBackup)),
+            ].spacing(ROW_SPACING),
+            column_errors,
+        ]
+    }
+
+    fn open_read_only(&mut self) {
+        let Some(file_path) = self.opening.clone() else {
+            return;
+        };
+        let passphrase = take(&mut self.passphrase);
+        let passphrase = Some(passphrase.as_str()).filter(|passphrase| !passphrase.is_empty());
+
+        match Accounts::load_read_only(&mut self.file, file_path, passphrase) {
+            Ok((accounts, file)) => self.opened(accounts, file),
+            Err(error) => self.display_error(error),
+        }
+    }
+
+    fn opened(&mut self, accounts: Accounts, file: File) {
+        self.accounts = accounts;
+        self.file = Some(file);
+        self.history.clear();
+        self.refresh();
+        self.list_backups();
+        self.opening = None;
+        self.restoring = None;
+        self.startup_error = None;
+        self.lock_released = false;
+        self.changed_on_disk = false;
+        self.found = Vec::new();
+        self.screen = Screen::Accounts;
+        self.start_journal();
+    }
+
+    fn read_only(&self) -> bool {
+        self.file.as_ref().is_some_and(|file| file.read_only)
+    }
+
+    /// Notices when another program changes the file, and when the other
+    /// instance lets go of a file that is open read-only.
+    fn check_file(&mut self) {
+        // Errors show as a change, reloading then says what is wrong.
+        if let Some(file) = &self.file {
+            self.changed_on_disk |= file
+                .stamp
+                .as_ref()
+                .is_some_and(|stamp| stamp.changed(&file.path).unwrap_or(true));
+            self.lock_released = file.read_only && !lock::is_locked(&file.path).unwrap_or(true);
+        }
+    }
+
+    /// Loads the file that is open again, e.g. read-write once it has been let
+    /// go, or after another program changed it.
+    fn reload_file(&mut self) {
+        if let Some(file) = &self.file {
+            let file_path = file.path.clone();
+            let passphrase = file
+                .encryption
+                .as_ref()
+                .map(|encryption| encryption.passphrase().to_string());
+            self.open_file(file_path, passphrase.as_deref());
+        }
+    }
+
+    /// Loads the file another program changed, dropping the changes made here
+    /// that weren't saved.
+    fn reload_changed(&mut self) {
+        if let Some(journal) = &mut self.journal
+            && let Err(error) = journal.discard()
+        {
+            self.display_error(error);
+        }
+        self.reload_file();
+    }
+
+    /// Loads the file another program changed and makes the changes that
+    /// couldn't be saved here again on top of it.
+    fn merge_changed(&mut self) {
+        let pending = match &self.journal {
+            Some(journal) => journal.pending(),
+            None => Ok(Vec::new()),
+        };
+        let pending = match pending {
+            Ok(pending) => pending,
+            Err(error) => {
+                self.display_error(error.context("reading the journal"));
+                return;
+            }
+        };
+
+        self.reload_file();
+        if self.changed_on_disk {
+            // It couldn't be loaded, the error says why.
+            return;
+        }
+        if !self.replay(&pending) {
+            self.display_error(anyhow::Error::msg(
+                "The changes made here don't fit the file as it is now, so only the file was loaded.",
+            ));
+            if let Some(journal) = &mut self.journal
+                && let Err(error) = journal.discard()
+            {
+                self.display_error(error);
+            }
+        }
+    }
+
+    /// Saves over the file another program changed. The file as it was is
+    /// backed up first.
+    fn keep_mine(&mut self) {
+        if let Some(file) = &mut self.file {
+            file.stamp = None;
+            self.changed_on_disk = false;
+            self.save();
+        }
+    }
+
+    #[rustfmt::skip]
+    fn locked_view(&self) -> Column<'_, Message> {
+        let name = self.opening.as_ref().map_or_else(String::new, |path| path.display().to_string());
+
+        let mut column_errors = Column::new();
+        if let Some(errors) = &self.errors {
+            for error in errors.iter() {
+                column_errors = column_errors.push(text_cell_red(error.to_string()));
+            }
+        }
+
+        column![
+            text_cell(format!("{name} is open in another financial-accounts.")),
+            row![
+                button_cell(button("Open Read-Only").on_press(Message::OpenReadOnly)),
+                button_cell(button("Cancel").on_press(Message::CancelOpen)),
+            ].spacing(ROW_SPACING),
+            column_errors,
+        ]
+    }
+
+    /// Says another program changed the file and offers what to do about it.
+    #[rustfmt::skip]
+    fn changed_banner(&self) -> Row<'_, Message> {
+        let name = self.file.as_ref().map_or_else(String::new, |file| file.path.display().to_string());
+
+        let mut row = row![
+            text_cell(format!("{name} was changed by another program.")),
+            button_cell(button("Reload").on_press(Message::ReloadFile)),
+        ];
+        if !self.read_only() {
+            row = row.extend([
+                button_cell(button("Merge").on_press(Message::MergeFile)).into(),
+                button_cell(button("Keep Mine").on_press(Message::KeepMine)).into(),
+            ]);
+        }
+        row
+    }
+
+    /// Says the file is open read-only, and offers to reload it once the other
+    /// instance has let go of it.
+    #[rustfmt::skip]
+    fn read_only_banner(&self) -> Row<'_, Message> {
+        let name = self.file.as_ref().map_or_else(String::new, |file| file.path.display().to_string());
+
+        if self.lock_released {
+            row![
+                text_cell(format!("Read-only: {name} is no longer open elsewhere.")),
+                button_cell(button("Reload Read-Write").on_press(Message::ReloadReadWrite)),
+            ]
+        } else {
+            row![text_cell(format!(
+                "Read-only: {name} is open in another financial-accounts. Changes are disabled, use Save As to keep a copy."
+            ))]
+        }
+    }
+
+    fn submit_passphrase(&mut self) {
+        let passphrase = take(&mut self.passphrase);
+        if self.restoring.is_some() {
+            self.open_latest_backup(Some(&passphrase));
+        } else if let Some(file_path) = self.opening.clone() {
+            self.open_file(file_path, Some(&passphrase));
+        }
+    }
+
+    fn cancel_open(&mut self) {
+        self.opening = None;
+        self.passphrase = String::new();
+        self.screen = if self.restoring.take().is_some() {
+            Screen::StartupError
+        } else {
+            Screen::Accounts
+        };
+    }
+
+    #[rustfmt::skip]
+    fn passphrase_view(&self) -> Column<'_, Message> {
+        let name = self.opening.as_ref().or(self.restoring.as_ref()).map_or_else(String::new, |path| path.display().to_string());
+
+        let mut column_errors = Column::new();
+        if let Some(errors) = &self.errors {
+            for error in errors.iter() {
+                column_errors = column_errors.push(text_cell_red(error.to_string()));
+            }
+        }
+
+        let mut open = button("Open");
+        if !self.passphrase.is_empty() {
+            open = open.on_press(Message::SubmitPassphrase);
+        }
+
+        column![
+            text_cell(format!("{name} is encrypted.")),
+            row![
+                text_cell("Passphrase:"),
+                text_input("Passphrase", &self.passphrase)
+                    .secure(true)
+                    .on_input(Message::ChangePassphrase)
+                    .on_submit(Message::SubmitPassphrase),
+            ].padding(PADDING).spacing(ROW_SPACING),
+            row![
+                button_cell(open),
+                button_cell(button("Cancel").on_press(Message::CancelOpen)),
+            ].spacing(ROW_SPACING),
+            column_errors,
+        ]
+    }
+
+    #[rustfmt::skip]
+    fn config_encryption(&self) -> Column<'_, Message> {
+        let encrypted = self.file.as_ref().is_some_and(|file| file.encryption.is_some());
+        let status = if encrypted { "Encryption: the file is encrypted" } else { "Encryption: the file is not encrypted" };
+
+        let mut encrypt = button(if encrypted { "Change Passphrase" } else { "Encrypt" });
+        if self.file.is_some() && !self.passphrase.is_empty() && self.passphrase == self.passphrase_confirm {
+            encrypt = encrypt.on_press(Message::Encrypt);
+        }
+
+        let mut decrypt = button("Decrypt");
+        if encrypted {
+            decrypt = decrypt.on_press(Message::Decrypt);
+        }
+
+        column![
+            text_cell(status),
+            row![
+                button_cell(encrypt),
+                button_cell(decrypt),
+                text_cell("Passphrase:"),
+                text_input("Passphrase", &self.passphrase)
+                    .secure(true)
+                    .on_input(Message::ChangePassphrase),
+                text_cell("Again:"),
+                text_input("Passphrase", &self.passphrase_confirm)
+                    .secure(true)
+                    .on_input(Message::ChangePassphraseConfirm),
+            ],
+        ]
+    }
+
+    /// Encrypts the file, and any backups that aren't encrypted yet, with the
+    /// passphrase that was entered.
+    fn encrypt(&mut self) {
+        let passphrase = take(&mut self.passphrase);
+        self.passphrase_confirm = String::new();
+
+        let result = self
+            .file
+            .as_mut()
+            .context("There is no file to encrypt!")
+            .and_then(|file| file.encrypt(passphrase));
+
+        match result {
+            Ok(()) => {
+                self.start_journal();
+                self.save();
+            }
+            Err(error) => self.display_error(error),
+        }
+    }
+
+    /// Saves the file as plain text again. Backups stay as they are.
+    fn decrypt(&mut self) {
+        if let Some(file) = &mut self.file {
+            file.encryption = None;
+            self.start_journal();
+            self.save();
+        }
+    }
+
+    fn open_url(&mut self, url: &str) {
+        if let Err(error) = webbrowser::open(url) {
+            self.display_error(error.into());
+        }
+    }
+
+    fn save_file(&mut self) {
+        let result = rfd::FileDialog::new()
+            .set_title(TITLE_FILE_PICKER)
+            .add_filter("ron", &["ron"])
+            .add_filter("sqlite", &sqlite::EXTENSIONS)
+            .save_file()
+            .context("You must choose a file name for your configuration file.");
+
+        match result {
+            Ok(file_path) => match self.accounts.save_dialogue(&mut self.file, file_path) {
+                Ok(file) => {
+                    self.file = Some(file);
+                    self.list_backups();
+                    self.start_journal();
+                    // The paths to the included files may have changed.
+                    if !self.accounts.includes.is_empty() {
+                        self.reload_file();
+                    }
+                }
+                Err(error) => self.display_error(error),
+            },
+            Err(error) => self.display_error(error),
+        }
+    }
+
+    /// Writes the ledger as a plain-text accounting journal, in the format
+    /// chosen by the extension.
+    fn export_file(&mut self) {
+        let result = rfd::FileDialog::new()
+            .set_title(TITLE_FILE_PICKER)
+            .add_filter("ledger", &["journal", "ledger", "hledger"])
+            .add_filter("beancount", &["beancount", "bean"])
+            .save_file()
+            .context("You must choose a file name to export to.")
+            .and_then(|file_path| {
+                let format = Export::from_path(&file_path).with_context(|| {
+                    format!(
+                        "{} needs a .journal, .ledger or .beancount extension.",
+                        file_path.display()
+                    )
+                })?;
+                fs::write(&file_path, format.render(&self.accounts))
+                    .with_context(|| format!("writing {}", file_path.display()))
+            });
+
+        if let Err(error) = result {
+            self.display_error(error);
+        }
+    }
+
+    fn save(&mut self) {
+        let Some(file) = &mut self.file else {
+            self.display_error(anyhow::Error::msg("Cannot save because file is None!"));
+            return;
+        };
+
+        match self.accounts.save(file) {
+            Ok(()) => {
+                self.list_backups();
+                if let Some(journal) = &mut self.journal
+                    && let Err(error) = journal.saved()
+                {
+                    self.display_error(error);
+                }
+            }
+            Err(error) => {
+                self.changed_on_disk |= error.is::<Changed>();
+                self.display_error(error);
+            }
+        }
+    }
+
+    /// Remembers a change that was just made to the ledger and saves it.
+    fn record(&mut self, command: Command) {
+        if command.touches_read_only(&self.accounts) {
+            command.undo(&mut self.accounts);
+            self.display_error(anyhow::Error::msg(
+                "That is in a file that is included read-only, so it can't be changed.",
+            ));
+            return;
+        }
+
+        self.journal(&Entry::Apply(command.clone()));
+        self.history.record(command);
+        self.refresh();
+        self.save();
+    }
+
+    /// Writes a change to the journal before it is saved.
+    fn journal(&mut self, entry: &Entry) {
+        if let Some(journal) = &mut self.journal
+            && let Err(error) = journal.append(entry)
+        {
+            self.display_error(error.context("writing the journal"));
+        }
+    }
+
+    /// Starts a journal for the file that is open, if it isn't the one being
+    /// kept already, and offers what a session that crashed left in it.
+    fn start_journal(&mut self) {
+        // The journal of a file that is open read-only belongs to the instance
+        // that has it open.
+        let ledger = self
+            .file
+            .as_ref()
+            .filter(|file| !file.read_only)
+            .map(|file| file.path.clone());
+        let encryption = self.file.as_ref().and_then(|file| file.encryption.clone());
+        if let Some(journal) = &mut self.journal
+            && Some(journal.ledger()) == ledger.as_deref()
+        {
+            if let Err(error) = journal.set_encryption(encryption) {
+                self.display_error(error.context("writing the journal"));
+            }
+            return;
+        }
+        if self.journal.is_none() && ledger.is_none() {
+            return;
+        }
+
+        if let Some(journal) = self.journal.take()
+            && let Err(error) = journal.close()
+        {
+            self.display_error(error);
+        }
+        let Some(ledger) = ledger else {
+            return;
+        };
+
+        match Journal::open(&ledger, encryption) {
+            Ok((journal, recovered)) => {
+                self.journal = Some(journal);
+                if recovered.is_some() {
+                    self.recovered = recovered;
+                    self.screen = Screen::Recover;
+                }
+            }
+            Err(error) => self.display_error(error.context("reading the journal")),
+        }
+    }
+
+    /// Writes the transaction forms that have been started and the import
+    /// under review to the journal.
+    fn autosave(&mut self) {
+        if self.recovered.is_some() {
+            return;
+        }
+        let Some(journal) = &mut self.journal else {
+            return;
+        };
+
+        let draft = Draft {
+            forms: self
+                .accounts
+                .inner
+                .iter()
+                .filter(|account| !account.view.tx.is_empty())
+                .map(|account| (account.name.clone(), account.view.tx.clone()))
+                .collect(),
+            import: self.import.clone(),
+        };
+        if let Err(error) = journal.write_draft(draft) {
+            self.display_error(error.context("writing the journal"));
+        }
+    }
+
+    /// Replays the changes a session that crashed didn't save and restores its
+    /// drafts.
+    fn accept_recovered(&mut self) {
+        let Some(recovered) = self.recovered.take() else {
+            return;
+        };
+
+        if !self.replay(&recovered.changes) {
+            self.discard_recovered();
+            self.display_error(anyhow::Error::msg(
+                "The recovered changes don't fit the ledger as it is, so they were discarded.",
+            ));
+        }
+
+        for (name, form) in recovered.draft.forms {
+            if let Some(account) = self
+                .accounts
+                .inner
+                .iter_mut()
+                .find(|account| account.name == name)
+            {
+                account.view.tx = form;
+            }
+        }
+        self.screen = Screen::Accounts;
+        if let Some(import) = recovered.draft.import {
+            self.import = Some(import);
+            self.screen = Screen::Import;
+        }
+    }
+
+    /// Makes changes from the journal again and saves them. They are tried on
+    /// a copy first, so changes that don't fit the ledger as it is now are
+    /// refused, returning false, instead of damaging it.
+    fn replay(&mut self, changes: &[Entry]) -> bool {
+        if changes.is_empty() {
+            return true;
         }
 
-        let add_stock_plus = row![
-            button_cell(button("Add Stock Plus").on_press(Message::AddStockPlus)),
-            text_cell("Description:"),
-            text_input("Description", &self.stock_plus_description)
-                .on_input(Message::UpdateStockPlusDescription)
-                .on_paste(Message::UpdateStockPlusDescription),
-            text_cell("Symbol:"),
-            text_input("Symbol", &self.stock_plus_symbol)
-                .on_input(Message::UpdateStockPlusSymbol)
-                .on_paste(Message::UpdateStockPlusSymbol),
-        ];
+        let mut accounts = self.accounts.clone();
+        let replayed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            for entry in changes {
+                match entry {
+                    Entry::Apply(command) => command.apply(&mut accounts),
+                    Entry::Undo(command) => command.undo(&mut accounts),
+                    Entry::Draft(_) => {}
+                }
+            }
+        }));
+        if replayed.is_err() {
+            return false;
+        }
+
+        // The journal still holds the changes until this save succeeds.
+        self.accounts = accounts;
+        self.history.clear();
+        self.refresh();
+        self.save();
+        true
+    }
+
+    fn discard_recovered(&mut self) {
+        self.recovered = None;
+        self.screen = Screen::Accounts;
+        if let Some(journal) = &mut self.journal
+            && let Err(error) = journal.discard()
+        {
+            self.display_error(error);
+        }
+    }
+
+    #[rustfmt::skip]
+    fn recover_view(&self) -> Column<'_, Message> {
+        let name = self.file.as_ref().map_or_else(String::new, |file| file.path.display().to_string());
+
+        let mut column_recovered = Column::new();
+        if let Some(recovered) = &self.recovered {
+            for line in recovered.summary() {
+                column_recovered = column_recovered.push(text_cell(line));
+            }
+        }
 
         let mut column_errors = Column::new();
         if let Some(errors) = &self.errors {
@@ -226,98 +1231,146 @@ impl App {
             }
         }
 
-        let cols = column![
-            crypto_current,
-            add_crypto,
-            fiats_current,
-            add_fiat,
-            metals_current,
-            add_metal,
-            stock_plus_current,
-            add_stock_plus,
+        column![
+            text_cell(format!("{name} wasn't closed cleanly. Recovered:")),
+            column_recovered,
+            row![
+                button_cell(button("Accept").on_press(Message::AcceptRecovered)),
+                button_cell(button("Discard").on_press(Message::DiscardRecovered)),
+            ].spacing(ROW_SPACING),
             column_errors,
-            button_cell(button("Back").on_press(Message::Back)),
-        ];
+        ]
+    }
 
-        Scrollable::new(cols)
+    /// Brings the selectors and inputs that mirror the ledger up to date.
+    fn refresh(&mut self) {
+        self.fiat_selector = State::new(Fiat::all_minus_existing(&self.accounts.fiats));
+        self.currency_selector = State::new(self.accounts.get_currencies());
+        self.matcher_days = self.accounts.duplicate_matcher.days.to_string();
+        self.matcher_similarity = self.accounts.duplicate_matcher.similarity.to_string();
+        self.backups_count = self.accounts.backups.to_string();
     }
 
-    fn display_error(&mut self, error: anyhow::Error) {
-        match self.errors {
-            Some(ref mut errors) => {
-                let errors = Arc::get_mut(errors).unwrap();
-                errors.push(error);
-            }
-            None => self.errors = Some(Arc::new(vec![error])),
+    fn undo(&mut self) {
+        if matches!(self.screen, Screen::Import | Screen::Recover) {
+            return;
+        }
+        if let Some(command) = self.history.undo(&mut self.accounts) {
+            let entry = Entry::Undo(command.clone());
+            self.after_undo_redo(&entry);
         }
     }
 
-    fn load_file(&mut self) {
-        let result = rfd::FileDialog::new()
-            .set_title(TITLE_FILE_PICKER)
-            .add_filter("ron", &["ron"])
-            .pick_file()
-            .context("You must choose a file name for your configuration file.");
-
-        match result {
-            Ok(file_path) => match Accounts::load(take(&mut self.file), file_path) {
-                Ok((accounts, file)) => {
-                    self.accounts = accounts;
-                    self.file = Some(file);
-                }
-                Err(error) => self.display_error(error),
-            },
-            Err(error) => self.display_error(error),
+    fn redo(&mut self) {
+        if matches!(self.screen, Screen::Import | Screen::Recover) {
+            return;
+        }
+        if let Some(command) = self.history.redo(&mut self.accounts) {
+            let entry = Entry::Apply(command.clone());
+            self.after_undo_redo(&entry);
         }
     }
 
-    fn open_url(&mut self, url: &str) {
-        if let Err(error) = webbrowser::open(url) {
-            self.display_error(error.into());
+    fn after_undo_redo(&mut self, entry: &Entry) {
+        self.journal(entry);
+        for account in &mut self.accounts.inner {
+            account.view.editing = None;
+        }
+        let shown = match self.screen {
+            Screen::Account(i) => self.accounts.inner.get(i).is_some(),
+            Screen::AccountSecondary(i) => self
+                .accounts
+                .inner
+                .get(i)
+                .is_some_and(|account| account.txs_2nd.is_some()),
+            Screen::Accounts
+            | Screen::Configuration
+            | Screen::Import
+            | Screen::Locked
+            | Screen::Passphrase
+            | Screen::Recover
+            | Screen::Search
+            | Screen::StartupError => true,
+        };
+        if !shown {
+            self.screen = Screen::Accounts;
+        }
+        if matches!(self.screen, Screen::Search) {
+            self.search();
         }
+        self.refresh();
+        self.save();
     }
 
-    fn save_file(&mut self) {
-        let result = rfd::FileDialog::new()
-            .set_title(TITLE_FILE_PICKER)
-            .add_filter("ron", &["ron"])
-            .save_file()
-            .context("You must choose a file name for your configuration file.");
-
-        match result {
-            Ok(file_path) => match self.accounts.save_dialogue(take(&mut self.file), file_path) {
-                Ok(file) => self.file = Some(file),
-                Err(error) => self.display_error(error),
-            },
-            Err(error) => self.display_error(error),
+    /// Starts with the file given on the command line. A file that can't be
+    /// loaded or created shows why instead of the accounts.
+    #[must_use]
+    pub fn from_args(args: &command_line::Args) -> Self {
+        match command_line::get_configuration_file(args) {
+            command_line::File::Load(file_path) => {
+                match Accounts::load(&mut None, file_path.clone(), None) {
+                    Ok((accounts, file)) => Self::new(accounts, Some(file)),
+                    Err(error) if error.is::<NeedsPassphrase>() => {
+                        let mut app = Self::new(Accounts::new(), None);
+                        app.opening = Some(file_path);
+                        app.screen = Screen::Passphrase;
+                        app
+                    }
+                    Err(error) if error.is::<Locked>() => {
+                        let mut app = Self::new(Accounts::new(), None);
+                        app.opening = Some(file_path);
+                        app.screen = Screen::Locked;
+                        app
+                    }
+                    Err(error) => Self::startup_failed(file_path, error),
+                }
+            }
+            command_line::File::New(file_path) => {
+                let accounts = Accounts::new();
+                match accounts.save_first(file_path.clone()) {
+                    Ok(file) => Self::new(accounts, Some(file)),
+                    Err(error) => {
+                        let error = error.context(format!("creating {}", file_path.display()));
+                        Self::startup_failed(file_path, error)
+                    }
+                }
+            }
+            command_line::File::None => Self::new(Accounts::new(), None),
         }
     }
 
-    fn save(&mut self) {
-        match self.accounts.save(take(&mut self.file)) {
-            Ok(file) => self.file = Some(file),
-            Err(error) => self.display_error(error),
-        }
+    fn startup_failed(file_path: PathBuf, error: anyhow::Error) -> Self {
+        let mut app = Self::new(Accounts::new(), None);
+        app.startup_error = Some(StartupError::new(file_path, error));
+        app.screen = Screen::StartupError;
+        app
     }
 
     fn new(accounts: Accounts, file: Option<File>) -> Self {
         let currencies = accounts.get_currencies();
+        let matcher = accounts.duplicate_matcher.clone();
+        let backups_count = accounts.backups.to_string();
 
-        Self {
+        let mut app = Self {
             fiat_selector: State::new(Fiat::all_minus_existing(&accounts.fiats)),
 
             accounts,
             file,
             account_name: String::new(),
+            backups: Vec::new(),
+            backups_count,
             crypto_currency: None,
             crypto_currency_selector: State::new(Fiat::all()),
             crypto_description: String::new(),
             crypto_symbol: String::new(),
+            include_read_only: false,
             fiat: None,
             metal_currency: None,
             metal_currency_selector: State::new(Fiat::all()),
             metal_description: String::new(),
             metal_symbol: String::new(),
+            matcher_days: matcher.days.to_string(),
+            matcher_similarity: matcher.similarity.to_string(),
             progress_bar: None,
             stock_plus_description: String::new(),
             stock_plus_symbol: String::new(),
@@ -326,8 +1379,25 @@ impl App {
             duration: Duration::default(),
             project_months: None,
             screen: Screen::Accounts,
+            import: None,
+            history: History::default(),
+            opening: None,
+            restoring: None,
+            passphrase: String::new(),
+            passphrase_confirm: String::new(),
+            journal: None,
+            lock_released: false,
+            changed_on_disk: false,
+            recovered: None,
+            startup_error: None,
             errors: None,
-        }
+            search: String::new(),
+            found: Vec::new(),
+            script_output: Vec::new(),
+        };
+        app.list_backups();
+        app.start_journal();
+        app
     }
 
     fn change_project_months(&mut self, months: &str) {
@@ -338,137 +1408,147 @@ impl App {
         }
     }
 
-    fn check_account_name(&mut self, name: &str) -> anyhow::Result<()> {
-        for account in &self.accounts.inner {
-            if name == account.name {
-                return Err(anyhow::Error::msg("Duplicate name!"));
-            }
-        }
-        Ok(())
-    }
-
     fn delete(&mut self, i: usize) {
-        match self.screen {
-            Screen::Accounts => {
-                self.accounts.inner.remove(i);
-                for group in &mut self.accounts.groups {
-                    group.remove(i);
-                }
+        let command = match self.screen {
+            Screen::Accounts => self.accounts.remove_account(i),
+            Screen::Account(j) | Screen::AccountSecondary(j) => {
+                let secondary = matches!(self.screen, Screen::AccountSecondary(_));
+                let account = &mut self.accounts[j];
+                account.view.editing = None;
+                let tx = account.remove_tx(secondary, i);
+                Command::RemoveTransaction(TransactionChange {
+                    account: j,
+                    secondary,
+                    index: i,
+                    tx,
+                })
             }
-            Screen::Account(j) => {
-                self.accounts[j].txs_1st.txs.remove(i);
+            Screen::Configuration
+            | Screen::Import
+            | Screen::Locked
+            | Screen::Passphrase
+            | Screen::Recover
+            | Screen::Search
+            | Screen::StartupError => {
+                panic!("Nothing to delete!")
             }
-            Screen::AccountSecondary(j) => {
-                self.accounts[j].txs_2nd.as_mut().unwrap().txs.remove(i);
-            }
-            Screen::Configuration => panic!("Nothing to delete!"),
-        }
+        };
 
-        self.save();
+        self.record(command);
     }
 
     fn delete_group(&mut self, i: usize) {
-        self.accounts.groups.remove(i);
-        self.save();
+        let group = self.accounts.groups.remove(i);
+        self.record(Command::RemoveGroup { index: i, group });
     }
 
-    fn import_investor_360(&mut self, file_xls: &PathBuf) -> anyhow::Result<()> {
-        let file_csv = file_xls.file_stem().unwrap();
-        let mut file_csv = PathBuf::from_str(file_csv.to_str().unwrap())?;
-        file_csv.set_extension("csv");
-
-        if fs::exists(&file_csv)? {
-            return Err(anyhow::Error::msg(format!(
-                "\"{}\" already exists!",
-                &file_csv.display()
-            )));
+    fn import_boa(&mut self, i: usize) {
+        if let Some(file_path) = rfd::FileDialog::new()
+            .set_title(TITLE_FILE_PICKER)
+            .add_filter("csv", &["csv"])
+            .pick_file()
+        {
+            match import_boa(file_path) {
+                Ok(txs) => {
+                    self.import = Some(Import::boa(&self.accounts, i, txs));
+                    self.screen = Screen::Import;
+                }
+                Err(error) => {
+                    self.display_error(error);
+                    self.screen = Screen::Accounts;
+                }
+            }
         }
+    }
 
-        std::process::Command::new("libreoffice")
-            .arg("--convert-to")
-            .arg("csv")
-            .arg(file_xls)
-            .status()
-            .context(
-                r#"Couldn't execute "libreoffice --convert-to csv", you must install libreoffice."#,
-            )?;
-
-        for investor_360_record in csv::Reader::from_path(&file_csv)?.deserialize() {
-            let investor_360_record: Investor360 = investor_360_record?;
+    fn import_investor_360(&mut self) {
+        if let Some(file_path) = rfd::FileDialog::new()
+            .set_title(TITLE_FILE_PICKER)
+            .add_filter("xls", &["xls", "xlsx"])
+            .pick_file()
+        {
+            match import_investor_360(&file_path) {
+                Ok(records) => {
+                    self.import = Some(Import::investor_360(&self.accounts, records));
+                    self.screen = Screen::Import;
+                }
+                Err(error) => self.display_error(error),
+            }
+        }
+    }
 
-            // Skip some junk records.
-            if investor_360_record.symbol.is_empty() {
-                continue;
+    /// Adds the accounts of a journal straight away, as there is nothing to
+    /// review: they are all new.
+    fn import_journal(&mut self) {
+        if let Some(file_path) = rfd::FileDialog::new()
+            .set_title(TITLE_FILE_PICKER)
+            .add_filter(
+                "journal",
+                &["journal", "ledger", "hledger", "dat", "beancount", "bean"],
+            )
+            .pick_file()
+        {
+            match import_journal(&mut self.accounts, &file_path) {
+                Ok((commands, names)) => {
+                    self.record(Command::Batch(commands));
+                    self.run_scripts(&Event::Import(names));
+                }
+                Err(error) => self.display_error(error),
             }
+        }
+    }
 
-            let balance = investor_360_record.quantity.unwrap();
-            let mut tx = Transaction {
-                amount: dec!(0),
-                balance,
-                comment: investor_360_record.description.clone(),
-                date: Utc::now(),
-            };
+    fn import_submit(&mut self) {
+        let Some(import) = self.import.take() else {
+            return;
+        };
 
-            let name = format!("Investor 360: {}", &investor_360_record.symbol);
-            let mut name_matches = false;
-            for account in &mut self.accounts.inner {
-                if account.name == name {
-                    if investor_360_record.price.unwrap() == dec!(1) {
-                        tx.amount = balance - account.txs_1st.txs.last().unwrap().balance;
-                        account.txs_1st.txs.push(tx.clone());
-                    } else {
-                        tx.amount = balance
-                            - account
-                                .txs_2nd
-                                .as_ref()
-                                .unwrap()
-                                .txs
-                                .last()
-                                .unwrap()
-                                .balance;
-                        account.txs_2nd.as_mut().unwrap().txs.push(tx.clone());
-                    }
-                    name_matches = true;
-                    break;
+        let names = match import.source {
+            Source::Boa(i) => vec![self.accounts[i].name.clone()],
+            Source::Investor360 => import
+                .clone()
+                .checked()
+                .into_iter()
+                .map(|row| row.account)
+                .collect(),
+        };
+        let result = match import.source {
+            Source::Boa(i) => Ok(self.accounts.commit_boa(i, &import)),
+            Source::Investor360 => self.accounts.commit_investor_360(import.clone().checked()),
+        };
+
+        match result {
+            Ok(commands) => {
+                if !commands.is_empty() {
+                    self.record(Command::Batch(commands));
                 }
+                self.screen = Screen::Accounts;
+                self.run_scripts(&Event::Import(names));
             }
+            Err(error) => {
+                self.import = Some(import);
+                self.display_error(error);
+            }
+        }
+    }
 
-            if !name_matches {
-                tx.amount = balance;
-                let txs = vec![tx];
-
-                if investor_360_record.price.unwrap() == dec!(1) {
-                    let currency = Fiat::Usd;
-                    let transactions = Transactions {
-                        currency: currency.clone(),
-                        txs,
-                    };
-                    let mut account = Account::new(name, Currency::Fiat(currency));
-                    account.txs_1st = transactions;
-                    self.accounts.inner.push(account);
-                } else {
-                    let stock = StockPlus {
-                        description: investor_360_record.description,
-                        symbol: investor_360_record.symbol,
-                    };
-                    let currency = Currency::StockPlus(stock);
-                    let transactions = Transactions {
-                        currency: currency.clone(),
-                        txs,
-                    };
-                    let mut account = Account::new(name, currency);
-                    account.txs_2nd = Some(transactions);
-                    self.accounts.inner.push(account);
+    fn update_import(&mut self, message: import::Message) {
+        match message {
+            import::Message::Cancel => {
+                self.import = None;
+                self.screen = Screen::Accounts;
+            }
+            import::Message::Submit => self.import_submit(),
+            message => {
+                if let Some(import) = &mut self.import {
+                    import.update(message);
                 }
             }
         }
-
-        fs::remove_file(&file_csv)?;
-        Ok(())
     }
 
     #[rustfmt::skip]
-    fn display_groups(&self) -> GroupColumnDisplay<'_> {
+    fn display_groups<'a>(groups: Vec<report::Line>) -> GroupColumnDisplay<'a> {
         let mut a_ = column![text_cell(""), text_cell("Group")];
         let mut b_ = column![text_cell(""), text_cell("")].align_x(Alignment::End);
         let mut c_ = column![text_cell(""), text_cell("")].align_x(Alignment::End);
@@ -476,69 +1556,24 @@ impl App {
         let mut e_ = column![text_cell(""), text_cell("")].align_x(Alignment::End);
         let mut f_ = column![text_cell(""), text_cell("")];
 
-        for (index, group) in self.accounts.groups.iter().enumerate() {
-            a_ = a_.push(text_cell(&group.name));
-            let mut sum_before_last_week = dec!(0);
-            let mut sum_last_week = dec!(0);
-            let mut sum_before_last_month = dec!(0);
-            let mut sum_last_month = dec!(0);
-            let mut sum_before_last_year = dec!(0);
-            let mut sum_last_year = dec!(0);
-            let mut balance = dec!(0);
-
-            for index in &group.members {
-                let (before_last_week, last_week) = self.accounts.inner[*index].sum_last_week();
-                let (before_last_month, last_month) = self.accounts.inner[*index].sum_last_month();
-                let (before_last_year, last_year) = self.accounts.inner[*index].sum_last_year();
-
-                sum_before_last_week += before_last_week;
-                sum_last_week += last_week;
-                sum_before_last_month += before_last_month;
-                sum_last_month += last_month;
-                sum_before_last_year += before_last_year;
-                sum_last_year += last_year;
-                balance += self.accounts.inner[*index].balance_1st();
-            }
-
-            sum_last_week = div_0_ok(sum_last_week, sum_before_last_week);
-            sum_last_month = div_0_ok(sum_last_month, sum_before_last_month);
-            sum_last_year = div_0_ok(sum_last_year, sum_before_last_year);
-
-            sum_last_week.rescale(LAST_DATE_SCALE);
-            sum_last_month.rescale(LAST_DATE_SCALE);
-            sum_last_year.rescale(LAST_DATE_SCALE);
-            balance.rescale(2);
-
-            b_ = b_.push(number_cell(sum_last_week));
-            c_ = c_.push(number_cell(sum_last_month));
-            d_ = d_.push(number_cell(sum_last_year));
-            e_ = e_.push(number_cell(balance));
+        for (index, group) in groups.into_iter().enumerate() {
+            a_ = a_.push(text_cell(group.name));
+            b_ = b_.push(number_cell(group.week));
+            c_ = c_.push(number_cell(group.month));
+            d_ = d_.push(number_cell(group.year));
+            e_ = e_.push(number_cell(group.balance));
             f_ = f_.push(button_cell(button("Delete").on_press(Message::DeleteGroup(index))));
         }
 
         GroupColumnDisplay { a: a_, b: b_, c: c_, d: d_, e: e_, f: f_}
     }
 
-    fn display_totals(&self, currency: &Fiat) -> TotalsColumnDisplay<'_> {
-        let (before_last_week, mut last_week) = self.accounts.total_for_last_week(currency);
-        let (before_last_month, mut last_month) = self.accounts.total_for_last_month(currency);
-        let (before_last_year, mut last_year) = self.accounts.total_for_last_year(currency);
-        let mut balance = self.accounts.balance(currency);
-
-        last_week = div_0_ok(last_week, before_last_week);
-        last_month = div_0_ok(last_month, before_last_month);
-        last_year = div_0_ok(last_year, before_last_year);
-
-        last_week.rescale(LAST_DATE_SCALE);
-        last_month.rescale(LAST_DATE_SCALE);
-        last_year.rescale(LAST_DATE_SCALE);
-        balance.rescale(2);
-
-        let a_ = column![text_cell(format!("{currency} Total:"))];
-        let b_ = column![number_cell(last_week)];
-        let c_ = column![number_cell(last_month)];
-        let d_ = column![number_cell(last_year)];
-        let e_ = column![number_cell(balance)];
+    fn display_totals<'a>(total: &report::Line) -> TotalsColumnDisplay<'a> {
+        let a_ = column![text_cell(format!("{} Total:", total.name))];
+        let b_ = column![number_cell(total.week)];
+        let c_ = column![number_cell(total.month)];
+        let d_ = column![number_cell(total.year)];
+        let e_ = column![number_cell(total.balance)];
         let f_ = column![text_cell("")];
 
         TotalsColumnDisplay {
@@ -554,6 +1589,7 @@ impl App {
     #[rustfmt::skip]
     fn rows(&self) -> Row<'_, Message> {
         let mut col_0 = column![text_cell(" Account "), text_cell("---")];
+        let mut col_s = column![text_cell("File"), text_cell("---")];
         let mut col_1 = column![button_cell(button("Week").on_press(Message::ChartWeek)), text_cell("---")].align_x(Alignment::End);
         let mut col_2 = column![button_cell(button("Month").on_press(Message::ChartMonth)), text_cell("---")].align_x(Alignment::End);
         let mut col_3 = column![button_cell(button("Year").on_press(Message::ChartYear)), text_cell("---")].align_x(Alignment::End);
@@ -568,44 +1604,21 @@ impl App {
         let mut col_c = column![text_cell(""), text_cell("---")].spacing(COLUMN_SPACING);
         let mut col_d = column![text_cell(""), text_cell("---")].spacing(COLUMN_SPACING);
 
-        for (i, account) in self.accounts.inner.iter().enumerate() {
-            let (before_last_week, mut last_week) = account.sum_last_week();
-            let (before_last_month, mut last_month) = account.sum_last_month();
-            let (before_last_year, mut last_year) = account.sum_last_year();
-            let mut value = account.balance_1st();
-
-            let mut quantity = text_cell("");
-            let mut price = text_cell("");
-            if let Some(mut quantity_) = account.balance_2nd() {
-                let mut price_ = if quantity_ == dec!(0.0) {
-                    dec!(0.0)
-                } else {
-                    value / quantity_
-                };
-
-                quantity_.rescale(8);
-                price_.rescale(2);
-                quantity = number_cell(quantity_);
-                price = number_cell(price_);
-            }
-
-            last_week = div_0_ok(last_week, before_last_week);
-            last_month = div_0_ok(last_month, before_last_month);
-            last_year = div_0_ok(last_year, before_last_year);
-
-            last_week.rescale(LAST_DATE_SCALE);
-            last_month.rescale(LAST_DATE_SCALE);
-            last_year.rescale(LAST_DATE_SCALE);
-            value.rescale(2);
-
+        let report = Report::new(&self.accounts);
+        for (i, (account, line)) in self.accounts.inner.iter().zip(report.accounts).enumerate() {
             col_0 = col_0.push(text_cell(&account.name));
-            col_1 = col_1.push(number_cell(last_week));
-            col_2 = col_2.push(number_cell(last_month));
-            col_3 = col_3.push(number_cell(last_year));
-            col_4 = col_4.push(number_cell(value));
-            col_5 = col_5.push(price);
-            col_6 = col_6.push(quantity);
-            col_7 = col_7.push(Checkbox::new(self.accounts[i].check_box).on_toggle(move |b| Message::Checkbox((i, b))).size(35));
+            let mut source = self.accounts.source_name(account.source);
+            if self.accounts.is_read_only(account.source) {
+                source.push_str(" (read-only)");
+            }
+            col_s = col_s.push(text_cell(source));
+            col_1 = col_1.push(number_cell(line.week));
+            col_2 = col_2.push(number_cell(line.month));
+            col_3 = col_3.push(number_cell(line.year));
+            col_4 = col_4.push(number_cell(line.balance));
+            col_5 = col_5.push(line.price.map_or_else(|| text_cell(""), number_cell));
+            col_6 = col_6.push(line.quantity.map_or_else(|| text_cell(""), number_cell));
+            col_7 = col_7.push(Checkbox::new(self.accounts[i].view.check_box).on_toggle(move |b| Message::Checkbox((i, b))).size(35));
             col_8 = col_8.push(button_cell(button("Tx").on_press(Message::SelectAccount(i))));
             let mut txs_2nd = button("Tx 2nd");
             if let Some(account) = &account.txs_2nd
@@ -631,8 +1644,8 @@ impl App {
             col_d = col_d.push(button_cell(button("Delete").on_press(Message::Delete(i))));
         }
 
-        for currency in self.accounts.currencies() {
-            let totals_display = self.display_totals(&currency);
+        for total in &report.totals {
+            let totals_display = Self::display_totals(total);
             col_0 = col_0.push(totals_display.a);
             col_1 = col_1.push(totals_display.b);
             col_2 = col_2.push(totals_display.c);
@@ -641,7 +1654,7 @@ impl App {
             col_d = col_d.push(totals_display.f);
         }
 
-        let group_display = self.display_groups();
+        let group_display = Self::display_groups(report.groups);
         col_0 = col_0.push(group_display.a);
         col_1 = col_1.push(group_display.b);
         col_2 = col_2.push(group_display.c);
@@ -649,7 +1662,12 @@ impl App {
         col_4 = col_4.push(group_display.e);
         col_d = col_d.push(group_display.f);
 
-        row![col_0, col_1, col_2, col_3, col_4, col_5, col_6, col_7, col_8, col_9, col_a, col_b, col_c, col_d]
+        // The file each account is in only matters when there is more than one.
+        let mut row = row![col_0];
+        if !self.accounts.includes.is_empty() {
+            row = row.push(col_s);
+        }
+        row.extend([col_1, col_2, col_3, col_4, col_5, col_6, col_7, col_8, col_9, col_a, col_b, col_c, col_d].map(Element::from))
     }
 
     #[rustfmt::skip]
@@ -689,6 +1707,16 @@ impl App {
             add_group = add_group.on_press(Message::AddGroup);
         }
 
+        let mut undo = button("Undo");
+        if self.history.can_undo() {
+            undo = undo.on_press(Message::Undo);
+        }
+
+        let mut redo = button("Redo");
+        if self.history.can_redo() {
+            redo = redo.on_press(Message::Redo);
+        }
+
         let mut all_prices = row![button_cell(button("Get All Prices").on_press(Message::GetPriceAll))].padding(PADDING);
         if let Some(progress) = self.progress_bar {
             all_prices = all_prices.push(ProgressBar::<Theme>::new(0.0..=100.0, progress));
@@ -699,6 +1727,7 @@ impl App {
             charts,
             rows.spacing(ROW_SPACING),
             column_errors,
+            self.script_output(),
             text_cell(""),
             row![
                 widget::text("Account").size(TEXT_SIZE),
@@ -714,12 +1743,17 @@ impl App {
                 button_cell(button("Open BoA URL").on_press(Message::OpenBoaUrl)),
                 button_cell(button("Open Investor 360 URL").on_press(Message::OpenInvestor360Url)),
                 button_cell(button("Import Investor 360").on_press(Message::ImportInvestor360)),
+                button_cell(button("Import Journal").on_press(Message::ImportJournal)),
             ].spacing(ROW_SPACING).padding(PADDING).padding(PADDING),
             row![
                 button_cell(button("Exit").on_press(Message::Exit)),
                 button_cell(button("Load").on_press(Message::FileLoad)),
                 button_cell(button("Save As").on_press(Message::FileSaveAs)),
+                button_cell(button("Export").on_press(Message::Export)),
                 button_cell(button("Configuration").on_press(Message::Configuration)),
+                button_cell(button("Search").on_press(Message::Search)),
+                button_cell(undo),
+                button_cell(redo),
             ].spacing(ROW_SPACING).padding(PADDING)
         ];
 
@@ -728,55 +1762,80 @@ impl App {
 
     fn select_account(&mut self, message: account::Message) {
         if let Some(account) = match self.screen {
-            Screen::Accounts | Screen::Configuration => None,
+            Screen::Accounts
+            | Screen::Configuration
+            | Screen::Import
+            | Screen::Locked
+            | Screen::Passphrase
+            | Screen::Recover
+            | Screen::Search
+            | Screen::StartupError => None,
             Screen::Account(account) | Screen::AccountSecondary(account) => Some(account),
-        } && self.accounts[account].update(&self.screen, message)
+        } && let Some(command) = self.accounts[account].update(&self.screen, message)
         {
-            self.save();
+            self.record(command);
         }
     }
 
-    fn insert_new_account(&mut self, new_account: Account) {
-        if self.accounts.inner.is_empty() {
-            self.accounts.inner.push(new_account);
+    /// Inserts the account in name order and adds it to the groups at the
+    /// indexes in `member_of`.
+    fn submit_account(&mut self) {
+        let name = self.account_name.trim().to_string();
+        if let Err(error) = self.accounts.check_account_name(&name) {
+            self.display_error(error);
             return;
         }
 
-        for (i, account) in self.accounts.inner.iter().enumerate() {
-            if account.name > new_account.name {
-                self.accounts.inner.insert(i, new_account);
-                for group in &mut self.accounts.groups {
-                    for index in &mut group.members {
-                        if *index > i {
-                            *index += 1;
-                        }
-                    }
-                }
-                return;
-            }
-        }
+        let new_account = Account::new(name, self.currency.clone().unwrap());
+        let command = self.accounts.insert_account(new_account, &[]);
+        self.record(command);
+    }
 
-        self.accounts.inner.push(new_account);
+    fn get_price(&mut self, i: usize) {
+        let result = futures::executor::block_on(self.accounts[i].submit_price_as_transaction());
+        self.add_prices(vec![(i, result)]);
     }
 
-    fn remove_account(&mut self, index: usize) -> Account {
-        for group in &mut self.accounts.groups {
-            group.members.remove(index);
-        }
-        self.accounts.inner.remove(index)
+    fn get_all_prices(&mut self) {
+        let results = futures::executor::block_on(self.accounts.get_all_prices());
+        self.add_prices(results);
     }
 
-    fn submit_account(&mut self) {
-        let name = self.account_name.trim().to_string();
-        if let Err(error) = self.check_account_name(&name) {
+    /// Adds the prices, then runs the scripts' `on_prices`.
+    fn add_prices(&mut self, results: Vec<(usize, anyhow::Result<Transaction>)>) {
+        let (commands, errors) = self.accounts.add_prices(results);
+        for error in errors {
             self.display_error(error);
-            return;
         }
 
-        let new_account = Account::new(name, self.currency.clone().unwrap());
+        if !commands.is_empty() {
+            self.record(Command::Batch(commands));
+        }
+        self.run_scripts(&Event::Prices);
+    }
 
-        self.insert_new_account(new_account);
-        self.save();
+    pub fn subscription(&self) -> Subscription<Message> {
+        let keys = keyboard::listen().filter_map(|event| match event {
+            keyboard::Event::KeyPressed { key, modifiers, .. } if modifiers.command() => {
+                match key.as_ref() {
+                    Key::Character("z" | "Z") if modifiers.shift() => Some(Message::Redo),
+                    Key::Character("z" | "Z") => Some(Message::Undo),
+                    Key::Character("y" | "Y") => Some(Message::Redo),
+                    _ => None,
+                }
+            }
+            _ => None,
+        });
+
+        let mut subscriptions = vec![
+            keys,
+            time::every(AUTOSAVE).map(|_| Message::Autosave),
+            window::close_requests().map(|_| Message::Exit),
+        ];
+        if self.file.is_some() {
+            subscriptions.push(time::every(CHECK_FILE).map(|_| Message::CheckFile));
+        }
+        Subscription::batch(subscriptions)
     }
 
     pub fn theme(&self) -> Theme {
@@ -785,21 +1844,35 @@ impl App {
 
     fn update_account_name(&mut self, i: usize) {
         let name = self.account_name.trim().to_string();
-        if let Err(error) = self.check_account_name(&name) {
+        if let Err(error) = self.accounts.check_account_name(&name) {
             self.display_error(error);
             return;
         }
 
-        let mut account = self.remove_account(i);
-        account.name = self.account_name.trim().to_string();
-        // add the new group
+        let member_of: Vec<_> = (0..)
+            .zip(&self.accounts.groups)
+            .filter(|(_, group)| group.members.contains(&i))
+            .map(|(index, _)| index)
+            .collect();
 
-        self.insert_new_account(account);
-        self.save();
+        let mut account = self.accounts[i].clone();
+        account.name = name;
+        let remove = self.accounts.remove_account(i);
+        let insert = self.accounts.insert_account(account, &member_of);
+        self.record(Command::Batch(vec![remove, insert]));
     }
 
     pub fn update(&mut self, message: Message) -> Task<Message> {
-        self.errors = None;
+        // The timers aren't something the user did, so they leave the errors shown.
+        if !matches!(message, Message::Autosave | Message::CheckFile) {
+            self.errors = None;
+        }
+        if message.mutates() && self.read_only() {
+            self.display_error(anyhow::Error::msg(
+                "The file is open read-only, so it can't be changed.",
+            ));
+            return Task::none();
+        }
 
         match message {
             Message::AddCrypto => self.add_crypto(),
@@ -808,73 +1881,49 @@ impl App {
             Message::AddMetal => self.add_metal(),
             Message::AddStockPlus => self.add_stock_plus(),
             Message::Account(message) => self.select_account(message),
+            Message::CancelOpen => self.cancel_open(),
+            Message::ChangePassphrase(passphrase) => self.passphrase = passphrase,
+            Message::ChangePassphraseConfirm(passphrase) => self.passphrase_confirm = passphrase,
+            Message::Decrypt => self.decrypt(),
+            Message::Encrypt => self.encrypt(),
+            Message::AcceptRecovered => self.accept_recovered(),
+            Message::Autosave => self.autosave(),
             Message::Back => self.screen = Screen::Accounts,
             Message::ChangeAccountName(name) => self.account_name = name,
             Message::ChangeProjectMonths(months) => self.change_project_months(&months),
+            Message::CheckFile => self.check_file(),
             Message::ChartWeek => self.duration = Duration::Week,
             Message::ChartMonth => self.duration = Duration::Month,
             Message::ChartYear => self.duration = Duration::Year,
             Message::ChartAll => self.duration = Duration::All,
-            Message::Checkbox((i, b)) => self.accounts[i].check_box = b,
+            Message::Checkbox((i, b)) => self.accounts[i].view.check_box = b,
             Message::Configuration => self.screen = Screen::Configuration,
             Message::Delete(i) => self.delete(i),
             Message::DeleteGroup(i) => self.delete_group(i),
+            Message::DiscardRecovered => self.discard_recovered(),
             Message::FileLoad => self.load_file(),
+            Message::FileNew => self.new_file(),
             Message::FileSaveAs => self.save_file(),
-            Message::GetPrice(i) => {
-                let account = &mut self.accounts[i];
-
-                match futures::executor::block_on(account.submit_price_as_transaction()) {
-                    Ok(tx) => {
-                        account.txs_1st.txs.push(tx);
-                        account.txs_1st.sort();
-                        self.save();
-                    }
-                    Err(error) => {
-                        self.display_error(error);
-                    }
-                }
-            }
-            Message::GetPriceAll => {
-                let errors = futures::executor::block_on(self.accounts.get_all_prices());
-                if !errors.is_empty() {
-                    self.errors = Some(Arc::new(errors));
-                }
-                self.save();
-            }
-            Message::ImportBoa(i) => {
-                let account = &mut self.accounts[i];
-
-                if let Some(file_path) = rfd::FileDialog::new()
-                    .set_title(TITLE_FILE_PICKER)
-                    .add_filter("csv", &["csv"])
-                    .pick_file()
-                {
-                    if let Err(error) = account.import_boa(file_path) {
-                        self.display_error(error);
-                    } else {
-                        self.save();
-                    }
-                    self.screen = Screen::Accounts;
-                }
-            }
-            Message::ImportInvestor360 => {
-                if let Some(file_path) = rfd::FileDialog::new()
-                    .set_title(TITLE_FILE_PICKER)
-                    .add_filter("xls", &["xls"])
-                    .pick_file()
-                {
-                    if let Err(error) = self.import_investor_360(&file_path) {
-                        self.display_error(error);
-                    } else {
-                        self.accounts.sort();
-                        self.save();
-                    }
-                }
-            }
+            Message::Export => self.export_file(),
+            Message::GetPrice(i) => self.get_price(i),
+            Message::GetPriceAll => self.get_all_prices(),
+            Message::Import(message) => self.update_import(message),
+            Message::ImportBoa(i) => self.import_boa(i),
+            Message::KeepMine => self.keep_mine(),
+            Message::MergeFile => self.merge_changed(),
+            Message::ImportInvestor360 => self.import_investor_360(),
+            Message::ImportJournal => self.import_journal(),
             Message::OpenBoaUrl => self.open_url(BOA_URL),
+            Message::OpenLatestBackup => self.open_latest_backup(None),
+            Message::OpenReadOnly => self.open_read_only(),
             Message::OpenInvestor360Url => self.open_url(INVESTOR_360_URL),
+            Message::Redo => self.redo(),
+            Message::Undo => self.undo(),
+            Message::ReloadFile => self.reload_changed(),
+            Message::ReloadReadWrite => self.reload_file(),
+            Message::RestoreBackup(backup) => self.restore_backup(&backup),
             Message::UpdateAccountName(i) => self.update_account_name(i),
+            Message::UpdateBackups(count) => self.backups_count = count,
             Message::UpdateCurrency(currency) => self.currency = Some(currency),
             Message::UpdateCryptoCurrency(fiat) => self.crypto_currency = Some(fiat),
             Message::UpdateCryptoDescription(description) => self.crypto_description = description,
@@ -887,48 +1936,69 @@ impl App {
                 self.stock_plus_description = description;
             }
             Message::UpdateStockPlusSymbol(symbol) => self.stock_plus_symbol = symbol,
-            Message::SelectAccount(i) => self.screen = Screen::Account(i),
-            Message::SelectAccountSecondary(i) => self.screen = Screen::AccountSecondary(i),
-            Message::SubmitAccount => self.submit_account(),
-            Message::Exit => {
-                return iced::exit();
+            Message::UpdateMatcherDays(days) => self.matcher_days = days,
+            Message::UpdateMatcherSimilarity(similarity) => self.matcher_similarity = similarity,
+            Message::SetBackups => self.set_backups(),
+            Message::IncludeFile => self.include_file(),
+            Message::IncludeReadOnly(read_only) => self.include_read_only = read_only,
+            Message::RemoveInclude(i) => self.remove_include(i),
+            Message::AddScript => self.add_script(),
+            Message::RemoveScript(i) => self.remove_script(i),
+            Message::RunScript(i) => self.run_script(i),
+            Message::RunScripts => self.run_scripts(&Event::Demand),
+            Message::SetMatcher => self.set_matcher(),
+            Message::SelectAccount(i) => {
+                self.accounts[i].view.editing = None;
+                self.screen = Screen::Account(i);
             }
+            Message::SelectAccountSecondary(i) => {
+                self.accounts[i].view.editing = None;
+                self.screen = Screen::AccountSecondary(i);
+            }
+            Message::SubmitAccount => self.submit_account(),
+            Message::SubmitPassphrase => self.submit_passphrase(),
+            Message::Search => self.open_search(),
+            Message::ChangeSearch(search) => self.search = search,
+            Message::SubmitSearch => self.search(),
+            Message::Exit => return self.exit(),
         }
 
         Task::none()
     }
 
-    pub fn view(&self) -> Element<'_, Message> {
-        match self.screen {
-            Screen::Accounts => self.list_accounts().into(),
-            Screen::Account(i) => self.accounts[i].list_transactions().into(),
-            Screen::AccountSecondary(i) => self.accounts[i].list_transactions_2nd().into(),
-            Screen::Configuration => self.config().into(),
+    fn exit(&mut self) -> Task<Message> {
+        // A journal that can't be removed is only offered again on the next
+        // start.
+        if let Some(journal) = self.journal.take() {
+            let _ = journal.close();
         }
+        iced::exit()
     }
-}
 
-impl Default for App {
-    fn default() -> Self {
-        match command_line::get_configuration_file() {
-            command_line::File::Load(file_path) => {
-                let file_path_ = file_path.clone();
-                let (accounts, file) = Accounts::load(None, file_path).unwrap_or_else(|err| {
-                    panic!("error loading {}: {}", &file_path_.display(), err)
-                });
-                Self::new(accounts, Some(file))
+    pub fn view(&self) -> Element<'_, Message> {
+        let view: Element<'_, Message> = match self.screen {
+            Screen::Accounts => self.list_accounts().into(),
+            Screen::Account(i) => self.accounts[i].list_transactions(&self.history).into(),
+            Screen::AccountSecondary(i) => {
+                self.accounts[i].list_transactions_2nd(&self.history).into()
             }
-            command_line::File::New(file_path) => {
-                let accounts = Accounts::new();
-                let file_path_ = file_path.clone();
-                let file = accounts.save_first(file_path).unwrap_or_else(|error| {
-                    panic!("error creating {}: {}", &file_path_.display(), error)
-                });
+            Screen::Configuration => self.config().into(),
+            Screen::Import => self.import_review().into(),
+            Screen::Locked => self.locked_view().into(),
+            Screen::Passphrase => self.passphrase_view().into(),
+            Screen::Recover => self.recover_view().into(),
+            Screen::Search => self.search_view().into(),
+            Screen::StartupError => self.startup_error_view().into(),
+        };
 
-                Self::new(accounts, Some(file))
-            }
-            command_line::File::None => Self::new(Accounts::new(), None),
+        let mut banners = Column::new();
+        if self.read_only() {
+            banners = banners.push(self.read_only_banner());
+        }
+        if self.changed_on_disk {
+            banners = banners.push(self.changed_banner());
         }
+        column![banners, view].into()
     }
 }
 
@@ -950,14 +2020,6 @@ struct TotalsColumnDisplay<'a> {
     f: Column<'a, Message>,
 }
 
-fn div_0_ok(dividend: Decimal, divisor: Decimal) -> Decimal {
-    if divisor.is_zero() {
-        dec!(0)
-    } else {
-        dividend / divisor
-    }
-}
-
 fn some_or_empty<T: ToString>(value: Option<&T>) -> String {
     value.map_or_else(String::new, ToString::to_string)
 }
@@ -993,32 +2055,10 @@ fn text_cell_red<'a>(s: impl ToString + IntoFragment<'a>) -> Row<'a, Message> {
 }
 
 #[derive(Clone, Debug, Default)]
-enum Duration {
+pub(crate) enum Duration {
     Week,
     Month,
     Year,
     #[default]
     All,
 }
-
-#[derive(Debug)]
-struct File {
-    path: PathBuf,
-    inner: fs::File,
-}
-
-#[derive(Debug, Deserialize)]
-struct Investor360 {
-    #[serde(rename = "Description")]
-    description: String,
-    #[serde(rename = "Symbol")]
-    symbol: String,
-    #[serde(rename = "Quantity")]
-    quantity: Option<Decimal>,
-    #[serde(rename = "Price ($)")]
-    price: Option<Decimal>,
-    #[serde(rename = "Value ($)")]
-    _value: Option<Decimal>,
-    #[serde(rename = "Assets (%)")]
-    _assets: Option<Decimal>,
-}
@@ -5,4 +5,10 @@ pub enum Screen {
     Account(usize),
     AccountSecondary(usize),
     Configuration,
+    Import,
+    Locked,
+    Passphrase,
+    Recover,
+    Search,
+    StartupError,
 }
@@ -0,0 +1,141 @@
+use std::path::Path;
+
+use anyhow::Context;
+use iced::widget::{Column, button, column, row};
+
+use crate::{
+    app::{App, Message, TITLE_FILE_PICKER, button_cell, text_cell},
+    ledger::{
+        Event, backups,
+        history::Command,
+        script::{self, Script},
+    },
+};
+
+impl App {
+    /// Runs the scripts of the ledger for the event. What they change is one
+    /// step to undo.
+    pub(super) fn run_scripts(&mut self, event: &Event) {
+        let Some(file) = &self.file else {
+            return;
+        };
+        if self.accounts.scripts.is_empty() {
+            return;
+        }
+        let scripts = self
+            .accounts
+            .scripts
+            .iter()
+            .map(|path| Script::read(&file.path, path))
+            .collect();
+        self.run(scripts, event);
+    }
+
+    /// Runs one of the scripts of the ledger from the top.
+    pub(super) fn run_script(&mut self, i: usize) {
+        let (Some(file), Some(path)) = (&self.file, self.accounts.scripts.get(i)) else {
+            return;
+        };
+        let script = Script::read(&file.path, path);
+        self.run(vec![script], &Event::Demand);
+    }
+
+    fn run(&mut self, scripts: Vec<anyhow::Result<Script>>, event: &Event) {
+        let (command, output) = script::run(&mut self.accounts, scripts, event);
+        if let Some(command) = command {
+            self.record(command);
+        }
+        self.script_output = output.lines;
+        for alert in output.alerts {
+            self.display_error(anyhow::Error::msg(alert));
+        }
+        for error in output.errors {
+            self.display_error(error);
+        }
+    }
+
+    pub(super) fn add_script(&mut self) {
+        let Some(file) = &self.file else {
+            self.display_error(anyhow::Error::msg(
+                "Save the ledger before adding scripts to it.",
+            ));
+            return;
+        };
+        let directory = backups::directory(&file.path);
+
+        let result = rfd::FileDialog::new()
+            .set_title(TITLE_FILE_PICKER)
+            .add_filter("rhai", &["rhai"])
+            .pick_file()
+            .context("You must choose a script to add.");
+
+        let file_path = match result {
+            Ok(file_path) => file_path,
+            Err(error) => {
+                self.display_error(error);
+                return;
+            }
+        };
+        if self
+            .accounts
+            .scripts
+            .iter()
+            .any(|path| script::resolve(&file.path, path) == file_path)
+        {
+            self.display_error(anyhow::Error::msg(format!(
+                "{} is already in the ledger.",
+                file_path.display()
+            )));
+            return;
+        }
+
+        // Kept relative when it can be, so the files can be moved together.
+        let path = file_path
+            .strip_prefix(directory)
+            .map_or(file_path.clone(), Path::to_path_buf);
+
+        let before = self.accounts.scripts.clone();
+        self.accounts.scripts.push(path);
+        let after = self.accounts.scripts.clone();
+        self.record(Command::SetScripts { before, after });
+    }
+
+    pub(super) fn remove_script(&mut self, i: usize) {
+        if i < self.accounts.scripts.len() {
+            let before = self.accounts.scripts.clone();
+            self.accounts.scripts.remove(i);
+            let after = self.accounts.scripts.clone();
+            self.record(Command::SetScripts { before, after });
+        }
+    }
+
+    /// What the scripts printed the last time they ran.
+    pub(super) fn script_output(&self) -> Column<'_, Message> {
+        self.script_output
+            .iter()
+            .fold(Column::new(), |column, line| column.push(text_cell(line)))
+    }
+
+    #[rustfmt::skip]
+    pub(super) fn config_scripts(&self) -> Column<'_, Message> {
+        let mut scripts = column![text_cell("Scripts, run on import, after getting prices or here:")];
+        for (i, path) in self.accounts.scripts.iter().enumerate() {
+            scripts = scripts.push(row![
+                button_cell(button("Run").on_press(Message::RunScript(i))),
+                button_cell(button("Remove").on_press(Message::RemoveScript(i))),
+                text_cell(path.display().to_string()),
+            ]);
+        }
+
+        let mut run_all = button("Run Scripts");
+        if !self.accounts.scripts.is_empty() {
+            run_all = run_all.on_press(Message::RunScripts);
+        }
+        let add = row![
+            button_cell(button("Add Script").on_press(Message::AddScript)),
+            button_cell(run_all),
+        ];
+
+        column![scripts, add, self.script_output()]
+    }
+}
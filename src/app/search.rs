@@ -0,0 +1,101 @@
+use iced::{
+    Alignment,
+    widget::{Column, Scrollable, button, column, row, text_input},
+};
+
+use crate::{
+    app::{
+        App, Message, PADDING, ROW_SPACING, Screen, button_cell, number_cell, text_cell,
+        text_cell_red,
+    },
+    ledger::Query,
+};
+
+impl App {
+    /// Shows the search screen with what the query finds now.
+    pub(super) fn open_search(&mut self) {
+        self.screen = Screen::Search;
+        self.search();
+    }
+
+    /// Runs the query in the search box again, e.g. after the ledger changed.
+    /// An empty one finds nothing rather than everything.
+    pub(super) fn search(&mut self) {
+        if self.search.trim().is_empty() {
+            self.found = Vec::new();
+            return;
+        }
+        match self.search.parse::<Query>() {
+            Ok(query) => self.found = query.run(&self.accounts),
+            Err(error) => {
+                self.found = Vec::new();
+                self.display_error(error);
+            }
+        }
+    }
+
+    #[rustfmt::skip]
+    pub(super) fn search_view(&self) -> Scrollable<'_, Message> {
+        let search = text_input("comment:/AMAZON/ abs>50 date:2025 currency:USD", &self.search)
+            .on_input(Message::ChangeSearch)
+            .on_paste(Message::ChangeSearch)
+            .on_submit(Message::SubmitSearch);
+
+        let mut col = Column::new().push(
+            row![
+                search,
+                button_cell(button("Search").on_press(Message::SubmitSearch)),
+            ].padding(PADDING).spacing(ROW_SPACING),
+        );
+
+        for found in &self.found {
+            let mut col_1 = column![text_cell("Date")];
+            let mut col_2 = column![text_cell("Account")];
+            let mut col_3 = column![text_cell("Amount")].align_x(Alignment::End);
+            let mut col_4 = column![text_cell("Total")].align_x(Alignment::End);
+            let mut col_5 = column![text_cell("Comment")];
+
+            for (tx, i) in found.txs.txs.iter().zip(&found.accounts) {
+                let mut amount = tx.amount;
+                let mut total = tx.balance;
+                amount.rescale(2);
+                total.rescale(2);
+
+                col_1 = col_1.push(text_cell(tx.date.format("%Y-%m-%d").to_string()));
+                col_2 = col_2.push(
+                    row![button(self.accounts[*i].name.as_str()).on_press(Message::SelectAccount(*i))]
+                        .padding(PADDING),
+                );
+                col_3 = col_3.push(number_cell(amount));
+                col_4 = col_4.push(number_cell(total));
+                col_5 = col_5.push(text_cell(&tx.comment));
+            }
+
+            let mut total = found.total();
+            total.rescale(2);
+            col = col
+                .push(text_cell(found.txs.currency.to_string()))
+                .push(row![col_1, col_2, col_3, col_4, col_5].spacing(ROW_SPACING))
+                .push(row![
+                    text_cell(format!("{} transactions, total: ", found.txs.txs.len())),
+                    number_cell(total),
+                ].spacing(ROW_SPACING));
+        }
+
+        let mut column_errors = Column::new();
+        if let Some(errors) = &self.errors {
+            for error in errors.iter() {
+                column_errors = column_errors.push(text_cell_red(error.to_string()));
+            }
+        }
+
+        col = col.push(column_errors).push(
+            row![
+                button("Back").on_press(Message::Back),
+                button("Exit").on_press(Message::Exit),
+            ].spacing(ROW_SPACING),
+        );
+
+        Scrollable::new(col)
+    }
+}
@@ -0,0 +1,46 @@
+use std::{fs, path::PathBuf};
+
+use ron::error::SpannedError;
+
+/// Why the file given on the command line couldn't be opened.
+#[derive(Debug)]
+pub struct StartupError {
+    pub path: PathBuf,
+    pub error: anyhow::Error,
+    pub location: Option<Location>,
+}
+
+/// Where the RON in the file stopped parsing.
+#[derive(Debug)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+    /// The text of the line, when the file isn't encrypted.
+    pub text: Option<String>,
+}
+
+impl StartupError {
+    pub fn new(path: PathBuf, error: anyhow::Error) -> Self {
+        let location = error
+            .chain()
+            .find_map(|error| error.downcast_ref::<SpannedError>())
+            .map(|error| {
+                let line = error.span.start.line;
+                let text = fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|ron| ron.lines().nth(line.saturating_sub(1)).map(str::to_string));
+
+                Location {
+                    line,
+                    column: error.span.start.col,
+                    text,
+                }
+            });
+
+        Self {
+            path,
+            error,
+            location,
+        }
+    }
+}
@@ -1,10 +1,12 @@
 use std::io::Write;
 
 use clap::{CommandFactory, Parser};
-use financial_accounts::app::{App, command_line::Args};
-use iced::window;
-use image::ImageFormat;
+use financial_accounts::{
+    command_line::{self, Args},
+    ledger,
+};
 
+#[cfg(feature = "gui")]
 const MONEY: &[u8] = include_bytes!("financial-accounts_256x256.png");
 
 fn main() -> anyhow::Result<()> {
@@ -24,7 +26,26 @@ fn main() -> anyhow::Result<()> {
         return Ok(());
     }
 
-    iced::application(App::default, App::update, App::view)
+    if let Some(mut paths) = args.convert {
+        let to = paths.pop().expect("clap requires two paths");
+        let from = paths.pop().expect("clap requires two paths");
+        return ledger::convert(from, to);
+    }
+
+    if let Some(command) = args.command {
+        return command_line::run(command);
+    }
+
+    run_window(args)
+}
+
+#[cfg(feature = "gui")]
+fn run_window(args: Args) -> anyhow::Result<()> {
+    use financial_accounts::app::App;
+    use iced::window;
+    use image::ImageFormat;
+
+    iced::application(move || App::from_args(&args), App::update, App::view)
         .title("Financial Accounts")
         .window(window::Settings {
             icon: Some(iced::window::icon::from_file_data(
@@ -34,11 +55,20 @@ fn main() -> anyhow::Result<()> {
             ..window::Settings::default()
         })
         .theme(App::theme)
+        .subscription(App::subscription)
+        .exit_on_close_request(false)
         .run()?;
 
     Ok(())
 }
 
+#[cfg(not(feature = "gui"))]
+fn run_window(_args: Args) -> anyhow::Result<()> {
+    Err(anyhow::Error::msg(
+        "This build has no window, it was built without the \"gui\" feature. Run one of the commands, see --help.",
+    ))
+}
+
 pub const COPYRIGHT: &str = r#".SH COPYRIGHT
 Copyright (c) 2023-2025 David Lawrence Campbell
 
@@ -0,0 +1,52 @@
+use std::{env, path::Path};
+
+use tiny_http::{Method, Server};
+
+use super::{
+    headless,
+    serve::{self, Reply, TOKEN},
+};
+
+/// Serves `/metrics` for Prometheus until the process is stopped. The ledger is
+/// read without locking it and loaded again whenever it changes, e.g. when the
+/// window or `get-prices` saves new prices. Requests need the token only if
+/// one is set.
+pub fn run(path: &Path, address: &str) -> anyhow::Result<()> {
+    let token = env::var(TOKEN).ok().filter(|token| !token.is_empty());
+    let mut ledger = headless::load_read_only(path)?;
+    let server = Server::http(address)
+        .map_err(|error| anyhow::Error::msg(format!("Can't listen on {address}: {error}")))?;
+    eprintln!("Exporting {} on http://{address}/metrics", path.display());
+
+    for request in server.incoming_requests() {
+        let reply = if token
+            .as_ref()
+            .is_some_and(|token| !serve::authorized(&request, token))
+        {
+            Reply::error(401, "The request needs \"Authorization: Bearer TOKEN\".")
+        } else if request.url().split('?').next() != Some("/metrics") {
+            Reply::error(404, "There is nothing at that path.")
+        } else if request.method() != &Method::Get {
+            Reply::error(405, "That can't be done to that path.")
+        } else {
+            // A ledger that can't be loaded, e.g. one that is being written,
+            // leaves the last one that could in place.
+            match ledger.changed_on_disk() {
+                Ok(false) => Reply::metrics(ledger.metrics()),
+                Ok(true) => match headless::load_read_only(path) {
+                    Ok(new_ledger) => {
+                        ledger = new_ledger;
+                        Reply::metrics(ledger.metrics())
+                    }
+                    Err(error) => Reply::error(500, &format!("{error:#}")),
+                },
+                Err(error) => Reply::error(500, &format!("{error:#}")),
+            }
+        };
+
+        if let Err(error) = request.respond(reply.into_response()) {
+            eprintln!("{error}");
+        }
+    }
+    Ok(())
+}
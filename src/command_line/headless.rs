@@ -0,0 +1,293 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use rust_decimal_macros::dec;
+
+use super::{Command, exporter, serve};
+use crate::ledger::{self, Event, Ledger, Output, Transaction};
+
+/// Where the passphrase of an encrypted ledger is read from.
+const PASSPHRASE: &str = "FINANCIAL_ACCOUNTS_PASSPHRASE";
+
+/// Runs the command on the ledger and prints what it asks for. Commands that
+/// change the ledger save it, so they can't run while it is open elsewhere.
+pub fn run(command: Command) -> anyhow::Result<()> {
+    match command {
+        Command::Balance { ledger } => {
+            let ledger = load_read_only(&ledger)?;
+            for currency in ledger.accounts().currencies() {
+                let mut balance = ledger.accounts().balance(&currency);
+                balance.rescale(2);
+                println!("{}\t{balance}", currency.symbol());
+            }
+        }
+        Command::Accounts { ledger } => {
+            let ledger = load_read_only(&ledger)?;
+            for account in &ledger.accounts().inner {
+                let mut balance = account.balance_1st();
+                balance.rescale(2);
+                print!(
+                    "{}\t{}\t{balance}",
+                    account.name,
+                    account.txs_1st.currency.symbol()
+                );
+                if let Some(txs_2nd) = &account.txs_2nd {
+                    print!("\t{}\t{}", txs_2nd.currency.symbol(), txs_2nd.balance());
+                }
+                println!();
+            }
+        }
+        Command::Txs {
+            ledger,
+            account,
+            secondary,
+        } => {
+            let ledger = load_read_only(&ledger)?;
+            let account = ledger.account(&account)?;
+            if secondary && account.txs_2nd.is_none() {
+                return Err(ledger::no_secondary(&account.name));
+            }
+            for tx in account.txs(secondary) {
+                println!(
+                    "{}\t{}\t{}\t{}",
+                    tx.date.format("%Y-%m-%d"),
+                    tx.amount,
+                    tx.balance,
+                    tx.comment
+                );
+            }
+        }
+        Command::Query { ledger, query } => print_query(&ledger, &query)?,
+        Command::AddTx {
+            ledger,
+            account,
+            amount,
+            balance,
+            date,
+            comment,
+            secondary,
+        } => {
+            let mut ledger = load(&ledger)?;
+            let tx = Transaction {
+                amount: amount.unwrap_or(dec!(0)),
+                balance: balance.unwrap_or(dec!(0)),
+                comment: comment.trim().to_string(),
+                date: ledger::parse_date(&date)?,
+                snapshot: balance.is_some(),
+            };
+            ledger.add_tx(&account, secondary, tx)?;
+            ledger.save()?;
+        }
+        Command::ImportBoa {
+            ledger,
+            account,
+            csv,
+        } => import_boa(&ledger, account, csv)?,
+        Command::ImportJournal { ledger, journal } => import_journal(&ledger, &journal)?,
+        Command::GetPrices { ledger } => get_prices(&ledger)?,
+        Command::RunScript { ledger, script } => run_script(&ledger, script.as_deref())?,
+        Command::Encrypt { ledger } => encrypt(&ledger, env::var(PASSPHRASE).ok())?,
+        Command::Decrypt { ledger } => decrypt(&ledger, env::var(PASSPHRASE).ok().as_deref())?,
+        Command::Report { ledger, format } => {
+            let ledger = load_read_only(&ledger)?;
+            print!("{}", ledger.report().to_format(format)?);
+        }
+        Command::Export { ledger, format } => {
+            let ledger = load_read_only(&ledger)?;
+            print!("{}", ledger.export(format));
+        }
+        Command::Serve { ledger, address } => serve::run(&ledger, &address)?,
+        Command::Exporter { ledger, address } => exporter::run(&ledger, &address)?,
+    }
+    Ok(())
+}
+
+/// Prints the transactions found, then their total, for each currency.
+fn print_query(ledger: &Path, query: &str) -> anyhow::Result<()> {
+    let passphrase = env::var(PASSPHRASE).ok();
+    let (names, found) = Ledger::query_file(ledger, passphrase.as_deref(), query)?;
+    for found in found {
+        for (tx, i) in found.txs.txs.iter().zip(&found.accounts) {
+            println!(
+                "{}\t{}\t{}\t{}\t{}",
+                tx.date.format("%Y-%m-%d"),
+                names[*i],
+                tx.amount,
+                tx.balance,
+                tx.comment
+            );
+        }
+        println!("total\t{}\t{}", found.txs.currency.symbol(), found.total());
+    }
+    Ok(())
+}
+
+/// Imports the CSV, then runs the scripts' `on_import`.
+fn import_boa(ledger: &Path, account: String, csv: PathBuf) -> anyhow::Result<()> {
+    let mut ledger = load(ledger)?;
+    let (imported, skipped) = ledger.import_boa(&account, csv)?;
+    let output = ledger.run_scripts(&Event::Import(vec![account]))?;
+    ledger.save()?;
+    println!("Imported {imported} row(s), left out {skipped} probable duplicate(s).");
+    print_output(&output)
+}
+
+/// Imports the journal, then runs the scripts' `on_import`.
+fn import_journal(ledger: &Path, journal: &Path) -> anyhow::Result<()> {
+    let mut ledger = load(ledger)?;
+    let names = ledger.import_journal(journal)?;
+    let output = ledger.run_scripts(&Event::Import(names.clone()))?;
+    ledger.save()?;
+    println!("Added {} account(s): {}", names.len(), names.join(", "));
+    print_output(&output)
+}
+
+/// Runs the script, or the scripts of the ledger, from the top.
+fn run_script(ledger: &Path, script: Option<&Path>) -> anyhow::Result<()> {
+    let mut ledger = load(ledger)?;
+    let output = match script {
+        Some(script) => ledger.run_script(script)?,
+        None => ledger.run_scripts(&Event::Demand)?,
+    };
+    ledger.save()?;
+    print_output(&output)
+}
+
+/// Adds every price that can be got, then fails if some couldn't.
+fn get_prices(ledger: &Path) -> anyhow::Result<()> {
+    let mut ledger = load(ledger)?;
+    let errors = block_on(ledger.get_prices())??;
+    let output = ledger.run_scripts(&Event::Prices)?;
+    ledger.save()?;
+    for error in &errors {
+        eprintln!("{error:#}");
+    }
+    print_output(&output)?;
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::Error::msg(format!(
+            "{} price(s) couldn't be got.",
+            errors.len()
+        )))
+    }
+}
+
+/// Encrypts the ledger, which is opened with the same passphrase if it is
+/// encrypted already.
+fn encrypt(ledger: &Path, passphrase: Option<String>) -> anyhow::Result<()> {
+    let passphrase = passphrase
+        .filter(|passphrase| !passphrase.is_empty())
+        .with_context(|| format!("Set {PASSPHRASE} to the passphrase to encrypt it with."))?;
+    let mut ledger = Ledger::open(ledger.to_path_buf(), Some(&passphrase))?;
+    ledger.encrypt(passphrase)?;
+    ledger.close()
+}
+
+fn decrypt(ledger: &Path, passphrase: Option<&str>) -> anyhow::Result<()> {
+    let mut ledger = Ledger::open(ledger.to_path_buf(), passphrase)?;
+    ledger.decrypt()?;
+    ledger.close()
+}
+
+/// Runs the future to the end on a runtime of its own, which the requests for
+/// prices need.
+pub(super) fn block_on<F: Future>(future: F) -> anyhow::Result<F::Output> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    Ok(runtime.block_on(future))
+}
+
+/// Prints what the scripts printed, and their alerts and errors to stderr, then
+/// fails if a script did.
+fn print_output(output: &Output) -> anyhow::Result<()> {
+    for line in &output.lines {
+        println!("{line}");
+    }
+    for alert in &output.alerts {
+        eprintln!("{alert}");
+    }
+    for error in &output.errors {
+        eprintln!("{error:#}");
+    }
+    if output.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow::Error::msg(format!(
+            "{} script(s) failed.",
+            output.errors.len()
+        )))
+    }
+}
+
+pub(super) fn load(ledger: &Path) -> anyhow::Result<Ledger> {
+    let passphrase = env::var(PASSPHRASE).ok();
+    Ledger::open(ledger.to_path_buf(), passphrase.as_deref())
+}
+
+/// Loads the ledger without locking it, so reading works while it is open
+/// in the window.
+pub(super) fn load_read_only(ledger: &Path) -> anyhow::Result<Ledger> {
+    let passphrase = env::var(PASSPHRASE).ok();
+    Ledger::open_read_only(ledger.to_path_buf(), passphrase.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::{Crypto, Currency, Fiat, NeedsPassphrase, testing};
+
+    #[test]
+    fn block_on_gives_the_future_a_runtime() {
+        let entered = block_on(async { tokio::runtime::Handle::try_current().is_ok() });
+        assert!(entered.unwrap());
+    }
+
+    /// Getting prices makes requests, which panicked without a runtime.
+    #[test]
+    fn get_prices_runs_without_a_window() {
+        let path = testing::directory("headless-get-prices").join("ledger.ron");
+        let mut ledger = Ledger::create(path.clone()).unwrap();
+        let bitcoin = Crypto {
+            currency: Fiat::Usd,
+            description: "Bitcoin".to_string(),
+            symbol: "BTC".to_string(),
+        };
+        ledger
+            .add_account("Bitcoin", Currency::Crypto(bitcoin))
+            .unwrap();
+        ledger.save().unwrap();
+        ledger.close().unwrap();
+
+        // Without a network the price can't be got, which is an error rather
+        // than a panic.
+        if let Err(error) = run(Command::GetPrices { ledger: path }) {
+            assert_eq!(error.to_string(), "1 price(s) couldn't be got.");
+        }
+    }
+
+    #[test]
+    fn encrypt_and_decrypt_without_a_window() {
+        let path = testing::directory("headless-encrypt").join("ledger.ron");
+        let mut ledger = Ledger::create(path.clone()).unwrap();
+        ledger
+            .add_account("Checking", Currency::Fiat(Fiat::Usd))
+            .unwrap();
+        ledger.save().unwrap();
+        ledger.close().unwrap();
+
+        assert!(encrypt(&path, None).is_err());
+        encrypt(&path, Some("correct horse".to_string())).unwrap();
+        let error = Ledger::open_read_only(path.clone(), None).unwrap_err();
+        assert!(error.is::<NeedsPassphrase>());
+
+        assert!(decrypt(&path, Some("wrong horse")).is_err());
+        decrypt(&path, Some("correct horse")).unwrap();
+        let ledger = Ledger::open_read_only(path, None).unwrap();
+        assert!(ledger.account("Checking").is_ok());
+    }
+}
@@ -0,0 +1,215 @@
+mod exporter;
+mod headless;
+mod serve;
+
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+use rust_decimal::Decimal;
+
+use crate::ledger::{Export, Format};
+
+#[derive(Clone, Debug, Parser)]
+#[command(version, about)]
+pub struct Args {
+    /// Load FILE
+    #[arg(long, value_name = "FILE", exclusive = true)]
+    pub load: Option<String>,
+
+    /// Create a new FILE
+    #[arg(long, value_name = "FILE", exclusive = true)]
+    pub new: Option<String>,
+
+    /// Convert the ledger FROM to TO, e.g. from RON to SQLite, as chosen by the
+    /// extensions
+    #[arg(long, num_args = 2, value_names = ["FROM", "TO"], exclusive = true)]
+    pub convert: Option<Vec<PathBuf>>,
+
+    /// Build the manpage
+    #[arg(long)]
+    pub man: bool,
+
+    /// Work on a ledger without opening a window
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// What can be done without opening a window. The passphrase of an encrypted
+/// ledger is read from `FINANCIAL_ACCOUNTS_PASSPHRASE`.
+#[derive(Clone, Debug, Subcommand)]
+pub enum Command {
+    /// Print the balance of every currency in LEDGER
+    Balance {
+        /// The ledger FILE
+        ledger: PathBuf,
+    },
+
+    /// Print the accounts in LEDGER with their balances
+    Accounts {
+        /// The ledger FILE
+        ledger: PathBuf,
+    },
+
+    /// Print the transactions of ACCOUNT
+    Txs {
+        /// The ledger FILE
+        ledger: PathBuf,
+        /// The name of the account
+        account: String,
+        /// The transactions of the secondary currency, e.g. a metal's quantity
+        #[arg(long)]
+        secondary: bool,
+    },
+
+    /// Print the transactions of every account that match QUERY, e.g.
+    /// `comment:/AMAZON/ abs>50 date:2025 currency:USD`, with a running total and
+    /// the total of each currency
+    Query {
+        /// The ledger FILE
+        ledger: PathBuf,
+        /// The terms to match, see the README
+        query: String,
+    },
+
+    /// Add a transaction to ACCOUNT
+    #[command(allow_negative_numbers = true)]
+    AddTx {
+        /// The ledger FILE
+        ledger: PathBuf,
+        /// The name of the account
+        account: String,
+        /// The amount of the transaction
+        #[arg(long, required_unless_present = "balance", conflicts_with = "balance")]
+        amount: Option<Decimal>,
+        /// The balance after the transaction, the amount is worked out from it
+        #[arg(long)]
+        balance: Option<Decimal>,
+        /// The DATE as YYYY-MM-DD, today if not given
+        #[arg(long, value_name = "DATE", default_value = "")]
+        date: String,
+        #[arg(long, default_value = "")]
+        comment: String,
+        /// Add it to the transactions of the secondary currency
+        #[arg(long)]
+        secondary: bool,
+    },
+
+    /// Import a Bank of America CSV file into ACCOUNT, leaving out the
+    /// probable duplicates as the review screen would
+    ImportBoa {
+        /// The ledger FILE
+        ledger: PathBuf,
+        /// The name of the account
+        account: String,
+        /// The CSV file
+        csv: PathBuf,
+    },
+
+    /// Import a ledger-cli, hledger or Beancount (.beancount) JOURNAL, adding
+    /// an account for each asset and liability account in it
+    ImportJournal {
+        /// The ledger FILE
+        ledger: PathBuf,
+        /// The journal file
+        journal: PathBuf,
+    },
+
+    /// Get the prices of the metals, stocks and crypto held and add them
+    GetPrices {
+        /// The ledger FILE
+        ledger: PathBuf,
+    },
+
+    /// Run the rhai SCRIPT on LEDGER, or the scripts of LEDGER if none is
+    /// given, and keep what they change
+    RunScript {
+        /// The ledger FILE
+        ledger: PathBuf,
+        /// The script FILE, relative to the ledger's directory unless absolute
+        script: Option<PathBuf>,
+    },
+
+    /// Encrypt LEDGER with the passphrase in `FINANCIAL_ACCOUNTS_PASSPHRASE`, and
+    /// its backups. It is saved encrypted from then on
+    Encrypt {
+        /// The ledger FILE
+        ledger: PathBuf,
+    },
+
+    /// Save LEDGER, encrypted with the passphrase in
+    /// `FINANCIAL_ACCOUNTS_PASSPHRASE`, as plain text again. The backups stay as
+    /// they are
+    Decrypt {
+        /// The ledger FILE
+        ledger: PathBuf,
+    },
+
+    /// Print how every account, currency and group changed over the last
+    /// week, month and year
+    Report {
+        /// The ledger FILE
+        ledger: PathBuf,
+        #[arg(long, value_enum, default_value_t = Format::Text)]
+        format: Format,
+    },
+
+    /// Print LEDGER as a plain-text accounting journal, e.g. to check it with
+    /// hledger's reports
+    Export {
+        /// The ledger FILE
+        ledger: PathBuf,
+        #[arg(long, value_enum, default_value_t = Export::Ledger)]
+        format: Export,
+    },
+
+    /// Serve LEDGER as JSON over HTTP until stopped. Requests need the header
+    /// "Authorization: Bearer TOKEN", with TOKEN read from
+    /// `FINANCIAL_ACCOUNTS_TOKEN`
+    Serve {
+        /// The ledger FILE
+        ledger: PathBuf,
+        /// The address and port to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        address: String,
+    },
+
+    /// Serve the balances and prices in LEDGER to Prometheus at /metrics until
+    /// stopped. The ledger is read without locking it, and read again when it
+    /// changes. If `FINANCIAL_ACCOUNTS_TOKEN` is set requests need it, as for
+    /// serve
+    Exporter {
+        /// The ledger FILE
+        ledger: PathBuf,
+        /// The address and port to listen on
+        #[arg(long, default_value = "127.0.0.1:9898")]
+        address: String,
+    },
+}
+
+#[derive(Debug)]
+pub enum File {
+    Load(PathBuf),
+    New(PathBuf),
+    None,
+}
+
+#[must_use]
+pub fn get_configuration_file(args: &Args) -> File {
+    if let Some(arg) = &args.load {
+        File::Load(PathBuf::from(arg))
+    } else if let Some(arg) = &args.new {
+        File::New(PathBuf::from(arg))
+    } else {
+        File::None
+    }
+}
+
+/// Runs a command on a ledger without opening a window.
+///
+/// # Errors
+///
+/// Fails if the ledger can't be loaded or saved, or the command can't be done,
+/// e.g. there is no account with the name given.
+pub fn run(command: Command) -> anyhow::Result<()> {
+    headless::run(command)
+}
@@ -0,0 +1,513 @@
+use std::{
+    env,
+    io::Read,
+    panic::{self, AssertUnwindSafe},
+    path::Path,
+};
+
+use anyhow::Context;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use super::headless;
+use crate::ledger::{self, Account, Changed, Event, Ledger, Line, Transaction};
+
+/// Where the token requests have to carry is read from.
+pub(super) const TOKEN: &str = "FINANCIAL_ACCOUNTS_TOKEN";
+
+/// The most of a request body that is read.
+const BODY_LIMIT: u64 = 64 * 1024;
+
+/// Serves the ledger as JSON until the process is stopped. The ledger stays
+/// locked the whole time, and every change is saved before it is answered.
+///
+/// A change that can't be saved is answered with the error and the ledger is
+/// loaded again from the file for the next request. While it can't be loaded,
+/// requests are answered with why and it is tried again for each one.
+pub fn run(path: &Path, address: &str) -> anyhow::Result<()> {
+    let token = env::var(TOKEN)
+        .ok()
+        .filter(|token| !token.is_empty())
+        .with_context(|| format!("Set {TOKEN} to the token requests have to carry."))?;
+    let mut ledger = Some(headless::load(path)?);
+    let server = Server::http(address)
+        .map_err(|error| anyhow::Error::msg(format!("Can't listen on {address}: {error}")))?;
+    eprintln!("Serving {} on http://{address}", path.display());
+
+    for mut request in server.incoming_requests() {
+        let reply = answer(&mut ledger, path, &token, &mut request);
+        if let Err(error) = request.respond(reply.into_response()) {
+            eprintln!("{error}");
+        }
+    }
+    Ok(())
+}
+
+/// Answers a request if it carries the token, and saves what it changed first.
+fn answer(ledger: &mut Option<Ledger>, path: &Path, token: &str, request: &mut Request) -> Reply {
+    if !authorized(request, token) {
+        return Reply::error(401, "The request needs \"Authorization: Bearer TOKEN\".");
+    }
+    let reply = match loaded(ledger, path) {
+        Ok(ledger) => handle(ledger, request),
+        Err(error) => return Reply::error(503, &format!("{error:#}")),
+    };
+
+    if reply.changed
+        && let Some(current) = ledger
+        && let Err(error) = current.save()
+    {
+        eprintln!("Saving {}: {error:#}", path.display());
+        // What is in memory is no longer what is in the file, so start over
+        // from the file.
+        if let Some(old) = ledger.take()
+            && let Err(error) = old.close()
+        {
+            eprintln!("Closing {}: {error:#}", path.display());
+        }
+        let status = if error.is::<Changed>() { 409 } else { 500 };
+        return Reply::error(status, &format!("{error:#}"));
+    }
+    reply
+}
+
+/// The ledger, loaded again from the file if a failed save let it go.
+fn loaded<'a>(ledger: &'a mut Option<Ledger>, path: &Path) -> anyhow::Result<&'a mut Ledger> {
+    if let Some(ledger) = ledger {
+        return Ok(ledger);
+    }
+
+    let reloaded = headless::load(path).inspect_err(|error| {
+        eprintln!("Loading {}: {error:#}", path.display());
+    })?;
+    Ok(ledger.insert(reloaded))
+}
+
+fn handle(ledger: &mut Ledger, request: &mut Request) -> Reply {
+    let url = request.url().to_string();
+    let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+    let Some(segments) = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(percent_decode)
+        .collect::<Option<Vec<_>>>()
+    else {
+        return Reply::error(400, "The path isn't valid percent-encoded UTF-8.");
+    };
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+    match (request.method(), segments.as_slice()) {
+        (Method::Get, ["accounts"]) => Reply::json(
+            &ledger
+                .accounts()
+                .inner
+                .iter()
+                .map(|account| AccountJson::new(ledger, account))
+                .collect::<Vec<_>>(),
+        ),
+        (Method::Get, ["accounts", name]) => match ledger.account(name) {
+            Ok(account) => Reply::json(&AccountJson::new(ledger, account)),
+            Err(error) => Reply::error(404, &error.to_string()),
+        },
+        (Method::Get, ["accounts", name, "txs"]) => {
+            let secondary = query.split('&').any(|pair| pair == "secondary=true");
+            match ledger.account(name) {
+                Ok(account) if secondary && account.txs_2nd.is_none() => {
+                    Reply::error(400, &ledger::no_secondary(name).to_string())
+                }
+                Ok(account) => Reply::json(
+                    &account
+                        .txs(secondary)
+                        .iter()
+                        .map(TxJson::new)
+                        .collect::<Vec<_>>(),
+                ),
+                Err(error) => Reply::error(404, &error.to_string()),
+            }
+        }
+        (Method::Post, ["accounts", name, "txs"]) => add_tx(ledger, name, request),
+        (Method::Get, ["balances"]) => Reply::json(&ledger.report().totals),
+        (Method::Get, ["groups"]) => {
+            let report = ledger.report();
+            Reply::json(
+                &ledger
+                    .accounts()
+                    .groups
+                    .iter()
+                    .zip(&report.groups)
+                    .map(|(group, line)| GroupJson {
+                        line,
+                        accounts: group
+                            .members
+                            .iter()
+                            .map(|i| ledger.accounts()[*i].name.as_str())
+                            .collect(),
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        }
+        (Method::Get, ["report"]) => Reply::json(&ledger.report()),
+        (Method::Get, ["metrics"]) => Reply::metrics(ledger.metrics()),
+        (Method::Post, ["prices"]) => get_prices(ledger),
+        (
+            _,
+            [
+                "accounts" | "balances" | "groups" | "report" | "metrics" | "prices",
+                ..,
+            ],
+        ) => Reply::error(405, "That can't be done to that path."),
+        _ => Reply::error(404, "There is nothing at that path."),
+    }
+}
+
+fn add_tx(ledger: &mut Ledger, account: &str, request: &mut Request) -> Reply {
+    if let Err(error) = ledger.account(account) {
+        return Reply::error(404, &error.to_string());
+    }
+
+    let mut body = Vec::new();
+    if let Err(error) = request
+        .as_reader()
+        .take(BODY_LIMIT + 1)
+        .read_to_end(&mut body)
+    {
+        return Reply::error(400, &error.to_string());
+    }
+    if body.len() as u64 > BODY_LIMIT {
+        return Reply::error(413, "The body is more than 64 KiB.");
+    }
+    let new_tx: NewTx = match serde_json::from_slice(&body) {
+        Ok(new_tx) => new_tx,
+        Err(error) => return Reply::error(400, &error.to_string()),
+    };
+    if new_tx.amount.is_some() == new_tx.balance.is_some() {
+        return Reply::error(400, "Give either an amount or a balance.");
+    }
+    let date = match ledger::parse_date(&new_tx.date) {
+        Ok(date) => date,
+        Err(error) => return Reply::error(400, error.to_string().trim_end()),
+    };
+
+    let tx = Transaction {
+        amount: new_tx.amount.unwrap_or(dec!(0)),
+        balance: new_tx.balance.unwrap_or(dec!(0)),
+        comment: new_tx.comment.trim().to_string(),
+        date,
+        snapshot: new_tx.balance.is_some(),
+    };
+    match ledger.add_tx(account, new_tx.secondary, tx) {
+        Ok(()) => {
+            let mut reply = match ledger.account(account) {
+                Ok(account) => Reply::json(&AccountJson::new(ledger, account)),
+                Err(error) => Reply::error(500, &error.to_string()),
+            };
+            reply.status = 201;
+            reply.changed = true;
+            reply
+        }
+        Err(error) => Reply::error(400, &error.to_string()),
+    }
+}
+
+/// Gets the prices, then runs the scripts' `on_prices`. Whatever goes wrong,
+/// even a panic while getting them, is answered rather than ending the server.
+fn get_prices(ledger: &mut Ledger) -> Reply {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| headless::block_on(ledger.get_prices())))
+        .unwrap_or_else(|_| Err(anyhow::Error::msg("Getting the prices panicked.")))
+        .flatten()
+        .and_then(|errors| Ok((errors, ledger.run_scripts(&Event::Prices)?)));
+    match result {
+        Ok((mut errors, output)) => {
+            errors.extend(output.errors);
+            let errors: Vec<String> = errors.iter().map(|error| format!("{error:#}")).collect();
+            let mut reply = Reply::json(&serde_json::json!({
+                "errors": errors,
+                "output": output.lines,
+                "alerts": output.alerts,
+            }));
+            reply.changed = true;
+            reply
+        }
+        Err(error) => Reply::error(500, &format!("{error:#}")),
+    }
+}
+
+/// Whether the request carries the token, compared in constant time.
+pub(super) fn authorized(request: &Request, token: &str) -> bool {
+    request.headers().iter().any(|header| {
+        header.field.equiv("Authorization")
+            && header
+                .value
+                .as_str()
+                .strip_prefix("Bearer ")
+                .is_some_and(|given| {
+                    given.len() == token.len()
+                        && given
+                            .bytes()
+                            .zip(token.bytes())
+                            .fold(0, |diff, (a, b)| diff | (a ^ b))
+                            == 0
+                })
+    })
+}
+
+/// Decodes `%XX` escapes, e.g. `Credit%20Card`. `None` if an escape is
+/// malformed or the result isn't UTF-8.
+fn percent_decode(text: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        if byte == b'%' {
+            let hex = tail.get(..2)?;
+            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                return None;
+            }
+            bytes.push(u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok()?);
+            rest = &tail[2..];
+        } else {
+            bytes.push(byte);
+            rest = tail;
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+pub(super) struct Reply {
+    status: u16,
+    content_type: &'static str,
+    body: String,
+    /// The ledger was changed and has to be saved before the reply is sent.
+    changed: bool,
+}
+
+impl Reply {
+    fn json<T: Serialize>(value: &T) -> Self {
+        match serde_json::to_string_pretty(value) {
+            Ok(body) => Self {
+                status: 200,
+                content_type: "application/json",
+                body,
+                changed: false,
+            },
+            Err(error) => Self::error(500, &error.to_string()),
+        }
+    }
+
+    /// In the Prometheus text format.
+    pub(super) const fn metrics(body: String) -> Self {
+        Self {
+            status: 200,
+            content_type: "text/plain; version=0.0.4",
+            body,
+            changed: false,
+        }
+    }
+
+    pub(super) fn error(status: u16, error: &str) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: serde_json::json!({ "error": error }).to_string(),
+            changed: false,
+        }
+    }
+
+    pub(super) fn into_response(self) -> Response<std::io::Cursor<Vec<u8>>> {
+        let content_type =
+            Header::from_bytes("Content-Type", self.content_type).expect("the header is valid");
+        Response::from_string(self.body)
+            .with_status_code(self.status)
+            .with_header(content_type)
+    }
+}
+
+#[derive(Serialize)]
+struct AccountJson<'a> {
+    name: &'a str,
+    /// The file the account is in, `main` for the ledger itself.
+    file: String,
+    currency: String,
+    balance: Decimal,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    currency_2nd: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    balance_2nd: Option<Decimal>,
+}
+
+impl<'a> AccountJson<'a> {
+    fn new(ledger: &Ledger, account: &'a Account) -> Self {
+        Self {
+            name: &account.name,
+            file: ledger.accounts().source_name(account.source),
+            currency: account.txs_1st.currency.symbol(),
+            balance: account.balance_1st(),
+            currency_2nd: account
+                .txs_2nd
+                .as_ref()
+                .map(|txs_2nd| txs_2nd.currency.symbol()),
+            balance_2nd: account.balance_2nd(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TxJson<'a> {
+    date: String,
+    amount: Decimal,
+    balance: Decimal,
+    comment: &'a str,
+    snapshot: bool,
+}
+
+impl<'a> TxJson<'a> {
+    fn new(tx: &'a Transaction) -> Self {
+        Self {
+            date: tx.date.format("%Y-%m-%d").to_string(),
+            amount: tx.amount,
+            balance: tx.balance,
+            comment: &tx.comment,
+            snapshot: tx.snapshot,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct GroupJson<'a> {
+    #[serde(flatten)]
+    line: &'a Line,
+    accounts: Vec<&'a str>,
+}
+
+/// The body of a transaction to add, like the arguments of `add-tx`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NewTx {
+    amount: Option<Decimal>,
+    balance: Option<Decimal>,
+    /// YYYY-MM-DD, today if empty.
+    #[serde(default)]
+    date: String,
+    #[serde(default)]
+    comment: String,
+    #[serde(default)]
+    secondary: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use tiny_http::TestRequest;
+
+    use super::*;
+    use crate::ledger::{Crypto, Currency, Fiat, testing};
+
+    const SECRET: &str = "secret";
+
+    fn served(test: &str) -> (Option<Ledger>, PathBuf) {
+        let path = testing::directory(test).join("ledger.ron");
+        let mut ledger = Ledger::create(path.clone()).unwrap();
+        ledger
+            .add_account("Credit Card", Currency::Fiat(Fiat::Usd))
+            .unwrap();
+        ledger.save().unwrap();
+        (Some(ledger), path)
+    }
+
+    fn request(method: Method, url: &str, token: Option<&str>, body: &'static str) -> Request {
+        let mut request = TestRequest::new()
+            .with_method(method)
+            .with_path(url)
+            .with_body(body);
+        if let Some(token) = token {
+            let header = Header::from_bytes("Authorization", format!("Bearer {token}")).unwrap();
+            request = request.with_header(header);
+        }
+        request.into()
+    }
+
+    fn status(ledger: &mut Option<Ledger>, path: &Path, mut request: Request) -> u16 {
+        answer(ledger, path, SECRET, &mut request).status
+    }
+
+    #[test]
+    fn requests_without_the_token_are_refused() {
+        let (mut ledger, path) = served("serve-token");
+        for token in [None, Some(""), Some("secreT"), Some("secret ")] {
+            let sent = request(Method::Get, "/accounts", token, "");
+            assert_eq!(status(&mut ledger, &path, sent), 401);
+        }
+
+        let sent = request(Method::Get, "/accounts", Some(SECRET), "");
+        assert_eq!(status(&mut ledger, &path, sent), 200);
+    }
+
+    #[test]
+    fn missing_paths_and_wrong_methods_are_told_apart() {
+        let (mut ledger, path) = served("serve-routes");
+        for (method, url, expected) in [
+            (Method::Get, "/", 404),
+            (Method::Get, "/nothing", 404),
+            (Method::Get, "/accounts/Savings", 404),
+            (Method::Get, "/accounts/Credit%20Card/txs", 200),
+            (Method::Get, "/accounts/Credit%2", 400),
+            (Method::Delete, "/accounts", 405),
+            (Method::Post, "/balances", 405),
+            (Method::Get, "/prices", 405),
+        ] {
+            let sent = request(method, url, Some(SECRET), "");
+            assert_eq!(status(&mut ledger, &path, sent), expected, "{url}");
+        }
+    }
+
+    #[test]
+    fn bodies_over_the_limit_are_refused() {
+        let (mut ledger, path) = served("serve-body-limit");
+        let url = "/accounts/Credit%20Card/txs";
+
+        let too_long = " ".repeat(usize::try_from(BODY_LIMIT).unwrap() + 1).leak();
+        let sent = request(Method::Post, url, Some(SECRET), too_long);
+        assert_eq!(status(&mut ledger, &path, sent), 413);
+
+        let body = r#"{"amount": "-12.50", "date": "2024-03-01"}"#;
+        let sent = request(Method::Post, url, Some(SECRET), body);
+        assert_eq!(status(&mut ledger, &path, sent), 201);
+        let ledger = ledger.unwrap();
+        assert_eq!(ledger.account("Credit Card").unwrap().txs(false).len(), 1);
+    }
+
+    #[test]
+    fn escapes_are_decoded() {
+        assert_eq!(percent_decode("Credit%20Card").unwrap(), "Credit Card");
+        assert_eq!(percent_decode("%e2%82%ACs").unwrap(), "€s");
+        assert_eq!(percent_decode("plain").unwrap(), "plain");
+        for malformed in ["%", "%2", "%zz", "%+1", "%FF"] {
+            assert_eq!(percent_decode(malformed), None, "{malformed}");
+        }
+    }
+
+    /// Getting prices makes requests, which panicked without a runtime and
+    /// took the server down with them.
+    #[test]
+    fn getting_prices_is_answered() {
+        let path = testing::directory("serve-get-prices").join("ledger.ron");
+        let mut ledger = Ledger::create(path).unwrap();
+        let bitcoin = Crypto {
+            currency: Fiat::Usd,
+            description: "Bitcoin".to_string(),
+            symbol: "BTC".to_string(),
+        };
+        ledger
+            .add_account("Bitcoin", Currency::Crypto(bitcoin))
+            .unwrap();
+
+        let reply = get_prices(&mut ledger);
+        assert_eq!(reply.status, 200);
+        assert!(reply.changed);
+        let body: serde_json::Value = serde_json::from_str(&reply.body).unwrap();
+        // Without a network the price can't be got, which is in the reply.
+        assert!(body["errors"].as_array().unwrap().len() <= 1);
+    }
+}
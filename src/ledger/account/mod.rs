@@ -0,0 +1,218 @@
+pub mod transaction;
+pub mod transactions;
+
+use std::{error::Error, fmt::Display};
+
+use chrono::{DateTime, NaiveDate, NaiveTime, ParseError, TimeDelta, Utc};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use transactions::{PriceAsTransaction, Transactions};
+
+use super::{
+    duplicates::Decision,
+    money::{Currency, Fiat},
+};
+use transaction::Transaction;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Account {
+    pub name: String,
+    #[serde(rename = "transactions")]
+    pub txs_1st: Transactions<Fiat>,
+    #[serde(rename = "transactions_secondary")]
+    pub txs_2nd: Option<Transactions<Currency>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub(crate) import_decisions: Vec<Decision>,
+    /// The include the account was loaded from, `None` for the ledger itself.
+    /// It is never saved to a ledger, only to the journal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<usize>,
+    /// What the window shows of the account. It goes with the account, so
+    /// undoing a delete brings it back as it was.
+    #[cfg(feature = "gui")]
+    #[serde(skip)]
+    pub(crate) view: crate::app::account::View,
+}
+
+impl Account {
+    /// An account without transactions. Accounts in a crypto, metal or stock
+    /// hold it as their secondary currency, with its value in the primary one.
+    #[must_use]
+    pub fn new(name: String, currency: Currency) -> Self {
+        let (txs_1st, txs_2nd) = match &currency {
+            Currency::StockPlus(_) => (
+                Transactions::new(Fiat::Usd),
+                Some(Transactions::new(currency)),
+            ),
+            Currency::Crypto(crypto) => (
+                Transactions::new(crypto.currency.clone()),
+                Some(Transactions::new(currency)),
+            ),
+            Currency::Metal(metal) => (
+                Transactions::new(metal.currency.clone()),
+                Some(Transactions::new(currency)),
+            ),
+            Currency::Fiat(currency) => (Transactions::new(currency.clone()), None),
+        };
+
+        Self {
+            name,
+            txs_1st,
+            txs_2nd,
+            import_decisions: Vec::new(),
+            source: None,
+            #[cfg(feature = "gui")]
+            view: crate::app::account::View::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn balance_1st(&self) -> Decimal {
+        self.txs_1st.balance()
+    }
+
+    #[must_use]
+    pub fn balance_2nd(&self) -> Option<Decimal> {
+        self.txs_2nd
+            .as_ref()
+            .map(transactions::Transactions::balance)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `secondary` is asked for and the account doesn't hold a
+    /// secondary currency.
+    #[must_use]
+    pub fn txs(&self, secondary: bool) -> &Vec<Transaction> {
+        if secondary {
+            &self.txs_2nd.as_ref().unwrap().txs
+        } else {
+            &self.txs_1st.txs
+        }
+    }
+
+    /// Adds the transaction in date order and returns where it went.
+    pub(crate) fn insert_tx(&mut self, secondary: bool, tx: Transaction) -> usize {
+        if secondary {
+            self.txs_2nd.as_mut().unwrap().insert(tx)
+        } else {
+            self.txs_1st.insert(tx)
+        }
+    }
+
+    pub(crate) fn insert_tx_at(&mut self, secondary: bool, index: usize, tx: Transaction) {
+        if secondary {
+            self.txs_2nd.as_mut().unwrap().insert_at(index, tx);
+        } else {
+            self.txs_1st.insert_at(index, tx);
+        }
+    }
+
+    pub(crate) fn remove_tx(&mut self, secondary: bool, index: usize) -> Transaction {
+        if secondary {
+            self.txs_2nd.as_mut().unwrap().remove(index)
+        } else {
+            self.txs_1st.remove(index)
+        }
+    }
+
+    pub(crate) async fn submit_price_as_transaction(&self) -> anyhow::Result<Transaction> {
+        let mut tx = self
+            .txs_2nd
+            .as_ref()
+            .unwrap()
+            .get_price_as_transaction()
+            .await?;
+        tx.amount = tx.balance - self.balance_1st();
+        Ok(tx)
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the account doesn't hold a secondary currency.
+    #[must_use]
+    pub fn total_2nd(&self) -> Decimal {
+        self.txs_2nd.as_ref().unwrap().total()
+    }
+
+    /// The sum of the amounts before and during the last week.
+    #[must_use]
+    pub fn sum_last_week(&self) -> (Decimal, Decimal) {
+        let last_week = Utc::now() - TimeDelta::weeks(1);
+        let mut previous_amount = dec!(0);
+        let mut amount = dec!(0);
+
+        for tx in &self.txs_1st.txs {
+            if tx.date >= last_week {
+                amount += tx.amount;
+            } else {
+                previous_amount += tx.amount;
+            }
+        }
+        (previous_amount, amount)
+    }
+
+    /// The sum of the amounts before and during the last 30 days.
+    #[must_use]
+    pub fn sum_last_month(&self) -> (Decimal, Decimal) {
+        let last_month = Utc::now() - TimeDelta::days(30);
+        let mut amount = dec!(0);
+        let mut previous_amount = dec!(0);
+
+        for tx in &self.txs_1st.txs {
+            if tx.date >= last_month {
+                amount += tx.amount;
+            } else {
+                previous_amount += tx.amount;
+            }
+        }
+        (previous_amount, amount)
+    }
+
+    /// The sum of the amounts before and during the last 365 days.
+    #[must_use]
+    pub fn sum_last_year(&self) -> (Decimal, Decimal) {
+        let last_year = Utc::now() - TimeDelta::days(365);
+        let mut amount = dec!(0);
+        let mut previous_amount = dec!(0);
+
+        for tx in &self.txs_1st.txs {
+            if tx.date >= last_year {
+                amount += tx.amount;
+            } else {
+                previous_amount += tx.amount;
+            }
+        }
+        (previous_amount, amount)
+    }
+}
+
+/// Parses a date entered as `YYYY-MM-DD`, now when it is empty.
+///
+/// # Errors
+///
+/// Fails if the date isn't empty and isn't `YYYY-MM-DD`.
+pub fn parse_date(date: &str) -> Result<DateTime<Utc>, ParseDateError> {
+    if date.is_empty() {
+        Ok(Utc::now())
+    } else {
+        match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+            Ok(naive_date) => Ok(naive_date.and_time(NaiveTime::MIN).and_utc()),
+            Err(error) => Err(ParseDateError { error }),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ParseDateError {
+    error: ParseError,
+}
+
+impl Display for ParseDateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        writeln!(f, "Parse Date error: {}", self.error)
+    }
+}
+
+impl Error for ParseDateError {}
@@ -0,0 +1,16 @@
+use chrono::{DateTime, Utc, serde::ts_seconds};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Transaction {
+    pub amount: Decimal,
+    pub balance: Decimal,
+    pub comment: String,
+    #[serde(with = "ts_seconds")]
+    pub date: DateTime<Utc>,
+    /// The balance was entered and the amount is worked out from the
+    /// transaction before it, e.g. a price or a statement balance.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub snapshot: bool,
+}
@@ -1,13 +1,12 @@
 use std::fmt::Display;
 
-use anyhow::Error;
-use chrono::{DateTime, Months, TimeDelta, Utc};
+use chrono::{DateTime, Utc};
 use reqwest::Client;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use serde::{Deserialize, Serialize};
 
-use crate::app::{Fiat, money::Currency};
+use crate::ledger::money::Currency;
 
 use super::transaction::Transaction;
 
@@ -45,6 +44,7 @@ impl PriceAsTransaction for Transactions<Currency> {
                 balance: count * price,
                 date: Utc::now(),
                 comment: String::new(),
+                snapshot: true,
             }),
             Currency::Fiat(_) => unreachable!("You can't have a fiat price_as_transaction!"),
         }
@@ -69,98 +69,47 @@ impl<T: Clone + Display> Transactions<T> {
         self.txs.last().map_or_else(|| dec!(0), |tx| tx.balance)
     }
 
-    pub fn balance_to_amount(&mut self, mut new_tx: Transaction) -> Transaction {
-        new_tx.amount = new_tx.balance;
-        let mut previous_tx = None;
-        for tx in &mut self.txs {
-            if tx.date <= new_tx.date {
-                previous_tx = Some(tx);
-            }
-        }
-        if let Some(tx) = previous_tx {
-            new_tx.amount = new_tx.balance - tx.balance;
-        }
-
-        for tx in &mut self.txs {
-            if tx.date > new_tx.date {
-                tx.amount = tx.balance - new_tx.balance;
-                break;
-            }
-        }
-
-        new_tx
-    }
-
     fn count(&self) -> Decimal {
         self.txs.iter().map(|tx| tx.amount).sum()
     }
 
-    pub fn date_most_recent(&self, date: &DateTime<Utc>) -> anyhow::Result<()> {
-        for tx in &self.txs {
-            if &tx.date > date {
-                return Err(Error::msg("The date is not the most recent!"));
-            }
-        }
-        Ok(())
-    }
-
-    pub fn filter_month(&mut self, filter_date: Option<DateTime<Utc>>) {
-        if let Some(date) = filter_date {
-            let mut filtered_tx = Vec::new();
-            for tx in &self.txs {
-                if tx.date >= date && tx.date < date.checked_add_months(Months::new(1)).unwrap() {
-                    filtered_tx.push(tx.clone());
-                }
-            }
-            self.txs = filtered_tx;
-        }
+    /// Adds the transaction after any others on the same date and recomputes
+    /// everything after it.
+    pub(crate) fn insert(&mut self, tx: Transaction) -> usize {
+        let index = self.txs.partition_point(|tx_old| tx_old.date <= tx.date);
+        self.txs.insert(index, tx);
+        self.recompute(index);
+        index
     }
 
-    pub fn last_week(&self) -> Transactions<T> {
-        let last_week = Utc::now() - TimeDelta::weeks(1);
-        let mut txs = Vec::new();
-
-        for tx in &self.txs {
-            if tx.date >= last_week {
-                txs.push(tx.clone());
-            }
-        }
-
-        Transactions {
-            txs,
-            currency: self.currency.clone(),
-        }
+    /// Puts the transaction back at `index` and recomputes everything after it.
+    pub(crate) fn insert_at(&mut self, index: usize, tx: Transaction) {
+        self.txs.insert(index, tx);
+        self.recompute(index);
     }
 
-    pub fn last_month(&self) -> Transactions<T> {
-        let last_week = Utc::now() - TimeDelta::days(30);
-        let mut txs = Vec::new();
-
-        for tx in &self.txs {
-            if tx.date >= last_week {
-                txs.push(tx.clone());
-            }
-        }
-
-        Transactions {
-            txs,
-            currency: self.currency.clone(),
-        }
+    /// Removes the transaction and recomputes everything after it.
+    pub(crate) fn remove(&mut self, index: usize) -> Transaction {
+        let tx = self.txs.remove(index);
+        self.recompute(index);
+        tx
     }
 
-    pub fn last_year(&self) -> Transactions<T> {
-        let last_week = Utc::now() - TimeDelta::days(365);
-        let mut txs = Vec::new();
+    /// Recomputes the running balance from `index` on. Snapshots keep their
+    /// balance and get a new amount instead.
+    pub(crate) fn recompute(&mut self, index: usize) {
+        let mut balance = index
+            .checked_sub(1)
+            .and_then(|i| self.txs.get(i))
+            .map_or_else(|| dec!(0), |tx| tx.balance);
 
-        for tx in &self.txs {
-            if tx.date >= last_week {
-                txs.push(tx.clone());
+        for tx in self.txs.iter_mut().skip(index) {
+            if tx.snapshot {
+                tx.amount = tx.balance - balance;
+            } else {
+                tx.balance = balance + tx.amount;
             }
-        }
-
-        Transactions {
-            txs,
-            currency: self.currency.clone(),
+            balance = tx.balance;
         }
     }
 
@@ -180,7 +129,7 @@ impl<T: Clone + Display> Transactions<T> {
         self.txs.iter().map(|tx| tx.date).min()
     }
 
-    pub fn sort(&mut self) {
+    pub(crate) fn sort(&mut self) {
         self.txs.sort_by_key(|tx| tx.date);
     }
 
@@ -190,6 +139,7 @@ impl<T: Clone + Display> Transactions<T> {
 }
 
 impl Transactions<Currency> {
+    #[must_use]
     pub const fn has_txs_2nd(&self) -> bool {
         match self.currency {
             Currency::Crypto(_) | Currency::Metal(_) | Currency::StockPlus(_) => true,
@@ -197,21 +147,3 @@ impl Transactions<Currency> {
         }
     }
 }
-
-impl Transactions<Fiat> {
-    pub fn remove_duplicates(&mut self, txs: &Transactions<Fiat>) {
-        let mut txs_new = Vec::new();
-        'outer: for tx in &self.txs {
-            for tx_2nd in &txs.txs {
-                if tx.date == tx_2nd.date
-                    && tx.amount == tx_2nd.amount
-                    && tx.comment == tx_2nd.comment
-                {
-                    continue 'outer;
-                }
-            }
-            txs_new.push(tx.clone());
-        }
-        self.txs = txs_new;
-    }
-}
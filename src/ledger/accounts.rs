@@ -0,0 +1,1015 @@
+use anyhow::Context;
+use ron::ser::PrettyConfig;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::ops::{Index, IndexMut};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use super::account::Account;
+
+use super::File;
+use super::account::{transaction::Transaction, transactions::Transactions};
+use super::backups;
+use super::crypto::Crypto;
+use super::duplicates::{self, Matcher};
+use super::encryption::{self, Encryption, NeedsPassphrase};
+use super::format;
+use super::history::{AccountChange, Command, TransactionChange};
+use super::import::{self, Import};
+use super::lock;
+use super::metal::Metal;
+use super::money::{Currency, Fiat};
+use super::sqlite::{self, Store};
+use super::stocks::StockPlus;
+use super::watch::{Changed, Stamp};
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Accounts {
+    /// The version of the file format, see `format::VERSION`.
+    #[serde(default)]
+    pub version: u32,
+    #[serde(rename = "accounts")]
+    pub inner: Vec<Account>,
+    pub groups: Vec<Group>,
+    pub crypto: Vec<Crypto>,
+    pub fiats: Vec<Fiat>,
+    pub metals: Vec<Metal>,
+    pub stocks_plus: Vec<StockPlus>,
+    #[serde(default)]
+    pub(crate) duplicate_matcher: Matcher,
+    /// How many backups of the file to keep.
+    #[serde(default = "default_backups")]
+    pub backups: usize,
+    /// Other ledgers whose accounts are loaded and saved along with these.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub includes: Vec<Include>,
+    /// Rhai scripts run on import, after prices are got or when asked to,
+    /// relative to the directory of the ledger unless absolute.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scripts: Vec<PathBuf>,
+}
+
+/// Another ledger file included in this one.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Include {
+    /// Relative to the directory of the including ledger, unless absolute.
+    pub path: PathBuf,
+    /// The included ledger is never saved, and its accounts can't be changed.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub read_only: bool,
+    /// The included ledger without its accounts and groups, so its settings
+    /// are saved back as they were.
+    #[serde(skip)]
+    pub(crate) settings: Accounts,
+}
+
+impl Include {
+    /// Where the include is, given the path of the including ledger.
+    pub(crate) fn resolve(&self, file_path: &Path) -> PathBuf {
+        backups::directory(file_path).join(&self.path)
+    }
+}
+
+impl Accounts {
+    #[must_use]
+    pub fn currencies(&self) -> HashSet<Fiat> {
+        let mut currencies = HashSet::new();
+        for account in &self.inner {
+            currencies.insert(account.txs_1st.currency.clone());
+        }
+        currencies
+    }
+
+    #[must_use]
+    pub fn all_accounts_txs_1st(&self, currency: Fiat) -> Transactions<Fiat> {
+        let mut transactions = Transactions::new(currency);
+        for account in &self.inner {
+            if account.txs_1st.currency == transactions.currency {
+                for tx in &account.txs_1st.txs {
+                    transactions.txs.push(tx.clone());
+                }
+            }
+        }
+        transactions.sort();
+
+        let mut balance = dec!(0);
+        for tx in &mut transactions.txs {
+            balance += tx.amount;
+            tx.balance = balance;
+        }
+        transactions
+    }
+
+    /// Fetches a price for every account holding a secondary currency, without
+    /// adding the transactions.
+    pub(crate) async fn get_all_prices(&self) -> Vec<(usize, anyhow::Result<Transaction>)> {
+        let mut tasks = Vec::new();
+        let mut indexes = Vec::new();
+        for (index, account) in self.inner.iter().enumerate() {
+            if account.txs_2nd.is_some() {
+                indexes.push(index);
+                tasks.push(account.submit_price_as_transaction());
+            }
+        }
+
+        let results = futures::future::join_all(tasks).await;
+        indexes.into_iter().zip(results).collect()
+    }
+
+    /// Adds the prices got by `get_all_prices`. Returns the changes made and
+    /// why the prices that couldn't be got weren't.
+    pub(crate) fn add_prices(
+        &mut self,
+        results: Vec<(usize, anyhow::Result<Transaction>)>,
+    ) -> (Vec<Command>, Vec<anyhow::Error>) {
+        let mut commands = Vec::new();
+        let mut errors = Vec::new();
+        for (i, result) in results {
+            match result {
+                Ok(tx) => commands.push(self.insert_tx(i, false, tx)),
+                Err(error) => errors.push(error),
+            }
+        }
+        (commands, errors)
+    }
+
+    /// Adds the transaction to the account in date order and returns the change.
+    pub(crate) fn insert_tx(
+        &mut self,
+        account: usize,
+        secondary: bool,
+        tx: Transaction,
+    ) -> Command {
+        let index = self[account].insert_tx(secondary, tx);
+        Command::InsertTransaction(TransactionChange {
+            account,
+            secondary,
+            index,
+            tx: self[account].txs(secondary)[index].clone(),
+        })
+    }
+
+    /// Adds the checked rows of a Bank of America import to the account, merges
+    /// the unchecked probable duplicates into the transactions they match, and
+    /// remembers what was decided about the rows. Decisions from before what
+    /// the import can match are let go, see `duplicates::prune`.
+    pub(crate) fn commit_boa(&mut self, account: usize, import: &Import) -> Vec<Command> {
+        let mut commands = Vec::new();
+        for row in import.clone().checked() {
+            commands.push(self.insert_tx(account, false, row.tx));
+        }
+        for row in import.clone().merged() {
+            commands.extend(self.merge_tx(account, &row));
+        }
+
+        let before = self[account].import_decisions.clone();
+        let mut after = import
+            .rows
+            .iter()
+            .map(|row| row.fingerprint.date)
+            .min()
+            .map_or_else(
+                || before.clone(),
+                |start| duplicates::prune(&before, start, self.duplicate_matcher.days),
+            );
+        after.extend(import.decisions());
+        self[account].import_decisions.clone_from(&after);
+        commands.push(Command::SetDecisions {
+            account,
+            before,
+            after,
+        });
+        commands
+    }
+
+    /// Gives the transaction a merged row matches the comment of the row, if it
+    /// has none of its own, and returns the change.
+    fn merge_tx(&mut self, account: usize, row: &import::Row) -> Option<Command> {
+        let matched = row.matched.as_ref()?;
+        if !matched.comment.trim().is_empty() || row.tx.comment.is_empty() {
+            return None;
+        }
+
+        // The rows added before may have changed its balance.
+        let txs = &mut self[account].txs_1st.txs;
+        let index = txs.iter().position(|tx| {
+            tx.date == matched.date && tx.amount == matched.amount && tx.comment == matched.comment
+        })?;
+        let before = txs[index].clone();
+        txs[index].comment.clone_from(&row.tx.comment);
+        Some(Command::EditTransaction {
+            account,
+            secondary: false,
+            index_before: index,
+            index_after: index,
+            before,
+            after: txs[index].clone(),
+        })
+    }
+
+    /// The index of the account with the name.
+    ///
+    /// # Errors
+    ///
+    /// Fails if there is no account with the name.
+    pub fn find(&self, name: &str) -> anyhow::Result<usize> {
+        self.inner
+            .iter()
+            .position(|account| account.name == name)
+            .with_context(|| format!("There is no account named \"{name}\"."))
+    }
+
+    pub(crate) fn check_account_name(&self, name: &str) -> anyhow::Result<()> {
+        for account in &self.inner {
+            if name == account.name {
+                return Err(anyhow::Error::msg("Duplicate name!"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the account in name order among those of its file, to the groups
+    /// given, and returns the change. The accounts of this file come first, then
+    /// those of each include in turn, see `merge`.
+    pub(crate) fn insert_account(&mut self, new_account: Account, member_of: &[usize]) -> Command {
+        let index = self.inner.partition_point(|account| {
+            (account.source, &account.name) <= (new_account.source, &new_account.name)
+        });
+        let groups_without = self.groups.clone();
+
+        for group in &mut self.groups {
+            for i in &mut group.members {
+                if *i >= index {
+                    *i += 1;
+                }
+            }
+        }
+        for group in member_of {
+            self.groups[*group].members.push(index);
+        }
+        self.inner.insert(index, new_account.clone());
+
+        Command::InsertAccount(AccountChange {
+            index,
+            account: Box::new(new_account),
+            groups_without,
+            groups_with: self.groups.clone(),
+        })
+    }
+
+    pub(crate) fn remove_account(&mut self, index: usize) -> Command {
+        let groups_with = self.groups.clone();
+        for group in &mut self.groups {
+            group.remove(index);
+        }
+        let account = self.inner.remove(index);
+
+        Command::RemoveAccount(AccountChange {
+            index,
+            account: Box::new(account),
+            groups_without: self.groups.clone(),
+            groups_with,
+        })
+    }
+
+    /// Adds the rows of an Investor 360 import to the ledger, returning a command
+    /// for each change made. If a row can't be added none of them are.
+    pub(crate) fn commit_investor_360(
+        &mut self,
+        rows: Vec<import::Row>,
+    ) -> anyhow::Result<Vec<Command>> {
+        let mut commands = Vec::new();
+        for row in rows {
+            if let Err(error) = self.commit_investor_360_row(row, &mut commands) {
+                for command in commands.iter().rev() {
+                    command.undo(self);
+                }
+                return Err(error);
+            }
+        }
+
+        Ok(commands)
+    }
+
+    fn commit_investor_360_row(
+        &mut self,
+        row: import::Row,
+        commands: &mut Vec<Command>,
+    ) -> anyhow::Result<()> {
+        let investor_360_record = row.holding.context("An Investor 360 row has no holding!")?;
+        let mut tx = row.tx;
+        let balance = tx.balance;
+        let name = row.account;
+        let secondary = investor_360_record.price != dec!(1);
+        if let Some(i) = self.inner.iter().position(|account| account.name == name) {
+            let account = &mut self.inner[i];
+            if secondary && account.txs_2nd.is_none() {
+                return Err(super::no_secondary(&name));
+            }
+            let index = account.insert_tx(secondary, tx);
+            commands.push(Command::InsertTransaction(TransactionChange {
+                account: i,
+                secondary,
+                index,
+                tx: account.txs(secondary)[index].clone(),
+            }));
+            return Ok(());
+        }
+
+        tx.amount = balance;
+        let txs = vec![tx];
+        if secondary {
+            let stock = StockPlus {
+                description: investor_360_record.description,
+                symbol: investor_360_record.symbol,
+            };
+            let currency = Currency::StockPlus(stock);
+            let transactions = Transactions {
+                currency: currency.clone(),
+                txs,
+            };
+            let mut account = Account::new(name, currency);
+            account.txs_2nd = Some(transactions);
+            commands.push(self.insert_account(account, &[]));
+        } else {
+            let currency = Fiat::Usd;
+            let transactions = Transactions {
+                currency: currency.clone(),
+                txs,
+            };
+            let mut account = Account::new(name, Currency::Fiat(currency));
+            account.txs_1st = transactions;
+            commands.push(self.insert_account(account, &[]));
+        }
+
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn get_currencies(&self) -> Vec<Currency> {
+        let mut currencies = Vec::new();
+        for crypto in &self.crypto {
+            currencies.push(Currency::Crypto(crypto.clone()));
+        }
+        for fiat in &self.fiats {
+            currencies.push(Currency::Fiat(fiat.clone()));
+        }
+        for metal in &self.metals {
+            currencies.push(Currency::Metal(metal.clone()));
+        }
+        for stock_plus in &self.stocks_plus {
+            currencies.push(Currency::StockPlus(stock_plus.clone()));
+        }
+        currencies
+    }
+
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            version: format::VERSION,
+            inner: Vec::new(),
+            groups: Vec::new(),
+            crypto: Vec::new(),
+            fiats: Vec::new(),
+            metals: Vec::new(),
+            stocks_plus: Vec::new(),
+            duplicate_matcher: Matcher::default(),
+            backups: backups::DEFAULT_COUNT,
+            includes: Vec::new(),
+            scripts: Vec::new(),
+        }
+    }
+
+    /// Whether the account, or group, comes from a ledger that is included
+    /// read-only.
+    #[must_use]
+    pub fn is_read_only(&self, source: Option<usize>) -> bool {
+        source
+            .and_then(|i| self.includes.get(i))
+            .is_some_and(|include| include.read_only)
+    }
+
+    /// What the account, or group, was loaded from, e.g. `household.ron`.
+    #[must_use]
+    pub fn source_name(&self, source: Option<usize>) -> String {
+        match source.and_then(|i| self.includes.get(i)) {
+            Some(include) => include.path.file_name().map_or_else(
+                || include.path.display().to_string(),
+                |name| name.to_string_lossy().to_string(),
+            ),
+            None => "main".to_string(),
+        }
+    }
+
+    /// The accounts and groups that belong in one file, `None` for this one,
+    /// with the members of the groups numbered as they are in that file.
+    pub(crate) fn part(&self, source: Option<usize>) -> Self {
+        let mut part = match source {
+            Some(i) => self.includes[i].settings.clone(),
+            None => Self {
+                version: self.version,
+                inner: Vec::new(),
+                groups: Vec::new(),
+                crypto: self.crypto.clone(),
+                fiats: self.fiats.clone(),
+                metals: self.metals.clone(),
+                stocks_plus: self.stocks_plus.clone(),
+                duplicate_matcher: self.duplicate_matcher.clone(),
+                backups: self.backups,
+                includes: self.includes.clone(),
+                scripts: self.scripts.clone(),
+            },
+        };
+
+        let mut local = HashMap::new();
+        for (i, account) in self.inner.iter().enumerate() {
+            if account.source == source {
+                local.insert(i, part.inner.len());
+                let mut account = account.clone();
+                account.source = None;
+                part.inner.push(account);
+            }
+        }
+        for group in &self.groups {
+            if group.source == source {
+                part.groups.push(Group {
+                    name: group.name.clone(),
+                    members: group
+                        .members
+                        .iter()
+                        .filter_map(|i| local.get(i).copied())
+                        .collect(),
+                    source: None,
+                });
+            }
+        }
+        part
+    }
+
+    /// Adds the accounts and groups of an included ledger, and the assets it
+    /// has that these don't.
+    pub(crate) fn merge(&mut self, source: usize, mut part: Self) -> anyhow::Result<()> {
+        let offset = self.inner.len();
+        for mut account in std::mem::take(&mut part.inner) {
+            if self.inner.iter().any(|other| other.name == account.name) {
+                return Err(anyhow::Error::msg(format!(
+                    "The account {} is in more than one of the included files.",
+                    account.name
+                )));
+            }
+            account.source = Some(source);
+            self.inner.push(account);
+        }
+        for mut group in std::mem::take(&mut part.groups) {
+            for member in &mut group.members {
+                *member += offset;
+            }
+            group.source = Some(source);
+            self.groups.push(group);
+        }
+
+        for crypto in &part.crypto {
+            if !self.crypto.contains(crypto) {
+                self.crypto.push(crypto.clone());
+            }
+        }
+        for fiat in &part.fiats {
+            if !self.fiats.contains(fiat) {
+                self.fiats.push(fiat.clone());
+            }
+        }
+        for metal in &part.metals {
+            if !self.metals.contains(metal) {
+                self.metals.push(metal.clone());
+            }
+        }
+        for stock_plus in &part.stocks_plus {
+            if !self.stocks_plus.contains(stock_plus) {
+                self.stocks_plus.push(stock_plus.clone());
+            }
+        }
+
+        self.includes[source].settings = part;
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn balance(&self, currency: &Fiat) -> Decimal {
+        let mut balance = dec!(0);
+        for account in &self.inner {
+            if account.txs_1st.currency == *currency {
+                balance += account.balance_1st();
+            }
+        }
+        balance
+    }
+
+    #[must_use]
+    pub fn total_for_last_week(&self, currency: &Fiat) -> (Decimal, Decimal) {
+        let mut previous_total = dec!(0);
+        let mut total = dec!(0);
+        for account in &self.inner {
+            if account.txs_1st.currency == *currency {
+                let (previous_sum, sum) = account.sum_last_week();
+                previous_total += previous_sum;
+                total += sum;
+            }
+        }
+        (previous_total, total)
+    }
+
+    #[must_use]
+    pub fn total_for_last_month(&self, currency: &Fiat) -> (Decimal, Decimal) {
+        let mut previous_total = dec!(0);
+        let mut total = dec!(0);
+        for account in &self.inner {
+            if account.txs_1st.currency == *currency {
+                let (previous_sum, sum) = account.sum_last_month();
+                previous_total += previous_sum;
+                total += sum;
+            }
+        }
+        (previous_total, total)
+    }
+
+    #[must_use]
+    pub fn total_for_last_year(&self, currency: &Fiat) -> (Decimal, Decimal) {
+        let mut previous_total = dec!(0);
+        let mut total = dec!(0);
+        for account in &self.inner {
+            if account.txs_1st.currency == *currency {
+                let (previous_sum, sum) = account.sum_last_year();
+                previous_total += previous_sum;
+                total += sum;
+            }
+        }
+        (previous_total, total)
+    }
+
+    pub(crate) fn to_string(&self) -> anyhow::Result<String> {
+        let pretty_config = PrettyConfig::new();
+        let string = ron::ser::to_string_pretty(self, pretty_config)?;
+        Ok(string)
+    }
+
+    /// Saves to a new path, keeping the encryption of the old file if it had one.
+    /// The format is chosen by the extension, so this also converts between RON
+    /// and SQLite.
+    ///
+    /// The included ledgers stay where they are, and are saved too. Paths to them
+    /// that are relative are made absolute when the new path is in another
+    /// directory, so the ledger needs loading again after this.
+    pub(crate) fn save_dialogue(
+        &self,
+        old_file: &mut Option<File>,
+        file_path: PathBuf,
+    ) -> anyhow::Result<File> {
+        let encryption = old_file.as_ref().and_then(|file| file.encryption.clone());
+        self.save_encrypted(old_file, file_path, encryption)
+    }
+
+    /// Saves to a new path like `save_dialogue`, but with the encryption given.
+    pub(crate) fn save_encrypted(
+        &self,
+        old_file: &mut Option<File>,
+        file_path: PathBuf,
+        encryption: Option<Encryption>,
+    ) -> anyhow::Result<File> {
+        if sqlite::is_sqlite(&file_path) && encryption.is_some() {
+            return Err(anyhow::Error::msg(
+                "SQLite files can't be encrypted. Decrypt the file before saving it as SQLite.",
+            ));
+        }
+
+        if !self.includes.is_empty() {
+            let old_path = old_file
+                .as_ref()
+                .map(|file| file.path.clone())
+                .unwrap_or_default();
+            let mut part = self.part(None);
+            if backups::directory(&old_path) != backups::directory(&file_path) {
+                for include in &mut part.includes {
+                    include.path = include.resolve(&old_path);
+                }
+            }
+            let includes = old_file
+                .as_mut()
+                .map(|file| std::mem::take(&mut file.includes))
+                .unwrap_or_default();
+            let mut file = part.save_as(old_file, file_path, encryption)?;
+            file.includes = includes;
+            self.save_includes(&mut file)?;
+            return Ok(file);
+        }
+
+        self.save_as(old_file, file_path, encryption)
+    }
+
+    fn save_as(
+        &self,
+        old_file: &mut Option<File>,
+        file_path: PathBuf,
+        encryption: Option<Encryption>,
+    ) -> anyhow::Result<File> {
+        if let Some(old_file) = old_file.take() {
+            old_file.unlock()?;
+        }
+
+        if fs::exists(&file_path)? {
+            let file = fs::File::open(&file_path)?;
+            lock::try_lock(&file, &file_path)?;
+            file.unlock()?;
+        }
+
+        backups::create(&file_path, self.backups)?;
+        let backed_up = Some(Instant::now());
+        if sqlite::is_sqlite(&file_path) {
+            let mut file = self.save_sqlite(file_path)?;
+            file.backed_up = backed_up;
+            return Ok(file);
+        }
+        let file = self.write_atomic(&file_path, encryption.as_ref())?;
+
+        Ok(File {
+            stamp: Some(Stamp::read(&file_path, true)?),
+            path: file_path,
+            inner: file,
+            encryption,
+            sqlite: None,
+            read_only: false,
+            backed_up,
+            includes: Vec::new(),
+        })
+    }
+
+    pub(crate) fn save_first(&self, file_path: PathBuf) -> anyhow::Result<File> {
+        if sqlite::is_sqlite(&file_path) {
+            if fs::exists(&file_path)? {
+                return Err(anyhow::Error::msg(format!(
+                    "{} already exists!",
+                    file_path.display()
+                )));
+            }
+            return self.save_sqlite(file_path);
+        }
+
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&file_path)?;
+
+        lock::try_lock(&file, &file_path)?;
+        file.write_all(self.to_string()?.as_bytes())?;
+
+        Ok(File {
+            stamp: Some(Stamp::read(&file_path, true)?),
+            path: file_path,
+            inner: file,
+            encryption: None,
+            sqlite: None,
+            read_only: false,
+            backed_up: None,
+            includes: Vec::new(),
+        })
+    }
+
+    /// Writes everything to a SQLite database, replacing what was in it.
+    fn save_sqlite(&self, file_path: PathBuf) -> anyhow::Result<File> {
+        let mut store = Store::create(&file_path)?;
+        let file = fs::File::open(&file_path)?;
+        lock::try_lock(&file, &file_path)?;
+        store.write(self)?;
+
+        Ok(File {
+            stamp: Some(Stamp::read(&file_path, false)?),
+            path: file_path,
+            inner: file,
+            encryption: None,
+            sqlite: Some(store),
+            read_only: false,
+            backed_up: None,
+            includes: Vec::new(),
+        })
+    }
+
+    /// Saves over the file, unless another program changed it since it was
+    /// loaded or saved (`Changed`). The file is left as it was on errors.
+    ///
+    /// Each account and group is saved to the file it was loaded from, the
+    /// included ledgers that are read-only aren't saved.
+    pub(crate) fn save(&self, file: &mut File) -> anyhow::Result<()> {
+        if self.includes.is_empty() {
+            return self.save_one(file);
+        }
+
+        self.part(None).save_one(file)?;
+        self.save_includes(file)
+    }
+
+    fn save_includes(&self, file: &mut File) -> anyhow::Result<()> {
+        if file.includes.len() != self.includes.len() {
+            return Err(anyhow::Error::msg(
+                "The included files changed, load the ledger again before saving it.",
+            ));
+        }
+
+        for (i, include) in file.includes.iter_mut().enumerate() {
+            if !include.read_only {
+                self.part(Some(i))
+                    .save_one(include)
+                    .with_context(|| format!("saving {}", include.path.display()))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Saves these accounts, and nothing from the includes, over the file.
+    pub(crate) fn save_one(&self, file: &mut File) -> anyhow::Result<()> {
+        if file.read_only {
+            return Err(anyhow::Error::msg(
+                "The file is open read-only, so it can't be saved.",
+            ));
+        }
+        if let Some(stamp) = &file.stamp
+            && stamp.changed(&file.path)?
+        {
+            return Err(Changed(file.path.clone()).into());
+        }
+
+        backups::create_due(&file.path, self.backups, &mut file.backed_up)?;
+        if let Some(store) = &mut file.sqlite {
+            store.write(self)?;
+            file.stamp = Some(Stamp::read(&file.path, false)?);
+            return Ok(());
+        }
+
+        let inner = self.write_atomic(&file.path, file.encryption.as_ref())?;
+        let old = std::mem::replace(&mut file.inner, inner);
+        file.stamp = Some(Stamp::read(&file.path, true)?);
+        old.unlock()?;
+        Ok(())
+    }
+
+    /// Writes to a temporary file in the same directory, flushes it to disk and
+    /// renames it over `file_path`, so a failed write leaves the old file as it
+    /// was. Returns the new file, locked.
+    fn write_atomic(
+        &self,
+        file_path: &Path,
+        encryption: Option<&Encryption>,
+    ) -> anyhow::Result<fs::File> {
+        let mut bytes = self.to_string()?.into_bytes();
+        if let Some(encryption) = encryption {
+            bytes = encryption.encrypt(&bytes)?;
+        }
+
+        let mut name = OsString::from(".");
+        name.push(backups::file_name(file_path)?);
+        name.push(".tmp");
+        let tmp_path = file_path.with_file_name(name);
+
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&tmp_path)?;
+        file.try_lock()?;
+
+        // Keep who can read the ledger, the temporary file is created with the
+        // default permissions.
+        let permissions = backups::permissions(file_path)?;
+
+        let result = permissions
+            .map_or(Ok(()), |permissions| file.set_permissions(permissions))
+            .and_then(|()| file.set_len(0))
+            .and_then(|()| file.write_all(&bytes))
+            .and_then(|()| file.sync_all())
+            .and_then(|()| fs::rename(&tmp_path, file_path));
+
+        if let Err(error) = result {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(error).with_context(|| format!("saving {}", file_path.display()));
+        }
+
+        // Make the rename itself durable.
+        if let Ok(directory) = fs::File::open(backups::directory(file_path)) {
+            let _ = directory.sync_all();
+        }
+
+        Ok(file)
+    }
+
+    /// Parses a ledger, decrypting it first if it is encrypted and upgrading it
+    /// if it is an older version. Also returns the version it was.
+    fn from_bytes(
+        bytes: &[u8],
+        passphrase: Option<&str>,
+    ) -> anyhow::Result<(Self, Option<Encryption>, u32)> {
+        let (ron, encryption) = if encryption::is_encrypted(bytes) {
+            let passphrase = passphrase.ok_or(NeedsPassphrase)?;
+            let (bytes, encryption) = Encryption::decrypt(bytes, passphrase)?;
+            (String::from_utf8(bytes)?, Some(encryption))
+        } else {
+            (str::from_utf8(bytes)?.to_string(), None)
+        };
+
+        let version = format::version(&ron)?;
+        let ron = format::migrate(ron, version)?;
+        let mut accounts: Self = ron::from_str(&ron)?;
+        accounts.version = format::VERSION;
+        Ok((accounts, encryption, version))
+    }
+
+    #[cfg(feature = "gui")]
+    pub(crate) fn read_backup(
+        file_path: &Path,
+        passphrase: Option<&str>,
+    ) -> anyhow::Result<(Self, Option<Encryption>)> {
+        if sqlite::is_sqlite_file(file_path)? {
+            let (_store, accounts) = Store::open(file_path)?;
+            return Ok((accounts, None));
+        }
+
+        let bytes = fs::read(file_path)?;
+        let (accounts, encryption, _version) = Self::from_bytes(&bytes, passphrase)?;
+        Ok((accounts, encryption))
+    }
+
+    /// Loads the ledger. The file is read, parsed and locked before `old_file`
+    /// is let go, so a file that can't be loaded, e.g. an encrypted one without
+    /// the right passphrase (`NeedsPassphrase` when there is none) or one that is
+    /// open in another instance (`Locked`), leaves the old one locked.
+    ///
+    /// A file from an older version is upgraded and saved, and the original is
+    /// kept next to it, e.g. as `ledger.ron.format-0`.
+    pub(crate) fn load(
+        old_file: &mut Option<File>,
+        file_path: PathBuf,
+        passphrase: Option<&str>,
+    ) -> anyhow::Result<(Self, File)> {
+        Self::open(old_file, file_path, passphrase, false)
+    }
+
+    /// Loads the ledger without locking it, for when another instance has it
+    /// open. It can't be saved, but it can be saved as another file.
+    pub(crate) fn load_read_only(
+        old_file: &mut Option<File>,
+        file_path: PathBuf,
+        passphrase: Option<&str>,
+    ) -> anyhow::Result<(Self, File)> {
+        Self::open(old_file, file_path, passphrase, true)
+    }
+
+    fn open(
+        old_file: &mut Option<File>,
+        file_path: PathBuf,
+        passphrase: Option<&str>,
+        read_only: bool,
+    ) -> anyhow::Result<(Self, File)> {
+        let (mut accounts, encryption, sqlite, bytes, version) =
+            if sqlite::is_sqlite_file(&file_path)? {
+                let (store, accounts) = Store::open(&file_path)
+                    .with_context(|| format!("loading {}", file_path.display()))?;
+                (accounts, None, Some(store), Vec::new(), format::VERSION)
+            } else {
+                let bytes = fs::read(&file_path)?;
+                let (accounts, encryption, version) = Self::from_bytes(&bytes, passphrase)
+                    .with_context(|| format!("loading {}", file_path.display()))?;
+                (accounts, encryption, None, bytes, version)
+            };
+
+        let include_paths: Vec<_> = accounts
+            .includes
+            .iter()
+            .map(|include| include.resolve(&file_path))
+            .collect();
+
+        // Reloading the file that is open, or one that includes the same files,
+        // needs its locks let go first.
+        if old_file.as_ref().is_some_and(|file| {
+            (file.path == file_path && !file.read_only)
+                || file
+                    .includes
+                    .iter()
+                    .any(|include| include_paths.contains(&include.path))
+        }) && let Some(old_file) = old_file.take()
+        {
+            old_file.unlock()?;
+        }
+
+        let mut includes = Vec::new();
+        for (i, include_path) in include_paths.into_iter().enumerate() {
+            let include_read_only = read_only || accounts.includes[i].read_only;
+            let (part, include) = Self::open(
+                &mut None,
+                include_path.clone(),
+                passphrase,
+                include_read_only,
+            )
+            .with_context(|| format!("including {}", include_path.display()))?;
+            if !part.includes.is_empty() {
+                return Err(anyhow::Error::msg(format!(
+                    "{} includes other files, which an included file can't.",
+                    include_path.display()
+                )));
+            }
+            accounts.merge(i, part)?;
+            includes.push(include);
+        }
+
+        let file = fs::File::open(&file_path)?;
+        if !read_only {
+            lock::try_lock(&file, &file_path)?;
+        }
+        if let Some(old_file) = old_file.take() {
+            old_file.unlock()?;
+        }
+
+        let mut file = File {
+            stamp: Some(Stamp::read(&file_path, sqlite.is_none())?),
+            path: file_path,
+            inner: file,
+            encryption,
+            sqlite,
+            read_only,
+            backed_up: None,
+            includes: Vec::new(),
+        };
+
+        if version < format::VERSION && !read_only {
+            backups::keep_format(&file.path, version, &bytes)?;
+            accounts.part(None).save_one(&mut file)?;
+        }
+        file.includes = includes;
+
+        Ok((accounts, file))
+    }
+
+    /// Copies a ledger to another file, converting between RON and SQLite as
+    /// chosen by the extensions.
+    pub(crate) fn convert(from: PathBuf, to: PathBuf) -> anyhow::Result<()> {
+        let (accounts, file) = Self::load(&mut None, from, None)?;
+        accounts.save_dialogue(&mut Some(file), to)?;
+        Ok(())
+    }
+}
+
+const fn default_backups() -> usize {
+    backups::DEFAULT_COUNT
+}
+
+impl Index<usize> for Accounts {
+    type Output = Account;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        &self.inner[i]
+    }
+}
+
+impl IndexMut<usize> for Accounts {
+    fn index_mut(&mut self, i: usize) -> &mut Self::Output {
+        &mut self.inner[i]
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Group {
+    pub name: String,
+    pub members: Vec<usize>,
+    /// The include the group was loaded from, like `Account::source`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<usize>,
+}
+
+impl Group {
+    fn remove_inner(&mut self, index: usize) -> Option<usize> {
+        for (remove, i) in &mut self.members.iter().enumerate() {
+            if index == *i {
+                return Some(remove);
+            }
+        }
+
+        None
+    }
+
+    pub(crate) fn remove(&mut self, index: usize) -> Option<usize> {
+        for i in &mut self.members.iter_mut() {
+            if *i > index {
+                *i -= 1;
+            }
+        }
+
+        if let Some(index) = self.remove_inner(index) {
+            return Some(self.members.remove(index));
+        }
+
+        None
+    }
+}
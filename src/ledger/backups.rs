@@ -0,0 +1,216 @@
+use std::{
+    ffi::OsString,
+    fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use chrono::{TimeDelta, Utc};
+
+use super::{
+    encryption::{self, Encryption},
+    sqlite,
+};
+
+pub const DEFAULT_COUNT: usize = 10;
+
+/// How long saves go without another backup after one is made, so saving often,
+/// e.g. with autosave, doesn't rotate out the backups worth going back to.
+pub const INTERVAL: Duration = Duration::from_secs(15 * 60);
+
+/// Copies the ledger to a timestamped file next to it, e.g.
+/// `ledger.ron.2025-01-31T09-15-00-250.bak`, and removes all but the newest
+/// `count` backups. Nothing is copied when the ledger doesn't exist yet.
+pub fn create(file_path: &Path, count: usize) -> anyhow::Result<()> {
+    if count == 0 || !fs::exists(file_path)? {
+        return prune(file_path, count);
+    }
+
+    // A later timestamp rather than a suffix keeps the names in order.
+    let mut now = Utc::now();
+    let backup = loop {
+        let mut name = file_name(file_path)?;
+        name.push(format!(".{}.bak", now.format("%Y-%m-%dT%H-%M-%S-%3f")));
+        let backup = file_path.with_file_name(name);
+        if !fs::exists(&backup)? {
+            break backup;
+        }
+        now += TimeDelta::milliseconds(1);
+    };
+    if sqlite::is_sqlite_file(file_path)? {
+        sqlite::back_up(file_path, &backup)?;
+        if let Some(permissions) = permissions(file_path)? {
+            fs::set_permissions(&backup, permissions)?;
+        }
+    } else {
+        fs::copy(file_path, backup)?;
+    }
+
+    prune(file_path, count)
+}
+
+/// Backs the ledger up the first time it is saved after it is loaded, and
+/// after that at most once every `INTERVAL`.
+pub fn create_due(
+    file_path: &Path,
+    count: usize,
+    backed_up: &mut Option<Instant>,
+) -> anyhow::Result<()> {
+    if backed_up.is_some_and(|instant| instant.elapsed() < INTERVAL) {
+        return Ok(());
+    }
+
+    create(file_path, count)?;
+    *backed_up = Some(Instant::now());
+    Ok(())
+}
+
+/// The backups of the ledger, newest first.
+pub fn list(file_path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut prefix = file_name(file_path)?;
+    prefix.push(".");
+    let prefix = prefix.to_string_lossy().to_string();
+
+    let mut backups = Vec::new();
+    for entry in fs::read_dir(directory(file_path))? {
+        let path = entry?.path();
+        if let Some(name) = path.file_name().and_then(|name| name.to_str())
+            && name.starts_with(&prefix)
+            && path.extension().is_some_and(|extension| extension == "bak")
+        {
+            backups.push(path);
+        }
+    }
+
+    // The timestamps sort in the same order as the names.
+    backups.sort();
+    backups.reverse();
+    Ok(backups)
+}
+
+/// Encrypts the backups that aren't encrypted yet.
+pub fn encrypt(file_path: &Path, encryption: &Encryption) -> anyhow::Result<()> {
+    for backup in list(file_path)? {
+        let bytes = fs::read(&backup)?;
+        if !encryption::is_encrypted(&bytes) {
+            fs::write(&backup, encryption.encrypt(&bytes)?)?;
+        }
+    }
+    Ok(())
+}
+
+fn prune(file_path: &Path, count: usize) -> anyhow::Result<()> {
+    for backup in list(file_path)?.into_iter().skip(count) {
+        fs::remove_file(backup)?;
+    }
+    Ok(())
+}
+
+/// Keeps a ledger as it was before it was upgraded from format `version`, e.g.
+/// as `ledger.ron.format-0`, or `ledger.ron.format-0.1` if that is taken, so an
+/// earlier copy is never written over. Returns where it was kept.
+pub fn keep_format(file_path: &Path, version: u32, bytes: &[u8]) -> anyhow::Result<PathBuf> {
+    let mut name = file_name(file_path)?;
+    name.push(format!(".format-{version}"));
+    let permissions = permissions(file_path)?;
+
+    let mut copy = 0;
+    loop {
+        let mut numbered = name.clone();
+        if copy > 0 {
+            numbered.push(format!(".{copy}"));
+        }
+        let path = file_path.with_file_name(numbered);
+        match fs::File::create_new(&path) {
+            Ok(mut file) => {
+                if let Some(permissions) = permissions {
+                    file.set_permissions(permissions)?;
+                }
+                file.write_all(bytes)?;
+                file.sync_all()?;
+                return Ok(path);
+            }
+            Err(error) if error.kind() == io::ErrorKind::AlreadyExists => copy += 1,
+            Err(error) => return Err(error.into()),
+        }
+    }
+}
+
+/// The directory the ledger is in.
+pub fn directory(file_path: &Path) -> &Path {
+    file_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."))
+}
+
+/// Who can read and write the file, `None` if there is no file yet. Files
+/// written in its place get them, as they are created with the defaults.
+pub fn permissions(file_path: &Path) -> io::Result<Option<fs::Permissions>> {
+    match fs::metadata(file_path) {
+        Ok(metadata) => Ok(Some(metadata.permissions())),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+pub fn file_name(file_path: &Path) -> anyhow::Result<OsString> {
+    file_path
+        .file_name()
+        .map(ToOwned::to_owned)
+        .with_context(|| format!("{} isn't a file name!", file_path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::testing;
+
+    #[test]
+    fn backups_made_at_once_dont_overwrite_each_other() {
+        let path = testing::directory("backups-at-once").join("ledger.ron");
+        for contents in ["first", "second", "third"] {
+            fs::write(&path, contents).unwrap();
+            create(&path, DEFAULT_COUNT).unwrap();
+        }
+
+        let contents: Vec<_> = list(&path)
+            .unwrap()
+            .iter()
+            .map(|backup| fs::read_to_string(backup).unwrap())
+            .collect();
+        assert_eq!(contents, ["third", "second", "first"]);
+    }
+
+    #[test]
+    fn backups_are_made_once_an_interval() {
+        let path = testing::directory("backups-interval").join("ledger.ron");
+        fs::write(&path, "ledger").unwrap();
+
+        let mut backed_up = None;
+        create_due(&path, DEFAULT_COUNT, &mut backed_up).unwrap();
+        create_due(&path, DEFAULT_COUNT, &mut backed_up).unwrap();
+        assert_eq!(list(&path).unwrap().len(), 1);
+
+        backed_up = backed_up.and_then(|instant| instant.checked_sub(INTERVAL));
+        create_due(&path, DEFAULT_COUNT, &mut backed_up).unwrap();
+        assert_eq!(list(&path).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn format_copies_dont_overwrite_each_other() {
+        let path = testing::directory("backups-format").join("ledger.ron");
+        fs::write(&path, "current").unwrap();
+        for contents in ["first", "second"] {
+            keep_format(&path, 0, contents.as_bytes()).unwrap();
+        }
+
+        let first = path.with_file_name("ledger.ron.format-0");
+        let second = path.with_file_name("ledger.ron.format-0.1");
+        assert_eq!(fs::read_to_string(first).unwrap(), "first");
+        assert_eq!(fs::read_to_string(second).unwrap(), "second");
+        assert!(list(&path).unwrap().is_empty());
+    }
+}
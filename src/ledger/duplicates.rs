@@ -0,0 +1,187 @@
+use std::{collections::HashSet, fmt::Display};
+
+use chrono::{DateTime, TimeDelta, Utc, serde::ts_seconds};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use super::account::{transaction::Transaction, transactions::Transactions};
+
+/// Finds transactions that are probably the same charge entered twice, e.g. by
+/// hand and then again by a bank import.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Matcher {
+    /// How many days apart two transactions with the same amount can be.
+    pub days: u32,
+    /// The lowest comment similarity, from 0 to 1, that still counts as a match.
+    pub similarity: f64,
+}
+
+impl Matcher {
+    /// Returns the index of the transaction in `txs` that best matches `tx`,
+    /// skipping the indexes in `claimed`. Comments are only compared when both
+    /// transactions have one, as those entered by hand often don't.
+    pub fn find<T: Clone + Display>(
+        &self,
+        txs: &Transactions<T>,
+        tx: &Transaction,
+        claimed: &HashSet<usize>,
+    ) -> Option<usize> {
+        let days = TimeDelta::days(i64::from(self.days));
+
+        txs.txs
+            .iter()
+            .enumerate()
+            .filter(|(i, tx_old)| {
+                !claimed.contains(i)
+                    && tx_old.amount == tx.amount
+                    && (tx_old.date - tx.date).abs() <= days
+            })
+            .map(|(i, tx_old)| {
+                let distance = (tx_old.date - tx.date).abs();
+                (i, similarity(&tx_old.comment, &tx.comment), distance)
+            })
+            .filter(|(i, similarity, _)| {
+                *similarity >= self.similarity
+                    || txs.txs[*i].comment.trim().is_empty()
+                    || tx.comment.trim().is_empty()
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1).then(b.2.cmp(&a.2)))
+            .map(|(i, _, _)| i)
+    }
+}
+
+impl Default for Matcher {
+    fn default() -> Self {
+        Self {
+            days: 3,
+            similarity: 0.5,
+        }
+    }
+}
+
+/// How much two comments have in common, from 0 to 1.
+///
+/// This is the share of the words in the shorter comment that also appear in the
+/// longer one, so "Coffee" and "Checking: COFFEE SHOP 1234" are a full match.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let words = |s: &str| -> HashSet<String> {
+        s.split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    };
+    let (a, b) = (words(a), words(b));
+    let (shorter, longer) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+
+    if shorter.is_empty() {
+        return 0.0;
+    }
+
+    let common = shorter.intersection(&longer).count();
+    #[allow(clippy::cast_precision_loss)]
+    let similarity = common as f64 / shorter.len() as f64;
+    similarity
+}
+
+/// A remembered answer to "is this imported row already in the ledger?".
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Decision {
+    #[serde(with = "ts_seconds")]
+    pub date: DateTime<Utc>,
+    pub amount: Decimal,
+    pub comment: String,
+    /// The row was merged into an existing transaction instead of being added.
+    pub merged: bool,
+}
+
+impl Decision {
+    pub fn new(tx: &Transaction, merged: bool) -> Self {
+        Self {
+            date: tx.date,
+            amount: tx.amount,
+            comment: tx.comment.clone(),
+            merged,
+        }
+    }
+
+    pub fn matches(&self, tx: &Transaction) -> bool {
+        self.date == tx.date && self.amount == tx.amount && self.comment == tx.comment
+    }
+}
+
+/// The decisions that can still matter to an import whose earliest row is on
+/// `start`: those about rows from `days` before it on. Older rows aren't in
+/// later statements, so what was decided about them is let go.
+pub fn prune(decisions: &[Decision], start: DateTime<Utc>, days: u32) -> Vec<Decision> {
+    let cutoff = start - TimeDelta::days(i64::from(days));
+    decisions
+        .iter()
+        .filter(|decision| decision.date >= cutoff)
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::ledger::{money::Fiat, parse_date};
+
+    fn tx(date: &str, comment: &str) -> Transaction {
+        Transaction {
+            amount: dec!(-4.50),
+            balance: dec!(0),
+            comment: comment.to_string(),
+            date: parse_date(date).unwrap(),
+            snapshot: false,
+        }
+    }
+
+    fn find(old: &[Transaction], new: &Transaction) -> Option<usize> {
+        let mut txs = Transactions::new(Fiat::Usd);
+        txs.txs = old.to_vec();
+        Matcher::default().find(&txs, new, &HashSet::new())
+    }
+
+    #[test]
+    fn default_matcher_compares_comments() {
+        let bank = tx("2025-03-02", "CHECKCARD 0301 COFFEE SHOP 1234");
+        assert_eq!(find(&[tx("2025-03-01", "Coffee")], &bank), Some(0));
+        assert_eq!(find(&[tx("2025-03-01", "Bus fare")], &bank), None);
+        assert_eq!(find(&[tx("2025-02-20", "Coffee")], &bank), None);
+    }
+
+    #[test]
+    fn comments_left_empty_match_any() {
+        let bank = tx("2025-03-02", "CHECKCARD 0301 COFFEE SHOP 1234");
+        assert_eq!(find(&[tx("2025-03-01", "")], &bank), Some(0));
+        assert_eq!(
+            find(&[tx("2025-03-01", "Coffee")], &tx("2025-03-02", "")),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn similarity_is_the_share_of_the_shorter_comment() {
+        assert!((similarity("Coffee", "Checking: COFFEE SHOP 1234") - 1.0).abs() < f64::EPSILON);
+        assert!((similarity("coffee beans", "COFFEE SHOP") - 0.5).abs() < f64::EPSILON);
+        assert!(similarity("", "COFFEE").abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn prune_lets_go_of_decisions_before_the_window() {
+        let decisions = [
+            Decision::new(&tx("2025-01-10", "old"), true),
+            Decision::new(&tx("2025-02-27", "edge"), false),
+            Decision::new(&tx("2025-03-05", "new"), true),
+        ];
+        let kept = prune(&decisions, parse_date("2025-03-02").unwrap(), 3);
+        let comments: Vec<_> = kept
+            .iter()
+            .map(|decision| decision.comment.as_str())
+            .collect();
+        assert_eq!(comments, ["edge", "new"]);
+    }
+}
@@ -0,0 +1,351 @@
+use std::{error::Error, fmt::Display};
+
+use anyhow::Context;
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    KeyInit, XChaCha20Poly1305, XNonce,
+    aead::{Aead, Payload},
+};
+
+/// Marks an encrypted file. It is followed by the format version, the Argon2id
+/// memory, time and parallelism costs as little endian u32s, the salt, the nonce
+/// and the XChaCha20-Poly1305 ciphertext of the RON. Everything before the
+/// ciphertext is authenticated with it.
+const MAGIC: &[u8] = b"financial-accounts encrypted\n";
+const VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 3 * 4 + SALT_LEN;
+
+/// The most memory in KiB, passes and lanes a file can ask the key derivation
+/// for, so opening one made to be opened slowly fails instead. Well above the
+/// defaults it is encrypted with.
+const MAX_PARAMS: [u32; 3] = [1024 * 1024, 16, 8];
+
+/// The key a ledger is encrypted with and how it was derived.
+#[derive(Clone)]
+pub struct Encryption {
+    #[cfg_attr(not(feature = "gui"), allow(dead_code))]
+    passphrase: String,
+    params: [u32; 3],
+    salt: [u8; SALT_LEN],
+    key: [u8; KEY_LEN],
+}
+
+impl Encryption {
+    /// Derives a key from the passphrase with a new random salt.
+    pub fn new(passphrase: String) -> anyhow::Result<Self> {
+        let mut salt = [0; SALT_LEN];
+        getrandom::fill(&mut salt).map_err(|error| anyhow::Error::msg(error.to_string()))?;
+        let params = [
+            Params::DEFAULT_M_COST,
+            Params::DEFAULT_T_COST,
+            Params::DEFAULT_P_COST,
+        ];
+        let key = derive_key(&passphrase, params, &salt)?;
+
+        Ok(Self {
+            passphrase,
+            params,
+            salt,
+            key,
+        })
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn passphrase(&self) -> &str {
+        &self.passphrase
+    }
+
+    fn header(&self) -> Vec<u8> {
+        let mut header = Vec::with_capacity(HEADER_LEN);
+        header.extend_from_slice(MAGIC);
+        header.push(VERSION);
+        for param in self.params {
+            header.extend_from_slice(&param.to_le_bytes());
+        }
+        header.extend_from_slice(&self.salt);
+        header
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let mut nonce = [0; NONCE_LEN];
+        getrandom::fill(&mut nonce).map_err(|error| anyhow::Error::msg(error.to_string()))?;
+
+        let header = self.header();
+        let cipher = XChaCha20Poly1305::new(&self.key.into());
+        let ciphertext = cipher
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: plaintext,
+                    aad: &header,
+                },
+            )
+            .map_err(|_| anyhow::Error::msg("Encrypting the file failed!"))?;
+
+        let mut bytes = header;
+        bytes.extend_from_slice(&nonce);
+        bytes.extend_from_slice(&ciphertext);
+        Ok(bytes)
+    }
+
+    /// Decrypts the bytes of an encrypted file and returns them with the key
+    /// they were encrypted with, so saving again uses the same passphrase.
+    pub fn decrypt(bytes: &[u8], passphrase: &str) -> anyhow::Result<(Vec<u8>, Self)> {
+        let parts = Parts::split(bytes)?;
+        let key = derive_key(passphrase, parts.params, &parts.salt)?;
+        let plaintext = parts.open(&key)?;
+
+        Ok((
+            plaintext,
+            Self {
+                passphrase: passphrase.to_string(),
+                params: parts.params,
+                salt: parts.salt,
+                key,
+            },
+        ))
+    }
+
+    /// Decrypts bytes this encrypted, without deriving the key again unless
+    /// they were encrypted with another salt.
+    #[cfg(feature = "gui")]
+    pub fn decrypt_again(&self, bytes: &[u8]) -> anyhow::Result<Vec<u8>> {
+        let parts = Parts::split(bytes)?;
+        if parts.params == self.params && parts.salt == self.salt {
+            parts.open(&self.key)
+        } else {
+            parts.open(&derive_key(&self.passphrase, parts.params, &parts.salt)?)
+        }
+    }
+}
+
+/// An encrypted file taken apart.
+struct Parts<'a> {
+    header: &'a [u8],
+    params: [u32; 3],
+    salt: [u8; SALT_LEN],
+    nonce: &'a [u8],
+    ciphertext: &'a [u8],
+}
+
+impl<'a> Parts<'a> {
+    fn split(bytes: &'a [u8]) -> anyhow::Result<Self> {
+        if bytes.len() < HEADER_LEN + NONCE_LEN || !is_encrypted(bytes) {
+            return Err(anyhow::Error::msg("The file isn't an encrypted ledger."));
+        }
+
+        let (header, rest) = bytes.split_at(HEADER_LEN);
+        let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+        let version = header[MAGIC.len()];
+        if version != VERSION {
+            return Err(anyhow::Error::msg(format!(
+                "The file is encrypted with format {version}, which this version can't read."
+            )));
+        }
+
+        let mut params = [0; 3];
+        let mut offset = MAGIC.len() + 1;
+        for param in &mut params {
+            *param = u32::from_le_bytes(header[offset..offset + 4].try_into()?);
+            offset += 4;
+        }
+        let salt = header[offset..].try_into()?;
+        Ok(Self {
+            header,
+            params,
+            salt,
+            nonce,
+            ciphertext,
+        })
+    }
+
+    fn open(&self, key: &[u8; KEY_LEN]) -> anyhow::Result<Vec<u8>> {
+        XChaCha20Poly1305::new(key.into())
+            .decrypt(
+                XNonce::from_slice(self.nonce),
+                Payload {
+                    msg: self.ciphertext,
+                    aad: self.header,
+                },
+            )
+            .map_err(|_| anyhow::Error::msg("Wrong passphrase, or the file is damaged."))
+    }
+}
+
+impl std::fmt::Debug for Encryption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Encryption")
+            .field("params", &self.params)
+            .finish_non_exhaustive()
+    }
+}
+
+fn derive_key(passphrase: &str, params: [u32; 3], salt: &[u8]) -> anyhow::Result<[u8; KEY_LEN]> {
+    if params
+        .iter()
+        .zip(MAX_PARAMS)
+        .any(|(param, max)| *param > max)
+    {
+        return Err(anyhow::Error::msg(format!(
+            "The file asks for key derivation parameters {params:?}, more than the most allowed, {MAX_PARAMS:?}."
+        )));
+    }
+
+    let [m_cost, t_cost, p_cost] = params;
+    let params = Params::new(m_cost, t_cost, p_cost, Some(KEY_LEN))
+        .map_err(|error| anyhow::Error::msg(error.to_string()))
+        .context("The file has bad key derivation parameters.")?;
+
+    let mut key = [0; KEY_LEN];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|error| anyhow::Error::msg(error.to_string()))?;
+    Ok(key)
+}
+
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// Returned when an encrypted file is opened without a passphrase.
+#[derive(Clone, Debug)]
+pub struct NeedsPassphrase;
+
+impl Display for NeedsPassphrase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(f, "The file is encrypted and needs a passphrase.")
+    }
+}
+
+impl Error for NeedsPassphrase {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::{Currency, Fiat, Ledger, testing};
+
+    const PLAINTEXT: &[u8] = b"(version: 1)";
+
+    fn encrypted() -> Vec<u8> {
+        let encryption = Encryption::new("correct horse".to_string()).unwrap();
+        encryption.encrypt(PLAINTEXT).unwrap()
+    }
+
+    #[test]
+    fn decrypting_gives_back_what_was_encrypted() {
+        let bytes = encrypted();
+        assert!(is_encrypted(&bytes));
+        let (plaintext, encryption) = Encryption::decrypt(&bytes, "correct horse").unwrap();
+        assert_eq!(plaintext, PLAINTEXT);
+
+        // Encrypted again with the same key, under a new nonce.
+        let again = encryption.encrypt(PLAINTEXT).unwrap();
+        assert_ne!(again, bytes);
+        assert_eq!(
+            Encryption::decrypt(&again, "correct horse").unwrap().0,
+            PLAINTEXT
+        );
+        #[cfg(feature = "gui")]
+        assert_eq!(encryption.decrypt_again(&again).unwrap(), PLAINTEXT);
+    }
+
+    #[test]
+    fn a_wrong_passphrase_is_an_error() {
+        let error = Encryption::decrypt(&encrypted(), "wrong horse").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Wrong passphrase, or the file is damaged."
+        );
+    }
+
+    #[test]
+    fn changes_to_the_file_are_rejected() {
+        let bytes = encrypted();
+        let mut ciphertext = bytes.clone();
+        *ciphertext.last_mut().unwrap() ^= 1;
+        assert!(Encryption::decrypt(&ciphertext, "correct horse").is_err());
+
+        // The salt, which the key is derived with and is authenticated too.
+        let mut salt = bytes.clone();
+        salt[HEADER_LEN - 1] ^= 1;
+        assert!(Encryption::decrypt(&salt, "correct horse").is_err());
+
+        let mut version = bytes.clone();
+        version[MAGIC.len()] = VERSION + 1;
+        assert!(Encryption::decrypt(&version, "correct horse").is_err());
+
+        assert!(Encryption::decrypt(&bytes[..HEADER_LEN], "correct horse").is_err());
+    }
+
+    #[test]
+    fn costly_key_derivation_parameters_are_refused() {
+        let mut bytes = encrypted();
+        let m_cost = MAGIC.len() + 1;
+        bytes[m_cost..m_cost + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let error = Encryption::decrypt(&bytes, "correct horse").unwrap_err();
+        assert!(error.to_string().contains("more than the most allowed"));
+
+        let mut bytes = encrypted();
+        let t_cost = MAGIC.len() + 1 + 4;
+        bytes[t_cost..t_cost + 4].copy_from_slice(&1000_u32.to_le_bytes());
+        assert!(Encryption::decrypt(&bytes, "correct horse").is_err());
+    }
+
+    #[test]
+    fn encrypted_and_plain_ledgers_load() {
+        let path = testing::directory("encryption-ledgers").join("ledger.ron");
+        let mut ledger = Ledger::create(path.clone()).unwrap();
+        ledger
+            .add_account("Checking", Currency::Fiat(Fiat::Usd))
+            .unwrap();
+        ledger.encrypt("correct horse".to_string()).unwrap();
+        ledger.close().unwrap();
+        assert!(is_encrypted(&std::fs::read(&path).unwrap()));
+
+        let error = Ledger::open_read_only(path.clone(), None).unwrap_err();
+        assert!(error.is::<NeedsPassphrase>());
+        assert!(Ledger::open_read_only(path.clone(), Some("wrong horse")).is_err());
+
+        let mut ledger = Ledger::open(path.clone(), Some("correct horse")).unwrap();
+        assert!(ledger.account("Checking").is_ok());
+        ledger.decrypt().unwrap();
+        ledger.close().unwrap();
+
+        // A plain ledger loads whether or not a passphrase is given.
+        for passphrase in [None, Some("correct horse")] {
+            let ledger = Ledger::open_read_only(path.clone(), passphrase).unwrap();
+            assert!(ledger.account("Checking").is_ok());
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn an_encrypted_backup_is_restored_encrypted() {
+        use crate::ledger::{Accounts, backups};
+
+        let path = testing::directory("encryption-backup").join("ledger.ron");
+        let mut ledger = Ledger::create(path.clone()).unwrap();
+        ledger
+            .add_account("Checking", Currency::Fiat(Fiat::Usd))
+            .unwrap();
+        ledger.encrypt("correct horse".to_string()).unwrap();
+        ledger.close().unwrap();
+        backups::create(&path, 10).unwrap();
+
+        let backup = backups::list(&path).unwrap().remove(0);
+        let error = Accounts::read_backup(&backup, None).unwrap_err();
+        assert!(error.is::<NeedsPassphrase>());
+
+        let (accounts, encryption) = Accounts::read_backup(&backup, Some("correct horse")).unwrap();
+        assert!(encryption.is_some());
+        accounts
+            .save_encrypted(&mut None, path.clone(), encryption)
+            .unwrap()
+            .unlock()
+            .unwrap();
+        assert!(is_encrypted(&std::fs::read(&path).unwrap()));
+    }
+}
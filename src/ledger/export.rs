@@ -0,0 +1,558 @@
+use std::{fmt::Write, path::Path};
+
+use chrono::{DateTime, Duration, Utc};
+use clap::ValueEnum;
+use rust_decimal::Decimal;
+
+use super::{account::Account, accounts::Accounts};
+
+/// Where the money of a transaction comes from or goes to, as far as the
+/// ledger knows.
+const BALANCING: [&str; 2] = ["Expenses:Unknown", "Income:Unknown"];
+
+/// The plain-text accounting formats a ledger can be exported to.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum Export {
+    /// A ledger-cli or hledger journal
+    #[default]
+    Ledger,
+    /// A Beancount ledger, e.g. for Fava
+    Beancount,
+}
+
+impl Export {
+    /// The format chosen by the extension of the file exported to, e.g.
+    /// `.journal` or `.beancount`.
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "journal" | "hledger" | "ledger" | "dat" => Some(Self::Ledger),
+            "beancount" | "bean" => Some(Self::Beancount),
+            _ => None,
+        }
+    }
+
+    #[must_use]
+    pub fn render(self, accounts: &Accounts) -> String {
+        match self {
+            Self::Ledger => ledger(accounts),
+            Self::Beancount => beancount(accounts),
+        }
+    }
+}
+
+/// The accounts as a ledger-cli and hledger journal. Every account is under
+/// `Assets`, after the first group it is in, e.g. `Assets:Cash:Checking`. Each
+/// transaction is balanced by `Income:Unknown` or `Expenses:Unknown` and
+/// asserts the balance after it.
+///
+/// An account holding a secondary currency posts the quantities of it, priced
+/// at the value recorded nearest before, and the values become `P` prices, so
+/// `hledger bal -V` shows what the accounts screen does.
+fn ledger(accounts: &Accounts) -> String {
+    let paths = paths(accounts, component);
+    let mut text = String::from("; Exported from financial-accounts\n\n");
+
+    for path in paths.iter().map(String::as_str).chain(BALANCING) {
+        let _ = writeln!(text, "account {path}");
+    }
+
+    let mut prices = Vec::new();
+    for account in &accounts.inner {
+        if let Some(txs_2nd) = &account.txs_2nd {
+            let symbol = commodity(&txs_2nd.currency.symbol());
+            let currency = commodity(&account.txs_1st.currency.symbol());
+            for (date, price) in valuations(account) {
+                prices.push((date, format!("P {} {symbol} {price} {currency}", day(date))));
+            }
+        }
+    }
+    prices.sort();
+    prices.dedup();
+    if !prices.is_empty() {
+        text.push('\n');
+    }
+    for (_, price) in prices {
+        let _ = writeln!(text, "{price}");
+    }
+
+    let mut entries = Vec::new();
+    for (account, path) in accounts.inner.iter().zip(&paths) {
+        if let Some(txs_2nd) = &account.txs_2nd {
+            let symbol = commodity(&txs_2nd.currency.symbol());
+            let currency = commodity(&account.txs_1st.currency.symbol());
+            let valuations = valuations(account);
+            for tx in &txs_2nd.txs {
+                let mut posting = format!("{} {symbol}", tx.amount);
+                if let Some(price) = price_at(&valuations, tx.date) {
+                    let _ = write!(posting, " @ {price} {currency}");
+                }
+                let _ = write!(posting, " = {} {symbol}", tx.balance);
+                entries.push(entry(tx.date, &tx.comment, path, &posting, tx.amount));
+            }
+        } else {
+            let currency = commodity(&account.txs_1st.currency.symbol());
+            for tx in &account.txs_1st.txs {
+                let posting = format!("{} {currency} = {} {currency}", tx.amount, tx.balance);
+                entries.push(entry(tx.date, &tx.comment, path, &posting, tx.amount));
+            }
+        }
+    }
+    // Stable, so the transactions of an account on the same day stay in the
+    // order their balances were asserted in.
+    entries.sort_by_key(|(date, _)| *date);
+    for (_, entry) in entries {
+        text.push('\n');
+        text.push_str(&entry);
+    }
+
+    text
+}
+
+/// The accounts as a Beancount ledger, for Fava. It is laid out as the
+/// ledger-cli journal is, except that the values of an account holding a
+/// secondary currency become `price` directives, and the balance after the
+/// last transaction of each day is a `balance` directive on the next, as
+/// Beancount checks balances at the start of the day.
+fn beancount(accounts: &Accounts) -> String {
+    let paths = paths(accounts, bean_component);
+    let mut text = String::from("; Exported from financial-accounts\n\n");
+
+    let opened = accounts
+        .inner
+        .iter()
+        .flat_map(|account| {
+            account
+                .txs_1st
+                .txs
+                .iter()
+                .chain(account.txs_2nd.iter().flat_map(|txs_2nd| &txs_2nd.txs))
+        })
+        .map(|tx| tx.date)
+        .min()
+        .unwrap_or_else(Utc::now);
+    let opened = day(opened);
+    for path in paths.iter().map(String::as_str).chain(BALANCING) {
+        let _ = writeln!(text, "{opened} open {path}");
+    }
+
+    commodities(&mut text, accounts, &opened);
+
+    let mut prices = Vec::new();
+    for account in &accounts.inner {
+        if let Some(txs_2nd) = &account.txs_2nd {
+            let symbol = bean_commodity(&txs_2nd.currency.symbol());
+            let currency = bean_commodity(&account.txs_1st.currency.symbol());
+            for (date, price) in valuations(account) {
+                prices.push((
+                    date,
+                    format!("{} price {symbol} {price} {currency}", day(date)),
+                ));
+            }
+        }
+    }
+    prices.sort();
+    prices.dedup();
+    if !prices.is_empty() {
+        text.push('\n');
+    }
+    for (_, price) in prices {
+        let _ = writeln!(text, "{price}");
+    }
+
+    let mut entries: Vec<_> = accounts
+        .inner
+        .iter()
+        .zip(&paths)
+        .flat_map(|(account, path)| bean_entries(account, path))
+        .collect();
+    // Stable, so the transactions of an account on the same day stay in
+    // order, and the balances of a day come before its transactions, as
+    // Beancount checks them.
+    entries.sort_by_key(|(date, tx, _)| (date.date_naive(), *tx));
+    for (_, _, entry) in entries {
+        text.push('\n');
+        text.push_str(&entry);
+    }
+
+    text
+}
+
+/// The `commodity` directives of the crypto, metals and stocks, with their
+/// descriptions as names.
+fn commodities(text: &mut String, accounts: &Accounts, opened: &str) {
+    let commodities = accounts
+        .crypto
+        .iter()
+        .map(|crypto| (&crypto.symbol, &crypto.description))
+        .chain(
+            accounts
+                .metals
+                .iter()
+                .map(|metal| (&metal.symbol, &metal.description)),
+        )
+        .chain(
+            accounts
+                .stocks_plus
+                .iter()
+                .map(|stock_plus| (&stock_plus.symbol, &stock_plus.description)),
+        );
+    let mut declared = Vec::new();
+    for (symbol, description) in commodities {
+        let symbol = bean_commodity(symbol);
+        if !declared.contains(&symbol) {
+            let _ = write!(
+                text,
+                "\n{opened} commodity {symbol}\n  name: {}\n",
+                quote(description)
+            );
+            declared.push(symbol);
+        }
+    }
+}
+
+/// The transactions of the account, in the secondary currency if it holds
+/// one, each with whether it is a transaction rather than a balance.
+fn bean_entries(account: &Account, path: &str) -> Vec<(DateTime<Utc>, bool, String)> {
+    let (txs, symbol) = if let Some(txs_2nd) = &account.txs_2nd {
+        (&txs_2nd.txs, bean_commodity(&txs_2nd.currency.symbol()))
+    } else {
+        (
+            &account.txs_1st.txs,
+            bean_commodity(&account.txs_1st.currency.symbol()),
+        )
+    };
+    let currency = bean_commodity(&account.txs_1st.currency.symbol());
+    let valuations = valuations(account);
+
+    let mut entries = Vec::new();
+    for (i, tx) in txs.iter().enumerate() {
+        let mut posting = format!("{} {symbol}", tx.amount);
+        if account.txs_2nd.is_some()
+            && let Some(price) = price_at(&valuations, tx.date)
+        {
+            let _ = write!(posting, " @ {price} {currency}");
+        }
+        let other = if tx.amount.is_sign_negative() {
+            BALANCING[0]
+        } else {
+            BALANCING[1]
+        };
+        let entry = format!(
+            "{} * {}\n  {path}  {posting}\n  {other}\n",
+            day(tx.date),
+            quote(&tx.comment)
+        );
+        entries.push((tx.date, true, entry));
+
+        if txs
+            .get(i + 1)
+            .is_none_or(|next| next.date.date_naive() != tx.date.date_naive())
+        {
+            let date = tx.date + Duration::days(1);
+            let balance = format!("{} balance {path} {} {symbol}\n", day(date), tx.balance);
+            entries.push((date, false, balance));
+        }
+    }
+    entries
+}
+
+/// The journal account of each account, in the order of `Accounts::inner`.
+fn paths(accounts: &Accounts, component: fn(&str) -> String) -> Vec<String> {
+    accounts
+        .inner
+        .iter()
+        .enumerate()
+        .map(|(i, account)| {
+            let mut path = String::from("Assets:");
+            if let Some(group) = accounts
+                .groups
+                .iter()
+                .find(|group| group.members.contains(&i))
+            {
+                path.push_str(&component(&group.name));
+                path.push(':');
+            }
+            path.push_str(&component(&account.name));
+            path
+        })
+        .collect()
+}
+
+fn entry(
+    date: DateTime<Utc>,
+    comment: &str,
+    path: &str,
+    posting: &str,
+    amount: Decimal,
+) -> (DateTime<Utc>, String) {
+    let description = comment.split_whitespace().collect::<Vec<_>>().join(" ");
+    let other = if amount.is_sign_negative() {
+        BALANCING[0]
+    } else {
+        BALANCING[1]
+    };
+    let entry = format!(
+        "{} {description}\n    {path}  {posting}\n    {other}\n",
+        day(date)
+    );
+    (date, entry.replace(" \n", "\n"))
+}
+
+/// The value of one unit of the secondary currency each time the value of the
+/// account was recorded, from the quantity held then.
+fn valuations(account: &Account) -> Vec<(DateTime<Utc>, Decimal)> {
+    let Some(txs_2nd) = &account.txs_2nd else {
+        return Vec::new();
+    };
+    account
+        .txs_1st
+        .txs
+        .iter()
+        .filter_map(|tx| {
+            let quantity = txs_2nd
+                .txs
+                .iter()
+                .take_while(|tx_2nd| tx_2nd.date <= tx.date)
+                .last()?
+                .balance;
+            if quantity.is_zero() {
+                return None;
+            }
+            Some((tx.date, (tx.balance / quantity).round_dp(8).normalize()))
+        })
+        .collect()
+}
+
+/// The last valuation at or before the date, or the first one if there is
+/// none before it.
+fn price_at(valuations: &[(DateTime<Utc>, Decimal)], date: DateTime<Utc>) -> Option<Decimal> {
+    valuations
+        .iter()
+        .rev()
+        .find(|(valued, _)| *valued <= date)
+        .or_else(|| valuations.first())
+        .map(|(_, price)| *price)
+}
+
+/// A name that can be part of an account path: no `:`, which separates the
+/// parts, and no runs of spaces, which end the path.
+fn component(name: &str) -> String {
+    name.replace(':', "-")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// A name that Beancount takes as part of an account: each word capitalized
+/// and run together, keeping only letters, digits and `-`, e.g. `CreditCard`.
+fn bean_component(name: &str) -> String {
+    let mut component: String = name
+        .split_whitespace()
+        .flat_map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_ascii_uppercase())
+                .into_iter()
+                .chain(chars)
+        })
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect();
+    if !component.starts_with(|c: char| c.is_ascii_uppercase() || c.is_ascii_digit()) {
+        component.insert(0, 'X');
+    }
+    component
+}
+
+/// A symbol as Beancount takes it: upper case, starting with a letter, e.g.
+/// `BRK.B`.
+fn bean_commodity(symbol: &str) -> String {
+    let mut commodity: String = symbol
+        .to_uppercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '\'' | '.' | '_' | '-') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    if !commodity.starts_with(|c: char| c.is_ascii_uppercase()) {
+        commodity.insert(0, 'X');
+    }
+    while commodity.ends_with(|c: char| !c.is_ascii_alphanumeric()) {
+        commodity.pop();
+    }
+    commodity
+}
+
+/// A Beancount string.
+fn quote(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A symbol, quoted unless it is all letters, e.g. `"BRK.B"`.
+fn commodity(symbol: &str) -> String {
+    if !symbol.is_empty() && symbol.chars().all(char::is_alphabetic) {
+        symbol.to_string()
+    } else {
+        format!("\"{symbol}\"")
+    }
+}
+
+fn day(date: DateTime<Utc>) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::ledger::{parse_date, testing};
+
+    #[test]
+    fn from_path_goes_by_the_extension() {
+        assert_eq!(
+            Export::from_path(Path::new("a/b.journal")),
+            Some(Export::Ledger)
+        );
+        assert_eq!(Export::from_path(Path::new("b.dat")), Some(Export::Ledger));
+        assert_eq!(
+            Export::from_path(Path::new("b.bean")),
+            Some(Export::Beancount)
+        );
+        assert_eq!(Export::from_path(Path::new("b.ron")), None);
+        assert_eq!(Export::from_path(Path::new("journal")), None);
+    }
+
+    #[test]
+    fn ledger_declares_the_accounts_under_their_group() {
+        let text = Export::Ledger.render(&testing::accounts());
+        assert!(text.starts_with(
+            "; Exported from financial-accounts\n\n\
+             account Assets:Cash:Checking\n\
+             account Assets:Gold\n\
+             account Assets:Cash:Savings\n\
+             account Expenses:Unknown\n\
+             account Income:Unknown\n"
+        ));
+    }
+
+    #[test]
+    fn ledger_asserts_the_balance_after_each_transaction() {
+        let text = Export::Ledger.render(&testing::accounts());
+        assert!(text.contains(
+            "\n2024-01-15 Coffee: beans\n    Assets:Cash:Checking  -25 USD = 75 USD\n    Expenses:Unknown\n"
+        ));
+        assert!(text.contains(
+            "\n2024-02-01 statement\n    Assets:Cash:Checking  425 USD = 500 USD\n    Income:Unknown\n"
+        ));
+    }
+
+    #[test]
+    fn ledger_prices_holdings_at_their_values() {
+        let text = Export::Ledger.render(&testing::accounts());
+        assert!(text.contains(
+            "\nP 2024-01-01 XAU 2000 USD\nP 2024-02-01 XAU 2100 USD\nP 2024-03-01 XAU 2300 USD\n"
+        ));
+        assert!(text.contains(
+            "\n2024-02-15 buy more\n    Assets:Gold  0.5 XAU @ 2100 USD = 1.5 XAU\n    Income:Unknown\n"
+        ));
+    }
+
+    #[test]
+    fn ledger_entries_are_in_date_order() {
+        let text = Export::Ledger.render(&testing::accounts());
+        let dates: Vec<_> = text
+            .lines()
+            .filter(|line| line.starts_with("20"))
+            .map(|line| &line[..10])
+            .collect();
+        assert!(dates.is_sorted());
+        assert_eq!(dates.len(), 7);
+    }
+
+    #[test]
+    fn beancount_opens_the_accounts_and_names_the_commodities() {
+        let text = Export::Beancount.render(&testing::accounts());
+        assert!(text.starts_with(
+            "; Exported from financial-accounts\n\n\
+             2024-01-01 open Assets:Cash:Checking\n\
+             2024-01-01 open Assets:Gold\n\
+             2024-01-01 open Assets:Cash:Savings\n\
+             2024-01-01 open Expenses:Unknown\n\
+             2024-01-01 open Income:Unknown\n\
+             \n2024-01-01 commodity XAU\n  name: \"Gold Troy Oz\"\n\
+             \n2024-01-01 price XAU 2000 USD\n\
+             2024-02-01 price XAU 2100 USD\n\
+             2024-03-01 price XAU 2300 USD\n"
+        ));
+    }
+
+    #[test]
+    fn beancount_checks_the_balance_of_a_day_on_the_next() {
+        let text = Export::Beancount.render(&testing::accounts());
+        assert!(text.contains(
+            "\n2024-01-15 * \"Coffee: beans\"\n  Assets:Cash:Checking  -25 USD\n  Expenses:Unknown\n"
+        ));
+        assert!(text.contains("\n2024-01-16 balance Assets:Cash:Checking 75 USD\n"));
+        assert!(text.contains(
+            "\n2024-02-15 * \"buy more\"\n  Assets:Gold  0.5 XAU @ 2100 USD\n  Income:Unknown\n"
+        ));
+        assert!(text.contains("\n2024-02-16 balance Assets:Gold 1.5 XAU\n"));
+    }
+
+    #[test]
+    fn beancount_checks_a_day_once_after_its_last_transaction() {
+        let mut accounts = testing::accounts();
+        let tx = testing::tx("2024-01-15", "-5", "Tip", false);
+        accounts.inner[0].insert_tx(false, tx);
+        let text = Export::Beancount.render(&accounts);
+        assert_eq!(text.matches("2024-01-16 balance").count(), 1);
+        assert!(text.contains("\n2024-01-16 balance Assets:Cash:Checking 70 USD\n"));
+        // The balance of a day comes before its transactions.
+        let balance = text
+            .find("2024-01-02 balance Assets:Cash:Checking")
+            .unwrap();
+        let coffee = text.find("2024-01-15 * \"Coffee").unwrap();
+        assert!(balance < coffee);
+    }
+
+    #[test]
+    fn price_at_takes_the_last_value_before() {
+        let date = |date| parse_date(date).unwrap();
+        let valuations = [
+            (date("2024-02-01"), dec!(10)),
+            (date("2024-03-01"), dec!(12)),
+        ];
+        assert_eq!(price_at(&valuations, date("2024-01-01")), Some(dec!(10)));
+        assert_eq!(price_at(&valuations, date("2024-03-01")), Some(dec!(12)));
+        assert_eq!(price_at(&valuations, date("2024-02-15")), Some(dec!(10)));
+        assert_eq!(price_at(&[], date("2024-02-15")), None);
+    }
+
+    #[test]
+    fn components_and_symbols_are_safe_in_a_journal() {
+        assert_eq!(component("Visa:  Gold card"), "Visa- Gold card");
+        assert_eq!(commodity("USD"), "USD");
+        assert_eq!(commodity("BRK.B"), "\"BRK.B\"");
+        assert_eq!(commodity(""), "\"\"");
+    }
+
+    #[test]
+    fn components_and_symbols_are_safe_in_beancount() {
+        assert_eq!(bean_component("credit card"), "CreditCard");
+        assert_eq!(bean_component("Visa: gold card"), "VisaGoldCard");
+        assert_eq!(bean_component("401k"), "401k");
+        assert_eq!(bean_component("épargne"), "Xpargne");
+        assert_eq!(bean_commodity("brk.b"), "BRK.B");
+        assert_eq!(bean_commodity("1INCH"), "X1INCH");
+        assert_eq!(bean_commodity("€"), "X");
+        assert_eq!(bean_commodity("AB$"), "AB");
+        assert_eq!(quote("Said  \"hi\" \\o/"), "\"Said \\\"hi\\\" \\\\o/\"");
+    }
+}
@@ -0,0 +1,245 @@
+use anyhow::Context;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+/// The version of the file format written by this version of the program.
+/// Bump it and add a migration whenever a change to the file would stop older
+/// files from loading or change what they mean.
+pub const VERSION: u32 = 1;
+
+type Migration = fn(String) -> anyhow::Result<String>;
+
+/// `MIGRATIONS[n]` upgrades a version `n` file to version `n + 1`.
+const MIGRATIONS: [Migration; VERSION as usize] = [from_0];
+
+/// Just enough of a file to find out which version it is.
+#[derive(Deserialize)]
+struct Header {
+    #[serde(default)]
+    version: u32,
+}
+
+/// Returns the version of the file.
+pub fn version(ron: &str) -> anyhow::Result<u32> {
+    let header: Header = ron::from_str(ron).context("The file isn't a ledger.")?;
+    Ok(header.version)
+}
+
+/// Fails if the file comes from a newer version of the program.
+pub fn check_version(version: u32) -> anyhow::Result<()> {
+    if version > VERSION {
+        return Err(anyhow::Error::msg(format!(
+            "The file is format version {version}, but this version of financial-accounts \
+             only reads up to version {VERSION}. Please upgrade financial-accounts."
+        )));
+    }
+    Ok(())
+}
+
+/// Upgrades a file to the current version, one version at a time.
+pub fn migrate(mut ron: String, version: u32) -> anyhow::Result<String> {
+    check_version(version)?;
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        ron = migration(ron).with_context(|| format!("upgrading from format version {from}"))?;
+    }
+    Ok(ron)
+}
+
+/// Files written before there was a version. Prices and entered balances were
+/// plain rows then, which a backdated change would recompute from the rows
+/// before them, so they become snapshots: the values of an account holding a
+/// crypto, metal or stock, and any row whose balance isn't the one before it
+/// plus its amount. Everything else added since has a default that keeps the
+/// meaning. The file is read with the types of `v0`, so changes to today's
+/// types don't change how version 0 files are read.
+#[allow(clippy::needless_pass_by_value)]
+fn from_0(ron: String) -> anyhow::Result<String> {
+    let mut accounts: v0::Accounts = ron::from_str(&ron)?;
+    for account in &mut accounts.inner {
+        let holds = account.txs_2nd.is_some();
+        mark_snapshots(&mut account.txs_1st.txs, holds);
+        if let Some(txs_2nd) = &mut account.txs_2nd {
+            mark_snapshots(&mut txs_2nd.txs, false);
+        }
+    }
+    accounts.version = 1;
+    Ok(ron::to_string(&accounts)?)
+}
+
+fn mark_snapshots(txs: &mut [v0::Transaction], all: bool) {
+    let mut balance = Decimal::ZERO;
+    for tx in txs {
+        if all || tx.balance != balance + tx.amount {
+            tx.snapshot = true;
+        }
+        balance = tx.balance;
+    }
+}
+
+/// A ledger as version 0 wrote it. Frozen: these stay as they are when the
+/// types the program uses change. Only `version` and `snapshot` are new, and
+/// are written for version 1 to read.
+mod v0 {
+    use chrono::{DateTime, Utc, serde::ts_seconds};
+    use rust_decimal::Decimal;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Deserialize, Serialize)]
+    pub struct Accounts {
+        #[serde(default)]
+        pub version: u32,
+        #[serde(rename = "accounts")]
+        pub inner: Vec<Account>,
+        groups: Vec<Group>,
+        crypto: Vec<Crypto>,
+        fiats: Vec<Fiat>,
+        metals: Vec<Metal>,
+        stocks_plus: Vec<StockPlus>,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    pub struct Account {
+        name: String,
+        #[serde(rename = "transactions")]
+        pub txs_1st: Transactions<Fiat>,
+        #[serde(rename = "transactions_secondary")]
+        pub txs_2nd: Option<Transactions<Currency>>,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    pub struct Transactions<T> {
+        currency: T,
+        pub txs: Vec<Transaction>,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    pub struct Transaction {
+        pub amount: Decimal,
+        pub balance: Decimal,
+        comment: String,
+        #[serde(with = "ts_seconds")]
+        date: DateTime<Utc>,
+        #[serde(default)]
+        pub snapshot: bool,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    pub struct Group {
+        name: String,
+        members: Vec<usize>,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    pub enum Currency {
+        Crypto(Crypto),
+        Fiat(Fiat),
+        Metal(Metal),
+        StockPlus(StockPlus),
+    }
+
+    #[derive(Deserialize, Serialize)]
+    pub struct Crypto {
+        currency: Fiat,
+        description: String,
+        symbol: String,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    pub struct Metal {
+        currency: Fiat,
+        description: String,
+        symbol: String,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    pub struct StockPlus {
+        description: String,
+        symbol: String,
+    }
+
+    #[derive(Deserialize, Serialize)]
+    pub enum Fiat {
+        Usd,
+        Aud,
+        Gbp,
+        Eur,
+        Cad,
+        Chf,
+        Jpy,
+        Krw,
+        Inr,
+        Cny,
+        Zar,
+        Thb,
+        Sgd,
+        Hkd,
+        Czk,
+        Pln,
+        Myr,
+        Rub,
+        Aed,
+        Kwd,
+        Egp,
+        Omr,
+        Sar,
+        Mxn,
+        Jod,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::ledger::{Accounts, Transaction, testing::FORMAT_0};
+
+    fn load(ron: &str) -> Accounts {
+        let version = version(ron).unwrap();
+        ron::from_str(&migrate(ron.to_string(), version).unwrap()).unwrap()
+    }
+
+    fn snapshots(txs: &[Transaction]) -> Vec<bool> {
+        txs.iter().map(|tx| tx.snapshot).collect()
+    }
+
+    #[test]
+    fn reads_the_version() {
+        assert_eq!(version(FORMAT_0).unwrap(), 0);
+        assert_eq!(version("(version: 1, accounts: [])").unwrap(), 1);
+        assert!(version("not a ledger").is_err());
+    }
+
+    #[test]
+    fn refuses_newer_versions() {
+        assert!(check_version(VERSION).is_ok());
+        assert!(check_version(VERSION + 1).is_err());
+        assert!(migrate(String::new(), VERSION + 1).is_err());
+    }
+
+    #[test]
+    fn from_0_marks_values_and_entered_balances_as_snapshots() {
+        let accounts = load(FORMAT_0);
+        assert_eq!(accounts.version, 1);
+
+        let checking = &accounts.inner[0];
+        assert_eq!(
+            snapshots(&checking.txs_1st.txs),
+            [false, false, true, false]
+        );
+
+        let gold = &accounts.inner[1];
+        assert_eq!(snapshots(&gold.txs_1st.txs), [true, true, true]);
+        assert_eq!(snapshots(&gold.txs_2nd.as_ref().unwrap().txs), [false]);
+        let balances: Vec<_> = gold.txs_1st.txs.iter().map(|tx| tx.balance).collect();
+        assert_eq!(balances, [dec!(2000), dec!(2100), dec!(2300)]);
+    }
+
+    #[test]
+    fn current_files_are_left_alone() {
+        let ron = ron::to_string(&load(FORMAT_0)).unwrap();
+        assert_eq!(version(&ron).unwrap(), VERSION);
+        assert_eq!(migrate(ron.clone(), VERSION).unwrap(), ron);
+    }
+}
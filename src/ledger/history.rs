@@ -0,0 +1,439 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    account::{Account, transaction::Transaction},
+    accounts::{Accounts, Group},
+    crypto::Crypto,
+    duplicates::{Decision, Matcher},
+    metal::Metal,
+    money::Fiat,
+    stocks::StockPlus,
+};
+
+/// A change to the ledger that has been made and can be undone and redone.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum Command {
+    AddCrypto(Crypto),
+    AddFiat(Fiat),
+    AddGroup(Group),
+    AddMetal(Metal),
+    AddStockPlus(StockPlus),
+    /// Commands that were made together and are undone together.
+    Batch(Vec<Command>),
+    EditTransaction {
+        account: usize,
+        secondary: bool,
+        index_before: usize,
+        index_after: usize,
+        before: Transaction,
+        after: Transaction,
+    },
+    InsertAccount(AccountChange),
+    InsertTransaction(TransactionChange),
+    RemoveAccount(AccountChange),
+    RemoveGroup {
+        index: usize,
+        group: Group,
+    },
+    RemoveTransaction(TransactionChange),
+    SetBackups {
+        before: usize,
+        after: usize,
+    },
+    SetDecisions {
+        account: usize,
+        before: Vec<Decision>,
+        after: Vec<Decision>,
+    },
+    SetMatcher {
+        before: Matcher,
+        after: Matcher,
+    },
+    SetScripts {
+        before: Vec<PathBuf>,
+        after: Vec<PathBuf>,
+    },
+}
+
+impl Command {
+    #[cfg(feature = "gui")]
+    pub fn apply(&self, accounts: &mut Accounts) {
+        match self {
+            Self::AddCrypto(crypto) => accounts.crypto.push(crypto.clone()),
+            Self::AddFiat(fiat) => accounts.fiats.push(fiat.clone()),
+            Self::AddGroup(group) => accounts.groups.push(group.clone()),
+            Self::AddMetal(metal) => accounts.metals.push(metal.clone()),
+            Self::AddStockPlus(stock_plus) => accounts.stocks_plus.push(stock_plus.clone()),
+            Self::Batch(commands) => {
+                for command in commands {
+                    command.apply(accounts);
+                }
+            }
+            Self::EditTransaction {
+                account,
+                secondary,
+                index_before,
+                index_after,
+                after,
+                ..
+            } => {
+                let account = &mut accounts[*account];
+                account.remove_tx(*secondary, *index_before);
+                account.insert_tx_at(*secondary, *index_after, after.clone());
+            }
+            Self::InsertAccount(change) => change.insert(accounts),
+            Self::InsertTransaction(change) => change.insert(accounts),
+            Self::RemoveAccount(change) => change.remove(accounts),
+            Self::RemoveGroup { index, .. } => {
+                accounts.groups.remove(*index);
+            }
+            Self::RemoveTransaction(change) => change.remove(accounts),
+            Self::SetBackups { after, .. } => accounts.backups = *after,
+            Self::SetDecisions { account, after, .. } => {
+                accounts[*account].import_decisions.clone_from(after);
+            }
+            Self::SetMatcher { after, .. } => accounts.duplicate_matcher = after.clone(),
+            Self::SetScripts { after, .. } => accounts.scripts.clone_from(after),
+        }
+    }
+
+    pub fn undo(&self, accounts: &mut Accounts) {
+        match self {
+            Self::AddCrypto(_) => {
+                accounts.crypto.pop();
+            }
+            Self::AddFiat(_) => {
+                accounts.fiats.pop();
+            }
+            Self::AddGroup(_) => {
+                accounts.groups.pop();
+            }
+            Self::AddMetal(_) => {
+                accounts.metals.pop();
+            }
+            Self::AddStockPlus(_) => {
+                accounts.stocks_plus.pop();
+            }
+            Self::Batch(commands) => {
+                for command in commands.iter().rev() {
+                    command.undo(accounts);
+                }
+            }
+            Self::EditTransaction {
+                account,
+                secondary,
+                index_before,
+                index_after,
+                before,
+                ..
+            } => {
+                let account = &mut accounts[*account];
+                account.remove_tx(*secondary, *index_after);
+                account.insert_tx_at(*secondary, *index_before, before.clone());
+            }
+            Self::InsertAccount(change) => change.remove(accounts),
+            Self::InsertTransaction(change) => change.remove(accounts),
+            Self::RemoveAccount(change) => change.insert(accounts),
+            Self::RemoveGroup { index, group } => accounts.groups.insert(*index, group.clone()),
+            Self::RemoveTransaction(change) => change.insert(accounts),
+            Self::SetBackups { before, .. } => accounts.backups = *before,
+            Self::SetDecisions {
+                account, before, ..
+            } => accounts[*account].import_decisions.clone_from(before),
+            Self::SetMatcher { before, .. } => accounts.duplicate_matcher = before.clone(),
+            Self::SetScripts { before, .. } => accounts.scripts.clone_from(before),
+        }
+    }
+
+    /// Whether the command, once applied, changed an account or group from a
+    /// ledger that is included read-only.
+    pub fn touches_read_only(&self, accounts: &Accounts) -> bool {
+        match self {
+            Self::EditTransaction { account, .. } | Self::SetDecisions { account, .. } => {
+                accounts.is_read_only(accounts[*account].source)
+            }
+            Self::AddGroup(group) | Self::RemoveGroup { group, .. } => {
+                accounts.is_read_only(group.source)
+            }
+            Self::Batch(commands) => commands
+                .iter()
+                .any(|command| command.touches_read_only(accounts)),
+            Self::InsertAccount(change) | Self::RemoveAccount(change) => {
+                accounts.is_read_only(change.account.source)
+            }
+            Self::InsertTransaction(change) | Self::RemoveTransaction(change) => {
+                accounts.is_read_only(accounts[change.account].source)
+            }
+            Self::AddCrypto(_)
+            | Self::AddFiat(_)
+            | Self::AddMetal(_)
+            | Self::AddStockPlus(_)
+            | Self::SetBackups { .. }
+            | Self::SetMatcher { .. }
+            | Self::SetScripts { .. } => false,
+        }
+    }
+}
+
+/// An account at `index`, with the groups as they are without it and with it.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct AccountChange {
+    pub index: usize,
+    pub account: Box<Account>,
+    pub groups_without: Vec<Group>,
+    pub groups_with: Vec<Group>,
+}
+
+impl AccountChange {
+    fn insert(&self, accounts: &mut Accounts) {
+        accounts
+            .inner
+            .insert(self.index, self.account.as_ref().clone());
+        accounts.groups.clone_from(&self.groups_with);
+    }
+
+    fn remove(&self, accounts: &mut Accounts) {
+        accounts.inner.remove(self.index);
+        accounts.groups.clone_from(&self.groups_without);
+    }
+}
+
+/// A transaction at `index` in one of an account's transaction lists.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct TransactionChange {
+    pub account: usize,
+    pub secondary: bool,
+    pub index: usize,
+    pub tx: Transaction,
+}
+
+impl TransactionChange {
+    fn insert(&self, accounts: &mut Accounts) {
+        accounts[self.account].insert_tx_at(self.secondary, self.index, self.tx.clone());
+    }
+
+    fn remove(&self, accounts: &mut Accounts) {
+        accounts[self.account].remove_tx(self.secondary, self.index);
+    }
+}
+
+/// The undo and redo stacks for the session.
+#[cfg(feature = "gui")]
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Command>,
+    redo: Vec<Command>,
+}
+
+#[cfg(feature = "gui")]
+impl History {
+    pub const fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub const fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Remembers a command that has already been applied.
+    pub fn record(&mut self, command: Command) {
+        self.undo.push(command);
+        self.redo.clear();
+    }
+
+    /// Redoes the last undone command and returns it.
+    pub fn redo(&mut self, accounts: &mut Accounts) -> Option<&Command> {
+        let command = self.redo.pop()?;
+        command.apply(accounts);
+        self.undo.push(command);
+        self.undo.last()
+    }
+
+    /// Undoes the last command and returns it.
+    pub fn undo(&mut self, accounts: &mut Accounts) -> Option<&Command> {
+        let command = self.undo.pop()?;
+        command.undo(accounts);
+        self.redo.push(command);
+        self.redo.last()
+    }
+}
+
+#[cfg(all(test, feature = "gui"))]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::ledger::{Currency, testing};
+
+    fn ron(accounts: &Accounts) -> String {
+        accounts.to_string().unwrap()
+    }
+
+    /// Makes a change the way the window does, then checks that undoing it
+    /// gives back the ledger from before and redoing it the one from after.
+    fn round_trip(change: impl FnOnce(&mut Accounts) -> Command) {
+        let mut accounts = testing::accounts();
+        let before = ron(&accounts);
+        let command = change(&mut accounts);
+        let after = ron(&accounts);
+        assert_ne!(before, after, "{command:?}");
+
+        let mut history = History::default();
+        history.record(command);
+        for _ in 0..2 {
+            assert!(history.undo(&mut accounts).is_some());
+            assert_eq!(ron(&accounts), before);
+            assert!(history.redo(&mut accounts).is_some());
+            assert_eq!(ron(&accounts), after);
+        }
+    }
+
+    fn set_decisions(accounts: &mut Accounts) -> Command {
+        let before = accounts[0].import_decisions.clone();
+        let after = vec![Decision {
+            date: Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap(),
+            amount: dec!(-50),
+            comment: "GROCERIES 1234".to_string(),
+            merged: true,
+        }];
+        accounts[0].import_decisions.clone_from(&after);
+        Command::SetDecisions {
+            account: 0,
+            before,
+            after,
+        }
+    }
+
+    /// Moves Checking's first transaction after its statement.
+    fn edit_transaction(accounts: &mut Accounts) -> Command {
+        let account = &mut accounts[0];
+        let before = account.remove_tx(false, 0);
+        let after = testing::tx("2024-02-20", "150", "Pay day, late", false);
+        let index_after = account.insert_tx(false, after.clone());
+        Command::EditTransaction {
+            account: 0,
+            secondary: false,
+            index_before: 0,
+            index_after,
+            before,
+            after,
+        }
+    }
+
+    #[test]
+    fn every_command_is_undone_and_redone() {
+        round_trip(|accounts| {
+            let crypto = Crypto {
+                currency: Fiat::Usd,
+                description: "Bitcoin".to_string(),
+                symbol: "BTC".to_string(),
+            };
+            accounts.crypto.push(crypto.clone());
+            Command::AddCrypto(crypto)
+        });
+        round_trip(|accounts| {
+            accounts.fiats.push(Fiat::Eur);
+            Command::AddFiat(Fiat::Eur)
+        });
+        round_trip(|accounts| {
+            let group = Group {
+                name: "Metals".to_string(),
+                members: vec![1],
+                source: None,
+            };
+            accounts.groups.push(group.clone());
+            Command::AddGroup(group)
+        });
+        round_trip(|accounts| {
+            let metal = Metal {
+                currency: Fiat::Usd,
+                description: "Silver Troy Oz".to_string(),
+                symbol: "XAG".to_string(),
+            };
+            accounts.metals.push(metal.clone());
+            Command::AddMetal(metal)
+        });
+        round_trip(|accounts| {
+            let stock_plus = StockPlus {
+                description: "Vanguard Total Stock Market".to_string(),
+                symbol: "VTI".to_string(),
+            };
+            accounts.stocks_plus.push(stock_plus.clone());
+            Command::AddStockPlus(stock_plus)
+        });
+        round_trip(edit_transaction);
+        round_trip(|accounts| {
+            let account = Account::new("Brokerage".to_string(), Currency::Fiat(Fiat::Usd));
+            accounts.insert_account(account, &[0])
+        });
+        round_trip(|accounts| {
+            let tx = testing::tx("2024-03-15", "0.25", "buy", false);
+            accounts.insert_tx(1, true, tx)
+        });
+        round_trip(|accounts| accounts.remove_account(0));
+        round_trip(|accounts| {
+            let group = accounts.groups.remove(0);
+            Command::RemoveGroup { index: 0, group }
+        });
+        round_trip(|accounts| {
+            let tx = accounts[0].remove_tx(false, 1);
+            Command::RemoveTransaction(TransactionChange {
+                account: 0,
+                secondary: false,
+                index: 1,
+                tx,
+            })
+        });
+        round_trip(|accounts| {
+            let before = std::mem::replace(&mut accounts.backups, 3);
+            Command::SetBackups { before, after: 3 }
+        });
+        round_trip(set_decisions);
+        round_trip(|accounts| {
+            let after = Matcher {
+                days: 7,
+                similarity: 0.8,
+            };
+            let before = std::mem::replace(&mut accounts.duplicate_matcher, after.clone());
+            Command::SetMatcher { before, after }
+        });
+        round_trip(|accounts| {
+            let after = vec![PathBuf::from("alerts.rhai")];
+            let before = std::mem::replace(&mut accounts.scripts, after.clone());
+            Command::SetScripts { before, after }
+        });
+        round_trip(|accounts| {
+            let tx = testing::tx("2024-03-02", "-5", "tip", false);
+            let commands = vec![
+                accounts.insert_tx(0, false, tx),
+                set_decisions(accounts),
+                accounts.remove_account(2),
+            ];
+            Command::Batch(commands)
+        });
+    }
+
+    #[test]
+    fn a_new_command_clears_what_can_be_redone() {
+        let mut accounts = testing::accounts();
+        let mut history = History::default();
+        history.record(accounts.remove_account(2));
+        history.undo(&mut accounts);
+        assert!(history.can_redo());
+
+        accounts.fiats.push(Fiat::Eur);
+        history.record(Command::AddFiat(Fiat::Eur));
+        assert!(!history.can_redo());
+        assert!(history.redo(&mut accounts).is_none());
+        assert_eq!(accounts.inner.len(), 3);
+        assert!(history.can_undo());
+    }
+}
@@ -0,0 +1,361 @@
+use std::collections::HashSet;
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    account::{transaction::Transaction, transactions::Transactions},
+    accounts::Accounts,
+    duplicates::Decision,
+    import_investor_360::Investor360,
+    money::Fiat,
+};
+
+/// Where the rows being reviewed came from.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum Source {
+    Boa(usize),
+    Investor360,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Row {
+    pub account: String,
+    pub checked: bool,
+    /// For Investor 360 rows the balance holds the quantity and the amount is
+    /// worked out when the row is committed.
+    pub tx: Transaction,
+    pub value: String,
+    pub holding: Option<Investor360>,
+    /// The row as it was parsed, before any edits.
+    pub fingerprint: Transaction,
+    pub duplicate: bool,
+    /// The existing transaction this row probably duplicates.
+    pub matched: Option<Transaction>,
+    /// What was decided about this row the last time it was imported.
+    pub decision: Option<Decision>,
+    /// The row is older than the account's last transaction.
+    pub backdated: bool,
+}
+
+impl Row {
+    fn new(account: String, tx: Transaction, value: Decimal) -> Self {
+        Self {
+            account,
+            checked: true,
+            fingerprint: tx.clone(),
+            tx,
+            value: value.to_string(),
+            holding: None,
+            duplicate: false,
+            matched: None,
+            decision: None,
+            backdated: false,
+        }
+    }
+}
+
+/// Parsed rows that are staged for review before they are written to the ledger.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Import {
+    pub source: Source,
+    pub rows: Vec<Row>,
+}
+
+impl Import {
+    pub fn boa(accounts: &Accounts, index: usize, mut txs: Transactions<Fiat>) -> Self {
+        let account = &accounts[index];
+        let date_last = account.txs_1st.max_date();
+        let mut claimed = HashSet::new();
+        txs.sort();
+
+        let rows = txs
+            .txs
+            .into_iter()
+            .map(|tx| {
+                let amount = tx.amount;
+                let mut row = Row::new(account.name.clone(), tx, amount);
+                row.decision = account
+                    .import_decisions
+                    .iter()
+                    .find(|decision| decision.matches(&row.tx))
+                    .cloned();
+
+                if row.decision.is_none()
+                    && let Some(i) =
+                        accounts
+                            .duplicate_matcher
+                            .find(&account.txs_1st, &row.tx, &claimed)
+                {
+                    claimed.insert(i);
+                    row.matched = Some(account.txs_1st.txs[i].clone());
+                }
+
+                row.duplicate = row.decision.is_some() || row.matched.is_some();
+                row.backdated = date_last.is_some_and(|date| row.tx.date < date);
+                row.checked = !row.duplicate;
+                row
+            })
+            .collect();
+
+        Self {
+            source: Source::Boa(index),
+            rows,
+        }
+    }
+
+    /// The decisions to remember so importing the same rows again gives the same
+    /// result: proposed merges that were accepted or rejected, and rows whose
+    /// values were edited.
+    pub fn decisions(&self) -> Vec<Decision> {
+        self.clone()
+            .rows_applied()
+            .into_iter()
+            .filter(|row| row.decision.is_none())
+            .filter_map(|row| {
+                let edited = row.tx.amount != row.fingerprint.amount
+                    || row.tx.comment != row.fingerprint.comment;
+
+                if let Some(tx) = &row.matched {
+                    // Exact matches are found again without being remembered.
+                    if Decision::new(tx, false).matches(&row.fingerprint) {
+                        None
+                    } else {
+                        Some(Decision::new(&row.fingerprint, !row.checked))
+                    }
+                } else if row.checked && edited {
+                    Some(Decision::new(&row.fingerprint, false))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub fn investor_360(accounts: &Accounts, records: Vec<Investor360>) -> Self {
+        let rows = records
+            .into_iter()
+            .map(|record| {
+                let name = format!("Investor 360: {}", &record.symbol);
+                let tx = Transaction {
+                    amount: dec!(0),
+                    balance: record.quantity,
+                    comment: record.description.clone(),
+                    date: chrono::Utc::now(),
+                    snapshot: true,
+                };
+                let mut row = Row::new(name, tx, record.quantity);
+
+                let balance = accounts
+                    .inner
+                    .iter()
+                    .find(|account| account.name == row.account)
+                    .and_then(|account| {
+                        if record.price == dec!(1) {
+                            Some(account.balance_1st())
+                        } else {
+                            account.balance_2nd()
+                        }
+                    });
+                row.duplicate = balance == Some(record.quantity);
+                row.checked = !row.duplicate;
+                row.holding = Some(record);
+                row
+            })
+            .collect();
+
+        Self {
+            source: Source::Investor360,
+            rows,
+        }
+    }
+
+    /// The rows that are checked, with their edited values applied.
+    pub fn checked(self) -> Vec<Row> {
+        self.rows_applied()
+            .into_iter()
+            .filter(|row| row.checked)
+            .collect()
+    }
+
+    /// The probable duplicates left unchecked, so merged into the transactions
+    /// they match, with their edited values applied.
+    pub fn merged(self) -> Vec<Row> {
+        self.rows_applied()
+            .into_iter()
+            .filter(|row| !row.checked && row.decision.is_none() && row.matched.is_some())
+            .collect()
+    }
+
+    fn rows_applied(self) -> Vec<Row> {
+        let is_boa = matches!(self.source, Source::Boa(_));
+
+        self.rows
+            .into_iter()
+            .map(|mut row| {
+                let value = row.value.trim().parse().unwrap_or_default();
+                if is_boa {
+                    row.tx.amount = value;
+                } else {
+                    row.tx.balance = value;
+                }
+                row.tx.comment = row.tx.comment.trim().to_string();
+                row
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::ledger::{account::Account, money::Currency};
+
+    fn holding(symbol: &str, quantity: Decimal, price: Decimal) -> Investor360 {
+        Investor360 {
+            description: format!("{symbol} holding"),
+            symbol: symbol.to_string(),
+            quantity,
+            price,
+        }
+    }
+
+    /// A ledger with 100 dollars of cash already imported from Investor 360.
+    fn accounts() -> Accounts {
+        let mut accounts = Accounts::default();
+        let mut cash = Account::new("Investor 360: CASH".to_string(), Currency::Fiat(Fiat::Usd));
+        cash.txs_1st.txs.push(Transaction {
+            amount: dec!(100),
+            balance: dec!(100),
+            comment: String::new(),
+            date: chrono::Utc::now(),
+            snapshot: true,
+        });
+        accounts.insert_account(cash, &[]);
+        accounts
+    }
+
+    #[test]
+    fn investor_360_leaves_out_unchanged_holdings() {
+        let accounts = accounts();
+        let records = vec![
+            holding("CASH", dec!(100), dec!(1)),
+            holding("AAPL", dec!(5), dec!(200)),
+        ];
+
+        let import = Import::investor_360(&accounts, records);
+        let rows: Vec<_> = import
+            .rows
+            .iter()
+            .map(|row| (row.account.as_str(), row.duplicate, row.checked))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("Investor 360: CASH", true, false),
+                ("Investor 360: AAPL", false, true),
+            ]
+        );
+
+        let checked = import.checked();
+        assert_eq!(checked.len(), 1);
+        assert_eq!(checked[0].tx.balance, dec!(5));
+    }
+
+    #[test]
+    fn investor_360_commits_new_holdings_as_accounts() {
+        let mut accounts = accounts();
+        let records = vec![
+            holding("CASH", dec!(120), dec!(1)),
+            holding("AAPL", dec!(5), dec!(200)),
+        ];
+        let rows = Import::investor_360(&accounts, records).checked();
+
+        let commands = accounts.commit_investor_360(rows).unwrap();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(accounts.inner.len(), 2);
+        let aapl = &accounts.inner[0];
+        assert_eq!(aapl.name, "Investor 360: AAPL");
+        assert_eq!(aapl.balance_2nd(), Some(dec!(5)));
+        assert_eq!(accounts.inner[1].balance_1st(), dec!(120));
+    }
+
+    /// A row that can't be added undoes the rows added before it, so
+    /// submitting the import again doesn't add them twice.
+    #[test]
+    fn investor_360_commits_all_rows_or_none() {
+        let mut accounts = accounts();
+        let records = vec![
+            holding("AAPL", dec!(5), dec!(200)),
+            holding("CASH", dec!(120), dec!(1)),
+            // Priced, so it goes to the secondary currency CASH doesn't have.
+            holding("CASH", dec!(3), dec!(2)),
+        ];
+        let rows = Import::investor_360(&accounts, records).checked();
+
+        assert!(accounts.commit_investor_360(rows).is_err());
+        assert_eq!(accounts.inner.len(), 1);
+        assert_eq!(accounts.inner[0].txs_1st.txs.len(), 1);
+        assert_eq!(accounts.inner[0].balance_1st(), dec!(100));
+    }
+
+    fn bank_row(date: &str, amount: Decimal, comment: &str) -> Transaction {
+        Transaction {
+            amount,
+            balance: dec!(0),
+            comment: comment.to_string(),
+            date: crate::ledger::parse_date(date).unwrap(),
+            snapshot: false,
+        }
+    }
+
+    /// Unchecking a probable duplicate merges it: the transaction entered by
+    /// hand gets the bank's comment, and decisions from long before the
+    /// statement are let go. Undoing puts it all back.
+    #[test]
+    fn boa_merges_duplicates_and_prunes_decisions() {
+        let mut accounts = Accounts::default();
+        let mut checking = Account::new("Checking".to_string(), Currency::Fiat(Fiat::Usd));
+        checking.insert_tx(false, bank_row("2025-03-01", dec!(-4.50), ""));
+        checking.import_decisions.push(Decision::new(
+            &bank_row("2024-12-01", dec!(-9), "OLD"),
+            false,
+        ));
+        accounts.insert_account(checking, &[]);
+
+        let mut statement = Transactions::new(Fiat::Usd);
+        statement.txs = vec![
+            bank_row("2025-03-02", dec!(-4.50), "COFFEE SHOP"),
+            bank_row("2025-03-03", dec!(-20), "GROCERY"),
+        ];
+        let import = Import::boa(&accounts, 0, statement);
+        assert!(import.rows[0].duplicate && !import.rows[0].checked);
+        assert!(import.rows[1].checked);
+
+        let before = accounts.inner[0].clone();
+        let commands = accounts.commit_boa(0, &import);
+        let comments: Vec<_> = accounts.inner[0]
+            .txs_1st
+            .txs
+            .iter()
+            .map(|tx| tx.comment.as_str())
+            .collect();
+        assert_eq!(comments, ["COFFEE SHOP", "GROCERY"]);
+        let decisions: Vec<_> = accounts.inner[0]
+            .import_decisions
+            .iter()
+            .map(|decision| (decision.comment.as_str(), decision.merged))
+            .collect();
+        assert_eq!(decisions, [("COFFEE SHOP", true)]);
+
+        for command in commands.iter().rev() {
+            command.undo(&mut accounts);
+        }
+        assert_eq!(accounts.inner[0].txs_1st.txs, before.txs_1st.txs);
+        assert_eq!(accounts.inner[0].import_decisions.len(), 1);
+        assert_eq!(accounts.inner[0].import_decisions[0].comment, "OLD");
+    }
+}
@@ -59,6 +59,7 @@ pub fn import_boa(file_path: PathBuf) -> anyhow::Result<Transactions<Fiat>> {
             balance: dec!(0),
             comment,
             date: NaiveDateTime::parse_from_str(&boa_record.date, "%m/%d/%Y %H:%M:%S")?.and_utc(),
+            snapshot: false,
         };
         records.push(record);
     }
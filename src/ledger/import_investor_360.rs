@@ -0,0 +1,158 @@
+use std::{fmt, path::Path, str::FromStr};
+
+use anyhow::Context;
+use calamine::{Data, Reader, open_workbook_auto};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+const DESCRIPTION: &str = "Description";
+const SYMBOL: &str = "Symbol";
+const QUANTITY: &str = "Quantity";
+const PRICE: &str = "Price ($)";
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Investor360 {
+    pub description: String,
+    pub symbol: String,
+    pub quantity: Decimal,
+    pub price: Decimal,
+}
+
+/// Reads the holdings from the first sheet of an Investor 360 XLS or XLSX export.
+pub fn import_investor_360(file_path: &Path) -> anyhow::Result<Vec<Investor360>> {
+    let mut workbook = open_workbook_auto(file_path)
+        .with_context(|| format!("Couldn't open \"{}\"", file_path.display()))?;
+    let range = workbook
+        .worksheet_range_at(0)
+        .context("The spreadsheet doesn't have any sheets.")??;
+
+    let (row_start, column_start) = range.start().unwrap_or_default();
+    let mut rows = range.rows();
+    let header = rows.next().context("The spreadsheet is empty.")?;
+    let columns = Columns::new(header, column_start)?;
+
+    let mut records = Vec::new();
+    // Spreadsheets count rows from one and the header takes the first row.
+    for (row, cells) in (row_start as usize + 2..).zip(rows) {
+        let symbol = cell_string(cells, columns.symbol);
+
+        // Skip some junk records.
+        if symbol.is_empty() {
+            continue;
+        }
+
+        records.push(Investor360 {
+            description: cell_string(cells, columns.description),
+            quantity: columns.decimal(cells, columns.quantity, row, QUANTITY)?,
+            price: columns.decimal(cells, columns.price, row, PRICE)?,
+            symbol,
+        });
+    }
+
+    Ok(records)
+}
+
+struct Columns {
+    column_start: u32,
+    description: usize,
+    symbol: usize,
+    quantity: usize,
+    price: usize,
+}
+
+impl Columns {
+    fn new(header: &[Data], column_start: u32) -> anyhow::Result<Self> {
+        let find = |name: &str| {
+            header
+                .iter()
+                .position(|cell| cell.to_string().trim() == name)
+                .with_context(|| format!("The spreadsheet doesn't have a \"{name}\" column."))
+        };
+
+        Ok(Self {
+            column_start,
+            description: find(DESCRIPTION)?,
+            symbol: find(SYMBOL)?,
+            quantity: find(QUANTITY)?,
+            price: find(PRICE)?,
+        })
+    }
+
+    fn decimal(
+        &self,
+        cells: &[Data],
+        index: usize,
+        row: usize,
+        name: &'static str,
+    ) -> Result<Decimal, CellError> {
+        let cell = cells.get(index).unwrap_or(&Data::Empty);
+        let error = |value: String| CellError {
+            row,
+            column: column_name(self.column_start as usize + index),
+            name,
+            value,
+        };
+
+        match cell {
+            Data::Int(int) => Ok(Decimal::from(*int)),
+            Data::Float(float) => Decimal::try_from(*float)
+                .map(|decimal| decimal.normalize())
+                .map_err(|_| error(float.to_string())),
+            Data::String(string) => {
+                let number = string.trim().replace([',', '$'], "");
+                Decimal::from_str(&number).map_err(|_| error(string.clone()))
+            }
+            Data::Empty => Err(error(String::new())),
+            cell => Err(error(cell.to_string())),
+        }
+    }
+}
+
+fn cell_string(cells: &[Data], index: usize) -> String {
+    cells
+        .get(index)
+        .map_or_else(String::new, |cell| cell.to_string().trim().to_string())
+}
+
+/// Converts a zero based column index to its spreadsheet name, e.g. 27 -> "AB".
+fn column_name(mut index: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        #[allow(clippy::cast_possible_truncation)]
+        name.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap()
+}
+
+#[derive(Debug)]
+pub struct CellError {
+    row: usize,
+    column: String,
+    name: &'static str,
+    value: String,
+}
+
+impl fmt::Display for CellError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.value.is_empty() {
+            write!(
+                f,
+                "Investor 360: row {}, column {} ({}) is empty.",
+                self.row, self.column, self.name
+            )
+        } else {
+            write!(
+                f,
+                "Investor 360: row {}, column {} ({}): can't parse \"{}\" as a number.",
+                self.row, self.column, self.name, self.value
+            )
+        }
+    }
+}
+
+impl std::error::Error for CellError {}
@@ -0,0 +1,1160 @@
+use std::{collections::HashMap, ffi::OsStr, fs, path::Path};
+
+use anyhow::Context as _;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use rust_decimal::Decimal;
+
+use super::{
+    account::{Account, transaction::Transaction},
+    accounts::{Accounts, Group},
+    crypto::Crypto,
+    history::Command,
+    metal::Metal,
+    money::{Currency, Fiat},
+    stocks::StockPlus,
+};
+
+/// Commodities that are taken for crypto rather than stocks.
+const CRYPTO: [&str; 20] = [
+    "ADA", "ALGO", "ATOM", "AVAX", "BCH", "BTC", "DOGE", "DOT", "ETH", "LINK", "LTC", "MATIC",
+    "SOL", "TRX", "UNI", "USDC", "USDT", "XBT", "XLM", "XRP",
+];
+
+const METALS: [(&str, &str); 4] = [
+    ("XAU", "Gold"),
+    ("XAG", "Silver"),
+    ("XPD", "Palladium"),
+    ("XPT", "Platinum"),
+];
+
+/// The roots of the accounts that are imported. The others, e.g. `Expenses`,
+/// are where the money went, which the ledger doesn't keep.
+const ROOTS: [&str; 2] = ["assets", "liabilities"];
+
+/// Reads a ledger-cli or hledger journal, or a Beancount one if it ends in
+/// `.beancount` or `.bean`, into new accounts: one for each asset and liability
+/// account and commodity in it. Commodities that aren't fiat are held as
+/// metals, crypto or stocks, valued at the prices and costs in the journal.
+/// Returns the changes made and the accounts added.
+pub(crate) fn import_journal(
+    accounts: &mut Accounts,
+    path: &Path,
+) -> anyhow::Result<(Vec<Command>, Vec<String>)> {
+    let syntax = match path.extension().and_then(OsStr::to_str) {
+        Some("beancount" | "bean") => Syntax::Beancount,
+        _ => Syntax::Ledger,
+    };
+    let text =
+        fs::read_to_string(path).with_context(|| format!("{} can't be read.", path.display()))?;
+    let journal = parse(&text, syntax)?;
+    let imports = build(accounts, &journal)?;
+    Ok(commit(accounts, imports))
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Syntax {
+    Ledger,
+    Beancount,
+}
+
+#[derive(Clone, Debug)]
+struct Amount {
+    quantity: Decimal,
+    commodity: String,
+}
+
+#[derive(Clone, Debug)]
+struct Posting {
+    line: usize,
+    account: String,
+    amount: Option<Amount>,
+    /// Of one unit, from `@`, `@@` or a cost in braces.
+    price: Option<Amount>,
+    /// The balance after the posting, which is all there is when the amount
+    /// is left out to be worked out from it.
+    assertion: Option<Amount>,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    line: usize,
+    date: DateTime<Utc>,
+    description: String,
+    postings: Vec<Posting>,
+}
+
+/// A Beancount `balance`, which holds at the start of its day, or `pad`, which
+/// makes the next `balance` of the account hold.
+#[derive(Clone, Debug)]
+enum Check {
+    Balance(Amount),
+    Pad,
+}
+
+#[derive(Clone, Debug)]
+struct Directive {
+    line: usize,
+    date: DateTime<Utc>,
+    account: String,
+    check: Check,
+}
+
+#[derive(Debug, Default)]
+struct Journal {
+    entries: Vec<Entry>,
+    /// The price of one unit of a commodity.
+    prices: Vec<(DateTime<Utc>, String, Amount)>,
+    checks: Vec<Directive>,
+    /// What commodities are, from their `note` or `name`.
+    descriptions: HashMap<String, String>,
+}
+
+/// What the indented lines that follow belong to.
+enum Context {
+    None,
+    Entry,
+    Commodity(String),
+    /// Skipped until this line, e.g. `end comment`.
+    Block(&'static str),
+}
+
+fn parse(text: &str, syntax: Syntax) -> anyhow::Result<Journal> {
+    let mut journal = Journal::default();
+    let mut context = Context::None;
+    for (i, line) in text.lines().enumerate() {
+        parse_line(&mut journal, &mut context, i + 1, line, syntax)
+            .with_context(|| format!("line {}: \"{}\"", i + 1, line.trim()))?;
+    }
+    for entry in &mut journal.entries {
+        infer(entry)?;
+    }
+    Ok(journal)
+}
+
+fn parse_line(
+    journal: &mut Journal,
+    context: &mut Context,
+    number: usize,
+    line: &str,
+    syntax: Syntax,
+) -> anyhow::Result<()> {
+    if let Context::Block(end) = context {
+        if line.trim() == *end {
+            *context = Context::None;
+        }
+        return Ok(());
+    }
+    if line.trim().is_empty() {
+        *context = Context::None;
+        return Ok(());
+    }
+    let text = strip_comment(line).trim_end();
+    if text.trim().is_empty() {
+        return Ok(());
+    }
+
+    if text.starts_with(char::is_whitespace) {
+        let text = text.trim();
+        match context {
+            Context::Entry if !is_metadata(text, syntax) => {
+                let mut posting = parse_posting(text, syntax)?;
+                posting.line = number;
+                if let Some(entry) = journal.entries.last_mut() {
+                    entry.postings.push(posting);
+                }
+            }
+            Context::Commodity(symbol) => {
+                let description = match text.strip_prefix("name:") {
+                    Some(name) => strings(name).into_iter().next(),
+                    None => text
+                        .strip_prefix("note ")
+                        .map(|note| note.trim().to_string()),
+                };
+                if let Some(description) = description {
+                    journal.descriptions.insert(symbol.clone(), description);
+                }
+            }
+            _ => {}
+        }
+        return Ok(());
+    }
+
+    *context = Context::None;
+    let (first, rest) = split_word(text);
+    match first {
+        _ if first.starts_with(|c: char| c.is_ascii_digit()) => {
+            parse_dated(journal, context, number, parse_date(first)?, rest, syntax)?;
+        }
+        "P" => {
+            let (date, rest) = split_word(rest);
+            let date = parse_date(date)?;
+            // A time of day may follow the date.
+            let rest = match split_word(rest) {
+                (time, after) if time.contains(':') => after,
+                _ => rest,
+            };
+            let (commodity, price) = split_commodity(rest)?;
+            journal
+                .prices
+                .push((date, symbol(commodity)?, parse_amount(price)?));
+        }
+        "commodity" => *context = Context::Commodity(commodity_symbol(rest)?),
+        "include" => {
+            return Err(anyhow::Error::msg(
+                "Included files aren't imported, import each of them on its own.",
+            ));
+        }
+        "comment" => *context = Context::Block("end comment"),
+        "test" => *context = Context::Block("end test"),
+        // Other directives, e.g. `account`, `option` or periodic transactions.
+        _ => {}
+    }
+    Ok(())
+}
+
+fn parse_dated(
+    journal: &mut Journal,
+    context: &mut Context,
+    number: usize,
+    date: DateTime<Utc>,
+    rest: &str,
+    syntax: Syntax,
+) -> anyhow::Result<()> {
+    let (keyword, after) = split_word(rest);
+    if syntax == Syntax::Beancount {
+        match keyword {
+            "commodity" => {
+                *context = Context::Commodity(commodity_symbol(after)?);
+                return Ok(());
+            }
+            "price" => {
+                let (commodity, price) = split_commodity(after)?;
+                journal
+                    .prices
+                    .push((date, symbol(commodity)?, parse_amount(price)?));
+                return Ok(());
+            }
+            "balance" | "pad" => {
+                let (account, amount) = split_word(after);
+                let check = if keyword == "pad" {
+                    Check::Pad
+                } else {
+                    Check::Balance(parse_amount(amount)?)
+                };
+                journal.checks.push(Directive {
+                    line: number,
+                    date,
+                    account: account.to_string(),
+                    check,
+                });
+                return Ok(());
+            }
+            "open" | "close" | "note" | "event" | "document" | "custom" | "query" => {
+                return Ok(());
+            }
+            _ => {}
+        }
+    }
+
+    journal.entries.push(Entry {
+        line: number,
+        date,
+        description: description(rest, syntax),
+        postings: Vec::new(),
+    });
+    *context = Context::Entry;
+    Ok(())
+}
+
+/// Works out the amount a posting of the entry leaves out, which balances
+/// the others.
+fn infer(entry: &mut Entry) -> anyhow::Result<()> {
+    let mut sums: Vec<(String, Decimal)> = Vec::new();
+    for posting in &entry.postings {
+        let Some(amount) = &posting.amount else {
+            continue;
+        };
+        let (commodity, weight) = match &posting.price {
+            Some(price) => (&price.commodity, amount.quantity * price.quantity),
+            None => (&amount.commodity, amount.quantity),
+        };
+        match sums.iter_mut().find(|(sum, _)| sum == commodity) {
+            Some((_, sum)) => *sum += weight,
+            None => sums.push((commodity.clone(), weight)),
+        }
+    }
+    sums.retain(|(_, sum)| !sum.is_zero());
+
+    let mut missing = entry
+        .postings
+        .iter_mut()
+        .filter(|posting| posting.amount.is_none() && posting.assertion.is_none());
+    let Some(posting) = missing.next() else {
+        return Ok(());
+    };
+    if missing.next().is_some() {
+        return Err(anyhow::Error::msg(format!(
+            "line {}: more than one posting leaves out its amount.",
+            entry.line
+        )));
+    }
+    match sums.as_slice() {
+        [] => {}
+        [(commodity, sum)] => {
+            posting.amount = Some(Amount {
+                quantity: -sum,
+                commodity: commodity.clone(),
+            });
+        }
+        _ => {
+            return Err(anyhow::Error::msg(format!(
+                "line {}: the amount left out can't be worked out, the other postings are in more than one commodity.",
+                entry.line
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn parse_posting(text: &str, syntax: Syntax) -> anyhow::Result<Posting> {
+    let text = text.strip_prefix(['*', '!']).map_or(text, str::trim_start);
+    let (account, rest) = match syntax {
+        // Account names can have single spaces in them.
+        Syntax::Ledger => text
+            .find("  ")
+            .into_iter()
+            .chain(text.find('\t'))
+            .min()
+            .map_or((text, ""), |end| text.split_at(end)),
+        Syntax::Beancount => split_word(text),
+    };
+    let account = account
+        .trim()
+        .trim_start_matches(['(', '['])
+        .trim_end_matches([')', ']']);
+
+    let (rest, assertion) = match rest.split_once('=') {
+        Some((rest, assertion)) => {
+            let assertion = assertion.trim_start_matches(['=', '*']);
+            (rest, Some(parse_amount(assertion)?))
+        }
+        None => (rest, None),
+    };
+    // Whether the price is of all the units rather than one.
+    let (rest, price) = match rest.split_once('@') {
+        Some((rest, price)) => match price.strip_prefix('@') {
+            Some(total) => (rest, Some((parse_amount(total)?, true))),
+            None => (rest, Some((parse_amount(price)?, false))),
+        },
+        None => (rest, None),
+    };
+    let (rest, cost) = match rest.split_once('{') {
+        Some((rest, cost)) => {
+            let total = cost.starts_with('{');
+            let cost = cost.trim_matches(['{', '}', ' ']);
+            let cost = cost.split(',').next().unwrap_or_default().trim();
+            if cost.is_empty() {
+                (rest, None)
+            } else {
+                (rest, Some((parse_amount(cost)?, total)))
+            }
+        }
+        None => (rest, None),
+    };
+
+    let amount = if rest.trim().is_empty() {
+        None
+    } else {
+        Some(parse_amount(rest)?)
+    };
+    let price = match (price.or(cost), &amount) {
+        (Some((mut price, true)), Some(amount)) if !amount.quantity.is_zero() => {
+            price.quantity /= amount.quantity.abs();
+            Some(price)
+        }
+        (Some((price, false)), _) => Some(price),
+        _ => None,
+    };
+
+    Ok(Posting {
+        line: 0,
+        account: account.to_string(),
+        amount,
+        price,
+        assertion,
+    })
+}
+
+/// e.g. `-$1,000.50`, `$-12`, `12.50 USD` or `10 "BRK.B"`.
+fn parse_amount(text: &str) -> anyhow::Result<Amount> {
+    let text = text.trim();
+    let (mut negative, rest) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest.trim_start()),
+        None => (false, text.strip_prefix('+').unwrap_or(text).trim_start()),
+    };
+    let (commodity, number) = if rest.starts_with(|c: char| c.is_ascii_digit() || c == '.') {
+        let end = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ','))
+            .unwrap_or(rest.len());
+        (rest[end..].trim(), &rest[..end])
+    } else {
+        let (commodity, number) = split_commodity(rest)?;
+        (commodity, number.trim())
+    };
+    let number = match number.strip_prefix('-') {
+        Some(number) => {
+            negative = !negative;
+            number
+        }
+        None => number,
+    };
+
+    let mut quantity: Decimal = number
+        .replace(',', "")
+        .parse()
+        .with_context(|| format!("\"{text}\" isn't an amount."))?;
+    if negative {
+        quantity = -quantity;
+    }
+    Ok(Amount {
+        quantity,
+        commodity: symbol(commodity)?,
+    })
+}
+
+/// Splits a commodity, quoted or not, from the text after it.
+fn split_commodity(text: &str) -> anyhow::Result<(&str, &str)> {
+    let text = text.trim_start();
+    if let Some(quoted) = text.strip_prefix('"') {
+        let end = quoted
+            .find('"')
+            .context("A quote around a commodity isn't closed.")?;
+        return Ok((&quoted[..end], &quoted[end + 1..]));
+    }
+    let end = text
+        .find(|c: char| c.is_ascii_digit() || c.is_whitespace() || matches!(c, '-' | '+' | '.'))
+        .unwrap_or(text.len());
+    Ok(text.split_at(end))
+}
+
+/// The symbol of a commodity, e.g. `USD` for `$`.
+fn symbol(commodity: &str) -> anyhow::Result<String> {
+    match commodity.trim().trim_matches('"') {
+        "" => Err(anyhow::Error::msg("An amount has no commodity.")),
+        "$" => Ok("USD".to_string()),
+        "€" => Ok("EUR".to_string()),
+        "£" => Ok("GBP".to_string()),
+        "¥" => Ok("JPY".to_string()),
+        commodity => Ok(commodity.to_string()),
+    }
+}
+
+/// The symbol of a `commodity` directive, which in ledger-cli may be an
+/// example amount such as `$1,000.00`.
+fn commodity_symbol(text: &str) -> anyhow::Result<String> {
+    let text = text.trim();
+    parse_amount(text)
+        .map(|amount| amount.commodity)
+        .or_else(|_| symbol(text))
+}
+
+fn parse_date(text: &str) -> anyhow::Result<DateTime<Utc>> {
+    // A ledger-cli date may be followed by an auxiliary date after `=`.
+    let text = text.split('=').next().unwrap_or_default();
+    let date = NaiveDate::parse_from_str(&text.replace(['/', '.'], "-"), "%Y-%m-%d")
+        .with_context(|| format!("\"{text}\" isn't a date with a year, e.g. 2025-01-31."))?;
+    Ok(date.and_time(NaiveTime::MIN).and_utc())
+}
+
+/// The description of a transaction, without its status, code, tags and links.
+fn description(header: &str, syntax: Syntax) -> String {
+    let header = header.trim_start();
+    let header = header
+        .strip_prefix(['*', '!'])
+        .or_else(|| header.strip_prefix("txn"))
+        .unwrap_or(header)
+        .trim_start();
+    match syntax {
+        Syntax::Ledger => {
+            let header = match header.strip_prefix('(') {
+                Some(rest) => rest.split_once(')').map_or(rest, |(_, rest)| rest),
+                None => header,
+            };
+            header.trim().to_string()
+        }
+        // The payee and narration, which are the quoted strings.
+        Syntax::Beancount => strings(header)
+            .into_iter()
+            .filter(|string| !string.is_empty())
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+/// The quoted strings in the text, with `\` escapes taken out.
+fn strings(text: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut chars = text.chars();
+    while chars.any(|c| c == '"') {
+        let mut string = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => string.extend(chars.next()),
+                '"' => break,
+                c => string.push(c),
+            }
+        }
+        strings.push(string);
+    }
+    strings
+}
+
+/// The line without a comment, unless the `;` is in quotes.
+fn strip_comment(line: &str) -> &str {
+    if line.starts_with(['#', '%', '|', '*']) {
+        return "";
+    }
+    let mut quoted = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ';' if !quoted => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+/// A Beancount `key: value` line under a transaction.
+fn is_metadata(text: &str, syntax: Syntax) -> bool {
+    syntax == Syntax::Beancount
+        && text.starts_with(|c: char| c.is_ascii_lowercase())
+        && text.split_once(':').is_some_and(|(key, _)| {
+            key.chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+}
+
+fn split_word(text: &str) -> (&str, &str) {
+    let text = text.trim_start();
+    text.split_once(char::is_whitespace)
+        .map_or((text, ""), |(word, rest)| (word, rest.trim_start()))
+}
+
+/// An account to add, for one account and commodity of the journal.
+struct Import {
+    currency: Currency,
+    /// The second part of the journal account, e.g. `Bank` in
+    /// `Assets:Bank:Checking`.
+    group: Option<String>,
+    account: Account,
+}
+
+/// Works out the accounts to add without changing the ledger, so nothing is
+/// left half imported when the journal doesn't check out.
+fn build(accounts: &Accounts, journal: &Journal) -> anyhow::Result<Vec<Import>> {
+    let mut keys: Vec<(String, String)> = Vec::new();
+    let mut postings: HashMap<(String, String), Vec<(&Entry, &Posting)>> = HashMap::new();
+    for entry in &journal.entries {
+        for posting in &entry.postings {
+            let Some(amount) = posting.amount.as_ref().or(posting.assertion.as_ref()) else {
+                continue;
+            };
+            if !imported(&posting.account) {
+                continue;
+            }
+            let key = (posting.account.clone(), amount.commodity.clone());
+            if !keys.contains(&key) {
+                keys.push(key.clone());
+            }
+            postings.entry(key).or_default().push((entry, posting));
+        }
+    }
+    for directive in &journal.checks {
+        if let Check::Balance(amount) = &directive.check
+            && imported(&directive.account)
+        {
+            let key = (directive.account.clone(), amount.commodity.clone());
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+    }
+
+    let mut imports = Vec::new();
+    for key in &keys {
+        let (path, commodity) = key;
+        let name = name(&keys, path, commodity);
+        if accounts.inner.iter().any(|account| account.name == name) {
+            return Err(anyhow::Error::msg(format!(
+                "There already is an account named \"{name}\"."
+            )));
+        }
+
+        let currency = currency(accounts, journal, commodity);
+        let mut account = Account::new(name, currency.clone());
+        let postings = postings.get(key).map_or(&[][..], Vec::as_slice);
+        fill(&mut account, postings, key)?;
+        let secondary = account.txs_2nd.is_some();
+        check(&mut account, secondary, journal, key)?;
+        if secondary {
+            value(&mut account, journal, postings, commodity);
+        }
+
+        let parts: Vec<&str> = path.split(':').collect();
+        imports.push(Import {
+            currency,
+            group: (parts.len() > 2).then(|| parts[1].to_string()),
+            account,
+        });
+    }
+    Ok(imports)
+}
+
+/// Adds the postings to the account, the quantities if it holds a secondary
+/// currency, checking the balances they assert.
+fn fill(
+    account: &mut Account,
+    postings: &[(&Entry, &Posting)],
+    (path, commodity): &(String, String),
+) -> anyhow::Result<()> {
+    let mut txs = Vec::new();
+    for (entry, posting) in postings {
+        let assertion = posting
+            .assertion
+            .as_ref()
+            .filter(|assertion| assertion.commodity == *commodity)
+            .map(|assertion| assertion.quantity);
+        let tx = Transaction {
+            amount: posting
+                .amount
+                .as_ref()
+                .map_or(Decimal::ZERO, |amount| amount.quantity),
+            balance: assertion.unwrap_or_default(),
+            comment: entry.description.clone(),
+            date: entry.date,
+            snapshot: posting.amount.is_none(),
+        };
+        let assertion = assertion.filter(|_| posting.amount.is_some());
+        txs.push((tx, assertion.map(|assertion| (posting.line, assertion))));
+    }
+    // Stable, so the postings of a day stay in the order they were made in.
+    txs.sort_by_key(|(tx, _)| tx.date);
+    let (txs, assertions): (Vec<_>, Vec<_>) = txs.into_iter().unzip();
+
+    let secondary = if let Some(txs_2nd) = &mut account.txs_2nd {
+        txs_2nd.txs = txs;
+        txs_2nd.recompute(0);
+        true
+    } else {
+        account.txs_1st.txs = txs;
+        account.txs_1st.recompute(0);
+        false
+    };
+    for (tx, assertion) in account.txs(secondary).iter().zip(assertions) {
+        if let Some((line, expected)) = assertion
+            && tx.balance != expected
+        {
+            return Err(mismatch(line, path, tx.balance, expected, commodity));
+        }
+    }
+    Ok(())
+}
+
+/// Checks the `balance` directives of the account, making up the difference
+/// on the date of a `pad` before one.
+fn check(
+    account: &mut Account,
+    secondary: bool,
+    journal: &Journal,
+    (path, commodity): &(String, String),
+) -> anyhow::Result<()> {
+    let mut directives: Vec<&Directive> = journal
+        .checks
+        .iter()
+        .filter(|directive| {
+            directive.account == *path
+                && match &directive.check {
+                    Check::Balance(amount) => amount.commodity == *commodity,
+                    Check::Pad => true,
+                }
+        })
+        .collect();
+    directives.sort_by_key(|directive| directive.date);
+
+    let mut pad = None;
+    for directive in directives {
+        let Check::Balance(expected) = &directive.check else {
+            pad = Some(directive.date);
+            continue;
+        };
+        let balance = account
+            .txs(secondary)
+            .iter()
+            .take_while(|tx| tx.date < directive.date)
+            .last()
+            .map_or(Decimal::ZERO, |tx| tx.balance);
+        if balance != expected.quantity {
+            let Some(date) = pad else {
+                return Err(mismatch(
+                    directive.line,
+                    path,
+                    balance,
+                    expected.quantity,
+                    commodity,
+                ));
+            };
+            let tx = Transaction {
+                amount: expected.quantity - balance,
+                balance: Decimal::ZERO,
+                comment: "pad".to_string(),
+                date,
+                snapshot: false,
+            };
+            account.insert_tx(secondary, tx);
+        }
+        pad = None;
+    }
+    Ok(())
+}
+
+/// Values the holding at the prices and costs in the journal that are in the
+/// currency the account is valued in, from the quantity held then.
+fn value(
+    account: &mut Account,
+    journal: &Journal,
+    postings: &[(&Entry, &Posting)],
+    commodity: &str,
+) {
+    let currency = account.txs_1st.currency.symbol();
+    let mut prices: Vec<(DateTime<Utc>, Decimal, String)> = postings
+        .iter()
+        .filter_map(|(entry, posting)| {
+            let price = posting.price.as_ref()?;
+            Some((entry.date, price, entry.description.clone()))
+        })
+        .chain(
+            journal
+                .prices
+                .iter()
+                .filter(|(_, priced, _)| priced == commodity)
+                .map(|(date, _, price)| (*date, price, String::new())),
+        )
+        .filter(|(_, price, _)| price.commodity == currency)
+        .map(|(date, price, comment)| (date, price.quantity, comment))
+        .collect();
+    // One value a day: a cost and a price of the same day, e.g. from an
+    // export, are the same valuation, and the cost has a description.
+    prices.sort_by_key(|(date, _, _)| *date);
+    prices.dedup_by_key(|(date, _, _)| *date);
+
+    let Some(txs_2nd) = &account.txs_2nd else {
+        return;
+    };
+    let mut txs = Vec::new();
+    for (date, price, comment) in prices {
+        if let Some(held) = txs_2nd.txs.iter().take_while(|tx| tx.date <= date).last() {
+            txs.push(Transaction {
+                amount: Decimal::ZERO,
+                balance: held.balance * price,
+                comment,
+                date,
+                snapshot: true,
+            });
+        }
+    }
+    account.txs_1st.txs = txs;
+    account.txs_1st.recompute(0);
+}
+
+fn mismatch(
+    line: usize,
+    path: &str,
+    balance: Decimal,
+    expected: Decimal,
+    commodity: &str,
+) -> anyhow::Error {
+    anyhow::Error::msg(format!(
+        "line {line}: {path} is {balance} {commodity}, not {expected} {commodity} as asserted."
+    ))
+}
+
+fn imported(path: &str) -> bool {
+    path.split(':').next().is_some_and(|root| {
+        ROOTS
+            .iter()
+            .any(|imported| root.eq_ignore_ascii_case(imported))
+    })
+}
+
+/// The last part of the journal account, or all of it after the root when
+/// another one ends the same way, followed by the commodity when the account
+/// has more than one.
+fn name(keys: &[(String, String)], path: &str, commodity: &str) -> String {
+    let last = path.rsplit(':').next().unwrap_or(path);
+    let mut name = if keys
+        .iter()
+        .any(|(other, _)| other != path && other.rsplit(':').next() == Some(last))
+    {
+        path.split_once(':')
+            .map_or(path, |(_, rest)| rest)
+            .to_string()
+    } else {
+        last.to_string()
+    };
+    if keys
+        .iter()
+        .any(|(other, held)| other == path && held != commodity)
+    {
+        name.push(' ');
+        name.push_str(commodity);
+    }
+    name
+}
+
+/// The fiat of the commodity, or the metal, crypto or stock, as the ledger
+/// has it already if it does.
+fn currency(accounts: &Accounts, journal: &Journal, commodity: &str) -> Currency {
+    if let Some(fiat) = fiat(commodity) {
+        return Currency::Fiat(fiat);
+    }
+    if let Some(metal) = accounts
+        .metals
+        .iter()
+        .find(|metal| metal.symbol == commodity)
+    {
+        return Currency::Metal(metal.clone());
+    }
+    if let Some(crypto) = accounts
+        .crypto
+        .iter()
+        .find(|crypto| crypto.symbol == commodity)
+    {
+        return Currency::Crypto(crypto.clone());
+    }
+    if let Some(stock_plus) = accounts
+        .stocks_plus
+        .iter()
+        .find(|stock_plus| stock_plus.symbol == commodity)
+    {
+        return Currency::StockPlus(stock_plus.clone());
+    }
+
+    // Valued in the fiat it is first priced in.
+    let quote = journal
+        .prices
+        .iter()
+        .filter(|(_, priced, _)| priced == commodity)
+        .map(|(_, _, price)| price)
+        .chain(journal.entries.iter().flat_map(|entry| {
+            entry.postings.iter().filter_map(|posting| {
+                posting
+                    .amount
+                    .as_ref()
+                    .filter(|amount| amount.commodity == commodity)?;
+                posting.price.as_ref()
+            })
+        }))
+        .find_map(|price| fiat(&price.commodity))
+        .unwrap_or(Fiat::Usd);
+    let description = journal.descriptions.get(commodity).cloned();
+
+    if let Some((symbol, name)) = METALS.iter().find(|(symbol, _)| *symbol == commodity) {
+        Currency::Metal(Metal {
+            currency: quote,
+            description: description.unwrap_or_else(|| (*name).to_string()),
+            symbol: (*symbol).to_string(),
+        })
+    } else if CRYPTO.contains(&commodity) {
+        Currency::Crypto(Crypto {
+            currency: quote,
+            description: description.unwrap_or_else(|| commodity.to_string()),
+            symbol: commodity.to_string(),
+        })
+    } else {
+        Currency::StockPlus(StockPlus {
+            description: description.unwrap_or_else(|| commodity.to_string()),
+            symbol: commodity.to_string(),
+        })
+    }
+}
+
+fn fiat(symbol: &str) -> Option<Fiat> {
+    Fiat::all()
+        .into_iter()
+        .find(|fiat| fiat.symbol().eq_ignore_ascii_case(symbol))
+}
+
+/// Adds the accounts, and the currencies and groups they need, and returns
+/// the changes and the names of the accounts.
+fn commit(accounts: &mut Accounts, imports: Vec<Import>) -> (Vec<Command>, Vec<String>) {
+    let mut commands = Vec::new();
+    let mut names = Vec::new();
+    for import in imports {
+        match import.currency {
+            Currency::Crypto(crypto) if !accounts.crypto.contains(&crypto) => {
+                accounts.crypto.push(crypto.clone());
+                commands.push(Command::AddCrypto(crypto));
+            }
+            Currency::Metal(metal) if !accounts.metals.contains(&metal) => {
+                accounts.metals.push(metal.clone());
+                commands.push(Command::AddMetal(metal));
+            }
+            Currency::StockPlus(stock_plus) if !accounts.stocks_plus.contains(&stock_plus) => {
+                accounts.stocks_plus.push(stock_plus.clone());
+                commands.push(Command::AddStockPlus(stock_plus));
+            }
+            _ => {}
+        }
+        let fiat = import.account.txs_1st.currency.clone();
+        if !accounts.fiats.contains(&fiat) {
+            accounts.fiats.push(fiat.clone());
+            commands.push(Command::AddFiat(fiat));
+        }
+
+        let mut member_of = Vec::new();
+        if let Some(name) = import.group {
+            let index =
+                if let Some(index) = accounts.groups.iter().position(|group| group.name == name) {
+                    index
+                } else {
+                    let group = Group {
+                        name,
+                        members: Vec::new(),
+                        source: None,
+                    };
+                    accounts.groups.push(group.clone());
+                    commands.push(Command::AddGroup(group));
+                    accounts.groups.len() - 1
+                };
+            member_of.push(index);
+        }
+
+        names.push(import.account.name.clone());
+        commands.push(accounts.insert_account(import.account, &member_of));
+    }
+    (commands, names)
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::ledger::{export::Export, testing};
+
+    fn amount(text: &str) -> (Decimal, String) {
+        let amount = parse_amount(text).unwrap();
+        (amount.quantity, amount.commodity)
+    }
+
+    /// Imports the journal into a new ledger.
+    fn import(test: &str, file_name: &str, journal: &str) -> anyhow::Result<Accounts> {
+        let path = testing::directory(test).join(file_name);
+        fs::write(&path, journal).unwrap();
+        let mut accounts = Accounts::new();
+        import_journal(&mut accounts, &path)?;
+        Ok(accounts)
+    }
+
+    fn summary(txs: &[Transaction]) -> Vec<(DateTime<Utc>, Decimal, Decimal, String)> {
+        txs.iter()
+            .map(|tx| (tx.date, tx.amount, tx.balance, tx.comment.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn amounts_are_read_in_every_form() {
+        assert_eq!(amount("-$1,000.50"), (dec!(-1000.50), "USD".to_string()));
+        assert_eq!(amount("$-12"), (dec!(-12), "USD".to_string()));
+        assert_eq!(amount("- £3"), (dec!(-3), "GBP".to_string()));
+        assert_eq!(amount("12.50 USD"), (dec!(12.50), "USD".to_string()));
+        assert_eq!(amount("10 \"BRK.B\""), (dec!(10), "BRK.B".to_string()));
+        assert!(parse_amount("12.50").is_err());
+        assert!(parse_amount("USD twelve").is_err());
+    }
+
+    #[test]
+    fn postings_read_prices_costs_and_assertions() {
+        let posting =
+            parse_posting("Assets:Broker  10 VTI @@ $2,000 = 15 VTI", Syntax::Ledger).unwrap();
+        assert_eq!(posting.account, "Assets:Broker");
+        assert_eq!(posting.price.unwrap().quantity, dec!(200));
+        assert_eq!(posting.assertion.unwrap().quantity, dec!(15));
+
+        let posting = parse_posting(
+            "Assets:Broker 2 VTI {180 USD, 2024-01-01}",
+            Syntax::Beancount,
+        )
+        .unwrap();
+        assert_eq!(posting.amount.unwrap().quantity, dec!(2));
+        assert_eq!(posting.price.unwrap().quantity, dec!(180));
+    }
+
+    #[test]
+    fn descriptions_leave_out_status_and_code() {
+        assert_eq!(
+            description("* (1024) Coffee shop", Syntax::Ledger),
+            "Coffee shop"
+        );
+        assert_eq!(
+            description("txn \"Cafe\" \"Coffee\" #treat", Syntax::Beancount),
+            "Cafe Coffee"
+        );
+        assert_eq!(
+            strip_comment("  Assets:Cash  $5 ; note"),
+            "  Assets:Cash  $5 "
+        );
+        assert_eq!(strip_comment("2024-01-01 \"a;b\""), "2024-01-01 \"a;b\"");
+        assert_eq!(
+            description(r#"* "Said \"hi\"; left" "" "#, Syntax::Beancount),
+            "Said \"hi\"; left"
+        );
+        assert_eq!(
+            strip_comment(r#"2024-01-01 * "a\";b" ; c"#),
+            r#"2024-01-01 * "a\";b" "#
+        );
+    }
+
+    #[test]
+    fn amounts_left_out_balance_the_entry() {
+        let accounts = import(
+            "journal_infers_amounts",
+            "a.journal",
+            "2024-01-01 Pay\n    Income:Salary  -$1,000\n    Assets:Checking\n",
+        )
+        .unwrap();
+        assert_eq!(accounts.inner[0].name, "Checking");
+        assert_eq!(accounts.inner[0].balance_1st(), dec!(1000));
+    }
+
+    #[test]
+    fn failed_assertions_name_the_line() {
+        let error = import(
+            "journal_checks_assertions",
+            "a.journal",
+            "2024-01-01 Pay\n    Assets:Checking  $100 = $90\n    Income:Salary\n",
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 2: Assets:Checking is 100 USD, not 90 USD as asserted."
+        );
+    }
+
+    #[test]
+    fn names_tell_accounts_apart() {
+        let keys = [
+            ("Assets:A:Checking".to_string(), "USD".to_string()),
+            ("Assets:B:Checking".to_string(), "USD".to_string()),
+            ("Assets:Broker".to_string(), "USD".to_string()),
+            ("Assets:Broker".to_string(), "VTI".to_string()),
+        ];
+        assert_eq!(name(&keys, "Assets:A:Checking", "USD"), "A:Checking");
+        assert_eq!(name(&keys, "Assets:Broker", "VTI"), "Broker VTI");
+    }
+
+    #[test]
+    fn accounts_already_in_the_ledger_are_not_imported() {
+        let path = testing::directory("journal_keeps_names").join("a.journal");
+        fs::write(
+            &path,
+            "2024-01-01 Pay\n    Assets:Checking  $1\n    Income:Salary\n",
+        )
+        .unwrap();
+        let mut accounts = testing::accounts();
+        assert!(import_journal(&mut accounts, &path).is_err());
+        assert_eq!(accounts.inner.len(), 3);
+    }
+
+    #[test]
+    fn ledger_export_imports_back() {
+        let accounts = testing::accounts();
+        let text = Export::Ledger.render(&accounts);
+        let imported = import("journal_round_trip", "ledger.journal", &text).unwrap();
+
+        let names: Vec<_> = imported.inner.iter().map(|account| &account.name).collect();
+        assert_eq!(names, ["Checking", "Gold", "Savings"]);
+        assert_eq!(imported.groups.len(), 1);
+        assert_eq!(imported.groups[0].name, "Cash");
+        assert_eq!(imported.groups[0].members, [0, 2]);
+        for i in [0, 2] {
+            assert_eq!(
+                summary(imported.inner[i].txs(false)),
+                summary(accounts.inner[i].txs(false))
+            );
+        }
+
+        let (gold, imported_gold) = (&accounts.inner[1], &imported.inner[1]);
+        assert_eq!(summary(imported_gold.txs(true)), summary(gold.txs(true)));
+        // Each recorded value comes back, and the cost of a purchase values
+        // the holding on its day.
+        for tx in gold.txs(false) {
+            assert!(
+                imported_gold
+                    .txs(false)
+                    .iter()
+                    .any(|imported| imported.date == tx.date && imported.balance == tx.balance)
+            );
+        }
+        assert_eq!(imported_gold.txs(false).len(), 4);
+        assert_eq!(imported_gold.balance_1st(), gold.balance_1st());
+    }
+
+    #[test]
+    fn beancount_export_imports_back() {
+        let mut accounts = testing::accounts();
+        accounts.inner[2].txs_1st.txs[0].comment = r#"The "rainy day"; fund"#.to_string();
+        let text = Export::Beancount.render(&accounts);
+        let imported = import("beancount_round_trip", "ledger.beancount", &text).unwrap();
+
+        let names: Vec<_> = imported.inner.iter().map(|account| &account.name).collect();
+        assert_eq!(names, ["Checking", "Gold", "Savings"]);
+        assert_eq!(imported.groups[0].name, "Cash");
+        assert_eq!(imported.groups[0].members, [0, 2]);
+        assert_eq!(imported.metals, accounts.metals);
+        for i in [0, 2] {
+            assert_eq!(
+                summary(imported.inner[i].txs(false)),
+                summary(accounts.inner[i].txs(false))
+            );
+        }
+
+        let (gold, imported_gold) = (&accounts.inner[1], &imported.inner[1]);
+        assert_eq!(summary(imported_gold.txs(true)), summary(gold.txs(true)));
+        assert_eq!(imported_gold.txs(false).len(), 4);
+        assert_eq!(imported_gold.balance_1st(), gold.balance_1st());
+    }
+
+    #[test]
+    fn beancount_balances_are_checked_and_padded() {
+        let journal = "\
+2024-01-01 open Assets:Checking
+2024-01-01 pad Assets:Checking Equity:Opening-Balances
+2024-01-02 balance Assets:Checking 50 USD
+2024-01-05 * \"Pay\"
+  Assets:Checking  10 USD
+  Income:Salary
+2024-01-06 balance Assets:Checking 60 USD
+";
+        let accounts = import("beancount_pads", "a.beancount", journal).unwrap();
+        let txs = accounts.inner[0].txs(false);
+        assert_eq!(txs[0].comment, "pad");
+        assert_eq!(txs[0].amount, dec!(50));
+        assert_eq!(accounts.inner[0].balance_1st(), dec!(60));
+
+        let error = import(
+            "beancount_checks_balances",
+            "a.beancount",
+            &journal.replace("60 USD", "70 USD"),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 7: Assets:Checking is 60 USD, not 70 USD as asserted."
+        );
+    }
+}
@@ -0,0 +1,45 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    fs::{self, TryLockError},
+    path::{Path, PathBuf},
+};
+
+/// Locks the ledger so no other instance can open it for writing.
+pub fn try_lock(file: &fs::File, file_path: &Path) -> anyhow::Result<()> {
+    match file.try_lock() {
+        Ok(()) => Ok(()),
+        Err(TryLockError::WouldBlock) => Err(Locked(file_path.to_path_buf()).into()),
+        Err(TryLockError::Error(error)) => Err(error.into()),
+    }
+}
+
+/// Whether another instance holds the lock on the ledger.
+#[cfg(feature = "gui")]
+pub fn is_locked(file_path: &Path) -> anyhow::Result<bool> {
+    let file = fs::File::open(file_path)?;
+    match file.try_lock() {
+        Ok(()) => {
+            file.unlock()?;
+            Ok(false)
+        }
+        Err(TryLockError::WouldBlock) => Ok(true),
+        Err(TryLockError::Error(error)) => Err(error.into()),
+    }
+}
+
+/// Returned when the ledger is open in another instance.
+#[derive(Clone, Debug)]
+pub struct Locked(pub PathBuf);
+
+impl Display for Locked {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "{} is open in another financial-accounts.",
+            self.0.display()
+        )
+    }
+}
+
+impl Error for Locked {}
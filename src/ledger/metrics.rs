@@ -0,0 +1,210 @@
+use std::{collections::BTreeMap, fmt::Write};
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+use super::{account::Account, accounts::Accounts, report::Report};
+
+/// The balances of every account, currency and group and the last price got of
+/// every crypto, metal and stock, in the Prometheus text format.
+pub(crate) fn render(accounts: &Accounts) -> String {
+    let mut text = String::new();
+
+    gauge(
+        &mut text,
+        "financial_accounts_balance",
+        "The balance of the account in its currency.",
+        accounts.inner.iter().enumerate().map(|(i, account)| {
+            (
+                account_labels(accounts, i, account.txs_1st.currency.symbol()),
+                account.balance_1st(),
+            )
+        }),
+    );
+    gauge(
+        &mut text,
+        "financial_accounts_balance_2nd",
+        "How much of its secondary currency the account holds.",
+        accounts
+            .inner
+            .iter()
+            .enumerate()
+            .filter_map(|(i, account)| {
+                let txs_2nd = account.txs_2nd.as_ref()?;
+                Some((
+                    account_labels(accounts, i, txs_2nd.currency.symbol()),
+                    txs_2nd.balance(),
+                ))
+            }),
+    );
+
+    let report = Report::new(accounts);
+    gauge(
+        &mut text,
+        "financial_accounts_total",
+        "The balance of all the accounts in the currency.",
+        report
+            .totals
+            .iter()
+            .filter_map(|line| Some((vec![("currency", line.currency.clone()?)], line.balance))),
+    );
+    gauge(
+        &mut text,
+        "financial_accounts_group_balance",
+        "The balance of the accounts in the group, without a currency if they are in more than one.",
+        report.groups.iter().map(|line| {
+            let mut labels = vec![("group", line.name.clone())];
+            if let Some(currency) = &line.currency {
+                labels.push(("currency", currency.clone()));
+            }
+            (labels, line.balance)
+        }),
+    );
+
+    let prices = prices(accounts);
+    gauge(
+        &mut text,
+        "financial_accounts_price",
+        "The last price got of the crypto, metal or stock, in the currency.",
+        prices.iter().map(|((asset, currency), (_, price))| {
+            (
+                vec![("asset", asset.clone()), ("currency", currency.clone())],
+                *price,
+            )
+        }),
+    );
+    gauge(
+        &mut text,
+        "financial_accounts_price_timestamp_seconds",
+        "When the last price of the crypto, metal or stock was got.",
+        prices.iter().map(|((asset, currency), (date, _))| {
+            (
+                vec![("asset", asset.clone()), ("currency", currency.clone())],
+                Decimal::from(date.timestamp()),
+            )
+        }),
+    );
+
+    text
+}
+
+/// The name of the account, the currency and the first group the account is in,
+/// as in the account paths of an export, so summing by group counts each
+/// account once.
+fn account_labels(accounts: &Accounts, i: usize, currency: String) -> Vec<(&'static str, String)> {
+    let mut labels = vec![
+        ("account", accounts[i].name.clone()),
+        ("currency", currency),
+    ];
+    if let Some(group) = accounts
+        .groups
+        .iter()
+        .find(|group| group.members.contains(&i))
+    {
+        labels.push(("group", group.name.clone()));
+    }
+    labels
+}
+
+/// The newest price of each asset and the currency it is in, from the
+/// snapshots prices are added as.
+fn prices(accounts: &Accounts) -> BTreeMap<(String, String), (DateTime<Utc>, Decimal)> {
+    let mut prices = BTreeMap::new();
+    for account in &accounts.inner {
+        let Some((date, price)) = last_price(account) else {
+            continue;
+        };
+        let key = (
+            account
+                .txs_2nd
+                .as_ref()
+                .map(|txs| txs.currency.symbol())
+                .unwrap_or_default(),
+            account.txs_1st.currency.symbol(),
+        );
+        if prices.get(&key).is_none_or(|(newest, _)| *newest < date) {
+            prices.insert(key, (date, price));
+        }
+    }
+    prices
+}
+
+/// The balance of the last snapshot divided by the quantity held then.
+fn last_price(account: &Account) -> Option<(DateTime<Utc>, Decimal)> {
+    let txs_2nd = account.txs_2nd.as_ref()?;
+    let snapshot = account.txs_1st.txs.iter().rev().find(|tx| tx.snapshot)?;
+    let quantity = txs_2nd
+        .txs
+        .iter()
+        .take_while(|tx| tx.date <= snapshot.date)
+        .last()?
+        .balance;
+    if quantity.is_zero() {
+        return None;
+    }
+    let mut price = snapshot.balance / quantity;
+    price.rescale(2);
+    Some((snapshot.date, price))
+}
+
+fn gauge(
+    text: &mut String,
+    name: &str,
+    help: &str,
+    samples: impl Iterator<Item = (Vec<(&'static str, String)>, Decimal)>,
+) {
+    let _ = writeln!(text, "# HELP {name} {help}");
+    let _ = writeln!(text, "# TYPE {name} gauge");
+    for (labels, value) in samples {
+        let labels: Vec<String> = labels
+            .iter()
+            .map(|(label, value)| format!("{label}=\"{}\"", escape(value)))
+            .collect();
+        let _ = writeln!(text, "{name}{{{}}} {}", labels.join(","), value.normalize());
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ledger::testing;
+
+    #[test]
+    fn gauges_are_labelled_by_account_currency_and_group() {
+        let text = render(&testing::accounts());
+        assert!(text.starts_with(
+            "# HELP financial_accounts_balance The balance of the account in its currency.\n\
+             # TYPE financial_accounts_balance gauge\n\
+             financial_accounts_balance{account=\"Checking\",currency=\"USD\",group=\"Cash\"} 450\n\
+             financial_accounts_balance{account=\"Gold\",currency=\"USD\"} 3450\n\
+             financial_accounts_balance{account=\"Savings\",currency=\"USD\",group=\"Cash\"} 1000\n"
+        ));
+        for line in [
+            "# TYPE financial_accounts_balance_2nd gauge\n\
+             financial_accounts_balance_2nd{account=\"Gold\",currency=\"XAU\"} 1.5\n",
+            "\nfinancial_accounts_total{currency=\"USD\"} 4900\n",
+            "\nfinancial_accounts_group_balance{group=\"Cash\",currency=\"USD\"} 1450\n",
+            "\nfinancial_accounts_price{asset=\"XAU\",currency=\"USD\"} 2300\n",
+            "\nfinancial_accounts_price_timestamp_seconds{asset=\"XAU\",currency=\"USD\"} 1709251200\n",
+        ] {
+            assert!(text.contains(line), "{line} isn't in\n{text}");
+        }
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        let mut accounts = testing::accounts();
+        accounts.inner[0].name = "Joint \"A\\B\"\nold".to_string();
+        let text = render(&accounts);
+        assert!(text.contains(
+            "financial_accounts_balance{account=\"Joint \\\"A\\\\B\\\"\\nold\",currency=\"USD\",group=\"Cash\"} 450\n"
+        ));
+    }
+}
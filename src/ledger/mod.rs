@@ -0,0 +1,628 @@
+pub(crate) mod account;
+pub(crate) mod accounts;
+pub(crate) mod backups;
+mod crypto;
+pub(crate) mod duplicates;
+pub(crate) mod encryption;
+mod export;
+mod format;
+pub(crate) mod history;
+pub(crate) mod import;
+pub(crate) mod import_boa;
+pub(crate) mod import_investor_360;
+pub(crate) mod import_journal;
+pub(crate) mod lock;
+mod metal;
+mod metrics;
+pub(crate) mod money;
+mod query;
+pub(crate) mod report;
+pub(crate) mod script;
+pub(crate) mod sqlite;
+mod stocks;
+#[cfg(test)]
+pub(crate) mod testing;
+pub(crate) mod watch;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use anyhow::Context;
+
+pub use account::{
+    Account, ParseDateError, parse_date, transaction::Transaction, transactions::Transactions,
+};
+pub use accounts::{Accounts, Group, Include};
+pub use crypto::Crypto;
+pub use encryption::NeedsPassphrase;
+pub use export::Export;
+pub use lock::Locked;
+pub use metal::Metal;
+pub use money::{Currency, Fiat};
+pub use query::{Found, Query};
+pub use report::{Format, Kind, Line, Report};
+pub use script::{Event, Output};
+pub use stocks::StockPlus;
+pub use watch::Changed;
+
+use encryption::Encryption;
+use history::Command;
+use import::Import;
+
+/// A ledger that is open, with the file it is saved to. Unless it was opened
+/// read-only, the file stays locked until the ledger is closed or dropped, so
+/// the window and other programs can't change it meanwhile.
+///
+/// Changes are made in memory and written by `save`.
+#[derive(Debug)]
+pub struct Ledger {
+    accounts: Accounts,
+    file: File,
+}
+
+impl Ledger {
+    /// Loads and locks the ledger. A ledger from an older version is upgraded
+    /// and saved.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ledger can't be loaded, e.g. it is encrypted and the
+    /// passphrase is missing (`NeedsPassphrase`) or wrong, or it is open in
+    /// another instance (`Locked`).
+    pub fn open(path: PathBuf, passphrase: Option<&str>) -> anyhow::Result<Self> {
+        let (accounts, file) = Accounts::load(&mut None, path, passphrase)?;
+        Ok(Self { accounts, file })
+    }
+
+    /// Loads the ledger without locking it, so it can be read while it is open
+    /// elsewhere. It can't be saved.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ledger can't be loaded.
+    pub fn open_read_only(path: PathBuf, passphrase: Option<&str>) -> anyhow::Result<Self> {
+        let (accounts, file) = Accounts::load_read_only(&mut None, path, passphrase)?;
+        Ok(Self { accounts, file })
+    }
+
+    /// Creates an empty ledger, RON or SQLite as chosen by the extension.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the file already exists or can't be written.
+    pub fn create(path: PathBuf) -> anyhow::Result<Self> {
+        let accounts = Accounts::new();
+        let file = accounts.save_first(path)?;
+        Ok(Self { accounts, file })
+    }
+
+    #[must_use]
+    pub const fn accounts(&self) -> &Accounts {
+        &self.accounts
+    }
+
+    /// # Errors
+    ///
+    /// Fails if there is no account with the name.
+    pub fn account(&self, name: &str) -> anyhow::Result<&Account> {
+        Ok(&self.accounts[self.accounts.find(name)?])
+    }
+
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.file.path
+    }
+
+    #[must_use]
+    pub const fn is_read_only(&self) -> bool {
+        self.file.read_only
+    }
+
+    #[must_use]
+    pub fn report(&self) -> Report {
+        Report::new(&self.accounts)
+    }
+
+    /// Searches the transactions of every account, see `Query`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the query can't be parsed.
+    pub fn query(&self, query: &str) -> anyhow::Result<Vec<Found>> {
+        Ok(query.parse::<Query>()?.run(&self.accounts))
+    }
+
+    /// Searches the ledger in a file, see `Query`. A SQLite database isn't
+    /// loaded: only the transactions of the accounts searched, in the dates
+    /// searched, are read with the index on account and date. Other files are
+    /// loaded read-only first. Returns the names of the accounts the indexes in
+    /// `Found::accounts` are of.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the query can't be parsed or the ledger can't be read.
+    pub fn query_file(
+        path: &Path,
+        passphrase: Option<&str>,
+        query: &str,
+    ) -> anyhow::Result<(Vec<String>, Vec<Found>)> {
+        let query: Query = query.parse()?;
+        let names = |accounts: &Accounts| {
+            accounts
+                .inner
+                .iter()
+                .map(|account| account.name.clone())
+                .collect()
+        };
+
+        if sqlite::is_sqlite_file(path)? {
+            let store = sqlite::Store::open_read_only(path)?;
+            if let Some((accounts, found)) = store
+                .query(&query)
+                .with_context(|| format!("searching {}", path.display()))?
+            {
+                return Ok((names(&accounts), found));
+            }
+        }
+
+        let ledger = Self::open_read_only(path.to_path_buf(), passphrase)?;
+        let found = query.run(&ledger.accounts);
+        Ok((names(&ledger.accounts), found))
+    }
+
+    /// The balances of every account, currency and group and the last price
+    /// got of every crypto, metal and stock, in the Prometheus text format.
+    #[must_use]
+    pub fn metrics(&self) -> String {
+        metrics::render(&self.accounts)
+    }
+
+    /// The whole ledger as a plain-text accounting journal, see `Export`.
+    #[must_use]
+    pub fn export(&self, format: Export) -> String {
+        format.render(&self.accounts)
+    }
+
+    /// Whether another program changed the ledger, or a file it includes,
+    /// since it was loaded or saved.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a file can't be read.
+    pub fn changed_on_disk(&self) -> anyhow::Result<bool> {
+        self.file.changed()
+    }
+
+    /// Adds an account without transactions, in name order.
+    ///
+    /// # Errors
+    ///
+    /// Fails if there already is an account with the name.
+    pub fn add_account(&mut self, name: &str, currency: Currency) -> anyhow::Result<()> {
+        let name = name.trim().to_string();
+        self.accounts.check_account_name(&name)?;
+        let command = self
+            .accounts
+            .insert_account(Account::new(name, currency), &[]);
+        self.record(&[command])
+    }
+
+    /// Removes the account, and takes it out of its groups.
+    ///
+    /// # Errors
+    ///
+    /// Fails if there is no account with the name, or it is in a file that is
+    /// included read-only.
+    pub fn remove_account(&mut self, name: &str) -> anyhow::Result<()> {
+        let i = self.accounts.find(name)?;
+        if self.accounts.is_read_only(self.accounts[i].source) {
+            return Err(read_only());
+        }
+        let command = self.accounts.remove_account(i);
+        self.record(&[command])
+    }
+
+    /// Adds the transaction to the account in date order. For a transaction
+    /// with `snapshot` set the amount is worked out from the balance, otherwise
+    /// the balance from the amount.
+    ///
+    /// # Errors
+    ///
+    /// Fails if there is no account with the name, `secondary` is asked for and
+    /// the account doesn't hold a secondary currency, or the account is in a
+    /// file that is included read-only.
+    pub fn add_tx(
+        &mut self,
+        account: &str,
+        secondary: bool,
+        tx: Transaction,
+    ) -> anyhow::Result<()> {
+        let i = self.accounts.find(account)?;
+        if secondary && self.accounts[i].txs_2nd.is_none() {
+            return Err(no_secondary(account));
+        }
+        let command = self.accounts.insert_tx(i, secondary, tx);
+        self.record(&[command])
+    }
+
+    /// Imports a Bank of America CSV export into the account, leaving out the
+    /// rows that are probably already in it. Returns how many rows were
+    /// imported and how many were left out.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the CSV can't be read, there is no account with the name, the
+    /// account holds a secondary currency or it is included read-only.
+    pub fn import_boa(&mut self, account: &str, csv: PathBuf) -> anyhow::Result<(usize, usize)> {
+        let i = self.accounts.find(account)?;
+        if self.accounts[i].txs_2nd.is_some() {
+            return Err(anyhow::Error::msg(format!(
+                "\"{account}\" holds a secondary currency, Bank of America can't be imported into it."
+            )));
+        }
+
+        let import = Import::boa(&self.accounts, i, import_boa::import_boa(csv)?);
+        let skipped = import.rows.iter().filter(|row| !row.checked).count();
+        let commands = self.accounts.commit_boa(i, &import);
+        self.record(&commands)?;
+        Ok((import.rows.len() - skipped, skipped))
+    }
+
+    /// Imports the holdings of an Investor 360 XLS or XLSX export, adding an
+    /// account for each holding that isn't in the ledger yet and leaving out
+    /// the ones whose balance hasn't changed. Returns the accounts imported
+    /// into, one for each holding.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the export can't be read or the holdings can't be added.
+    pub fn import_investor_360(&mut self, xls: &Path) -> anyhow::Result<Vec<String>> {
+        let records = import_investor_360::import_investor_360(xls)?;
+        let rows = Import::investor_360(&self.accounts, records).checked();
+        let names = rows.iter().map(|row| row.account.clone()).collect();
+
+        let commands = self.accounts.commit_investor_360(rows)?;
+        self.record(&commands)?;
+        Ok(names)
+    }
+
+    /// Imports a ledger-cli or hledger journal, or a Beancount one if it ends
+    /// in `.beancount` or `.bean`, adding an account for each asset and
+    /// liability account and commodity in it. Returns the accounts added.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the journal can't be read, a balance it asserts doesn't hold,
+    /// or an account it would add is already in the ledger.
+    pub fn import_journal(&mut self, path: &Path) -> anyhow::Result<Vec<String>> {
+        let (commands, names) = import_journal::import_journal(&mut self.accounts, path)?;
+        self.record(&commands)?;
+        Ok(names)
+    }
+
+    /// Gets the price of every crypto, metal and stock held and adds it to the
+    /// account as a transaction. Returns why the prices that couldn't be got
+    /// weren't.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a price is for an account that is included read-only.
+    pub async fn get_prices(&mut self) -> anyhow::Result<Vec<anyhow::Error>> {
+        let results = self.accounts.get_all_prices().await;
+        let (commands, errors) = self.accounts.add_prices(results);
+        self.record(&commands)?;
+        Ok(errors)
+    }
+
+    /// Runs the scripts of the ledger for the event, see `Event`, and keeps
+    /// the changes they make. A script that fails makes no changes and doesn't
+    /// stop the others.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a script changed an account that is included read-only.
+    pub fn run_scripts(&mut self, event: &Event) -> anyhow::Result<Output> {
+        let scripts = self
+            .accounts
+            .scripts
+            .iter()
+            .map(|path| script::Script::read(&self.file.path, path))
+            .collect();
+        self.run(scripts, event)
+    }
+
+    /// Runs a script from the top, whether or not it is one of the ledger's,
+    /// and keeps the changes it makes.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the script can't be read or changed an account that is
+    /// included read-only.
+    pub fn run_script(&mut self, path: &Path) -> anyhow::Result<Output> {
+        let script = script::Script::read(&self.file.path, path)?;
+        self.run(vec![Ok(script)], &Event::Demand)
+    }
+
+    fn run(
+        &mut self,
+        scripts: Vec<anyhow::Result<script::Script>>,
+        event: &Event,
+    ) -> anyhow::Result<Output> {
+        let (command, output) = script::run(&mut self.accounts, scripts, event);
+        if let Some(command) = command {
+            self.record(&[command])?;
+        }
+        Ok(output)
+    }
+
+    /// Saves the accounts, each to the file it was loaded from.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ledger is open read-only, another program changed it since
+    /// it was loaded or saved (`Changed`), or it can't be written.
+    pub fn save(&mut self) -> anyhow::Result<()> {
+        self.accounts.save(&mut self.file)
+    }
+
+    /// Saves the ledger encrypted with the passphrase from now on, and
+    /// encrypts its backups.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ledger is SQLite or can't be saved.
+    pub fn encrypt(&mut self, passphrase: String) -> anyhow::Result<()> {
+        self.file.encrypt(passphrase)?;
+        self.save()
+    }
+
+    /// Saves the ledger as plain text again. The backups stay as they are.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ledger can't be saved.
+    pub fn decrypt(&mut self) -> anyhow::Result<()> {
+        self.file.encryption = None;
+        self.save()
+    }
+
+    /// Lets go of the locks on the ledger and the files it includes.
+    ///
+    /// # Errors
+    ///
+    /// Fails if a lock can't be let go.
+    pub fn close(self) -> anyhow::Result<()> {
+        self.file.unlock()
+    }
+
+    /// Keeps the changes, unless one is to a ledger that is included read-only,
+    /// in which case they are all undone.
+    fn record(&mut self, commands: &[Command]) -> anyhow::Result<()> {
+        if commands
+            .iter()
+            .any(|command| command.touches_read_only(&self.accounts))
+        {
+            for command in commands.iter().rev() {
+                command.undo(&mut self.accounts);
+            }
+            return Err(read_only());
+        }
+        Ok(())
+    }
+}
+
+/// Converts a ledger between RON and SQLite, as chosen by the extensions.
+///
+/// # Errors
+///
+/// Fails if `from` can't be loaded or `to` can't be written.
+pub fn convert(from: PathBuf, to: PathBuf) -> anyhow::Result<()> {
+    Accounts::convert(from, to)
+}
+
+fn read_only() -> anyhow::Error {
+    anyhow::Error::msg("That is in a file that is included read-only, so it can't be changed.")
+}
+
+pub(crate) fn no_secondary(account: &str) -> anyhow::Error {
+    anyhow::Error::msg(format!("\"{account}\" doesn't hold a secondary currency."))
+}
+
+#[derive(Debug)]
+pub(crate) struct File {
+    pub(crate) path: PathBuf,
+    pub(crate) inner: fs::File,
+    pub(crate) encryption: Option<Encryption>,
+    pub(crate) sqlite: Option<sqlite::Store>,
+    /// Another instance has the file locked.
+    pub(crate) read_only: bool,
+    /// The file as it was last loaded or saved, `None` to save over it whatever
+    /// it is now.
+    pub(crate) stamp: Option<watch::Stamp>,
+    /// When a save last backed the file up, `None` if none has since it was
+    /// loaded.
+    pub(crate) backed_up: Option<Instant>,
+    /// The ledgers it includes, in the order of `Accounts::includes`.
+    pub(crate) includes: Vec<File>,
+}
+
+impl File {
+    /// Lets go of the locks on the file and the files it includes.
+    pub(crate) fn unlock(self) -> anyhow::Result<()> {
+        for include in self.includes {
+            include.unlock()?;
+        }
+        self.inner.unlock()?;
+        Ok(())
+    }
+
+    fn changed(&self) -> anyhow::Result<bool> {
+        if let Some(stamp) = &self.stamp
+            && stamp.changed(&self.path)?
+        {
+            return Ok(true);
+        }
+        for include in &self.includes {
+            if include.changed()? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Encrypts the backups with the passphrase, and the file the next time it
+    /// is saved.
+    pub(crate) fn encrypt(&mut self, passphrase: String) -> anyhow::Result<()> {
+        if self.sqlite.is_some() {
+            return Err(anyhow::Error::msg("SQLite files can't be encrypted."));
+        }
+        let encryption = Encryption::new(passphrase)?;
+        backups::encrypt(&self.path, &encryption)?;
+        self.encryption = Some(encryption);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn balances(ledger: &Ledger, account: &str) -> Vec<Decimal> {
+        let account = ledger.account(account).unwrap();
+        account.txs_1st.txs.iter().map(|tx| tx.balance).collect()
+    }
+
+    /// Adding a price or a transaction before the rows of a ledger from before
+    /// there were snapshots keeps the values and entered balances after it.
+    #[test]
+    fn backdating_into_a_format_0_ledger() {
+        let path = testing::directory("backdating").join("old.ron");
+        fs::write(&path, testing::FORMAT_0).unwrap();
+
+        let mut ledger = Ledger::open(path.clone(), None).unwrap();
+        let price = Transaction {
+            amount: dec!(0),
+            balance: dec!(1900),
+            comment: String::new(),
+            date: parse_date("2023-10-01").unwrap(),
+            snapshot: true,
+        };
+        ledger.add_tx("Gold", false, price).unwrap();
+        let paid = Transaction {
+            amount: dec!(10),
+            balance: dec!(0),
+            comment: "refund".to_string(),
+            date: parse_date("2023-10-01").unwrap(),
+            snapshot: false,
+        };
+        ledger.add_tx("Checking", false, paid).unwrap();
+        ledger.save().unwrap();
+        ledger.close().unwrap();
+
+        let ledger = Ledger::open_read_only(path, None).unwrap();
+        assert_eq!(
+            balances(&ledger, "Gold"),
+            [dec!(1900), dec!(2000), dec!(2100), dec!(2300)]
+        );
+        assert_eq!(
+            balances(&ledger, "Checking"),
+            [dec!(10), dec!(110), dec!(85), dec!(500), dec!(450)]
+        );
+    }
+
+    /// Saving the file again doesn't copy a backup each time, so the backups
+    /// kept go further back than the last few saves.
+    #[test]
+    fn saving_often_keeps_older_backups() {
+        let path = testing::directory("saving-often").join("ledger.ron");
+        let mut ledger = Ledger::create(path.clone()).unwrap();
+        for name in ["Checking", "Savings", "Cash"] {
+            ledger.add_account(name, Currency::Fiat(Fiat::Usd)).unwrap();
+            ledger.save().unwrap();
+        }
+        assert_eq!(backups::list(&path).unwrap().len(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn saving_keeps_the_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = testing::directory("permissions").join("ledger.ron");
+        let mut ledger = Ledger::create(path.clone()).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        ledger.save().unwrap();
+
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    fn names(ledger: &Ledger) -> Vec<(&str, Option<usize>)> {
+        ledger
+            .accounts
+            .inner
+            .iter()
+            .map(|account| (account.name.as_str(), account.source))
+            .collect()
+    }
+
+    /// An account of an included ledger that is renamed stays among the
+    /// accounts of that ledger, where loading it again puts it, and is saved
+    /// to it.
+    #[test]
+    fn renaming_an_included_account_keeps_it_in_its_file() {
+        let directory = testing::directory("rename-included");
+        let household = directory.join("household.ron");
+        let mut ledger = Ledger::create(household.clone()).unwrap();
+        ledger
+            .add_account("Mortgage", Currency::Fiat(Fiat::Usd))
+            .unwrap();
+        ledger.save().unwrap();
+        ledger.close().unwrap();
+
+        let path = directory.join("ledger.ron");
+        let mut ledger = Ledger::create(path.clone()).unwrap();
+        for name in ["Checking", "Savings"] {
+            ledger.add_account(name, Currency::Fiat(Fiat::Usd)).unwrap();
+        }
+        ledger.accounts.includes.push(Include {
+            path: PathBuf::from("household.ron"),
+            read_only: false,
+            settings: Accounts::new(),
+        });
+        ledger.accounts.save_one(&mut ledger.file).unwrap();
+        ledger.close().unwrap();
+
+        let mut ledger = Ledger::open(path.clone(), None).unwrap();
+        let i = ledger.accounts.find("Mortgage").unwrap();
+        let mut account = ledger.accounts[i].clone();
+        account.name = "Car Loan".to_string();
+        ledger.accounts.remove_account(i);
+        ledger.accounts.insert_account(account, &[]);
+        ledger
+            .add_account("Zero Fee", Currency::Fiat(Fiat::Usd))
+            .unwrap();
+        let expected = [
+            ("Checking", None),
+            ("Savings", None),
+            ("Zero Fee", None),
+            ("Car Loan", Some(0)),
+        ];
+        assert_eq!(names(&ledger), expected);
+        ledger.save().unwrap();
+        ledger.close().unwrap();
+
+        let ledger = Ledger::open_read_only(path.clone(), None).unwrap();
+        assert_eq!(names(&ledger), expected);
+        let main = fs::read_to_string(&path).unwrap();
+        let included = fs::read_to_string(&household).unwrap();
+        assert!(!main.contains("Car Loan") && main.contains("Zero Fee"));
+        assert!(included.contains("Car Loan") && !included.contains("Mortgage"));
+    }
+}
@@ -12,6 +12,19 @@ pub enum Currency {
     StockPlus(StockPlus),
 }
 
+impl Currency {
+    /// The short name, e.g. `USD` or `BTC`.
+    #[must_use]
+    pub fn symbol(&self) -> String {
+        match self {
+            Self::Crypto(crypto) => crypto.symbol.clone(),
+            Self::Fiat(fiat) => fiat.symbol(),
+            Self::Metal(metal) => metal.symbol.clone(),
+            Self::StockPlus(stock_plus) => stock_plus.symbol.clone(),
+        }
+    }
+}
+
 impl fmt::Display for Currency {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -55,6 +68,7 @@ pub enum Fiat {
 }
 
 impl Fiat {
+    #[must_use]
     pub fn all() -> Vec<Self> {
         vec![
             Fiat::Usd,
@@ -85,6 +99,7 @@ impl Fiat {
         ]
     }
 
+    #[must_use]
     pub fn all_minus_existing(existing: &Vec<Self>) -> Vec<Self> {
         let fiats = Self::all();
         let mut fiats_new = Vec::new();
@@ -99,6 +114,7 @@ impl Fiat {
         fiats_new
     }
 
+    #[must_use]
     pub fn symbol(&self) -> String {
         match self {
             Self::Usd => "USD".to_string(),
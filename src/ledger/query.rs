@@ -0,0 +1,511 @@
+use std::{
+    mem::{Discriminant, discriminant},
+    str::FromStr,
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Months, NaiveDate, NaiveTime, Utc};
+use regex::Regex;
+use rust_decimal::Decimal;
+
+use super::{
+    account::{Account, transaction::Transaction, transactions::Transactions},
+    accounts::Accounts,
+    money::Fiat,
+};
+
+/// A search of the transactions of every account, e.g.
+/// `comment:/AMAZON/ abs>50 date:2025 currency:USD`.
+///
+/// The terms are separated by spaces, and values with spaces in them are
+/// quoted, e.g. `account:"Credit Card"`. A transaction is found when it matches
+/// all the terms, except that terms with the same key match if any of them
+/// does, so `account:Checking account:Savings` searches both accounts.
+///
+/// | Term                       | Matches                                          |
+/// | -------------------------- | ------------------------------------------------ |
+/// | `account:NAME`             | the account, ignoring case                       |
+/// | `currency:USD`             | accounts in the currency, or holding it, e.g. `XAU` |
+/// | `group:NAME`               | the accounts in the group                        |
+/// | `date:2025`                | a year, month (`2025-03`) or day (`2025-03-15`)  |
+/// | `date:2025-01..2025-06`    | from the start of one to the end of the other, either can be left out |
+/// | `amount>50`                | the amount compared with `>`, `>=`, `<`, `<=` or `=` |
+/// | `abs>50`                   | the amount without its sign, e.g. spending over 50 |
+/// | `comment:REGEX`, `/REGEX/` | the comment, with `/` around the regex optional  |
+/// | `WORD`                     | comments with the word in them, ignoring case    |
+///
+/// Only the transactions in the primary currency of an account are searched.
+#[derive(Clone, Debug, Default)]
+pub struct Query {
+    terms: Vec<Term>,
+}
+
+#[derive(Clone, Debug)]
+enum Term {
+    Account(String),
+    Currency(String),
+    Group(String),
+    /// From the start, up to but not including the end.
+    Date(Option<DateTime<Utc>>, Option<DateTime<Utc>>),
+    Amount(Comparison, Decimal),
+    Abs(Comparison, Decimal),
+    Comment(Regex),
+    Word(String),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+/// The transactions a query found in one currency, oldest first, as if they
+/// were one account: each balance is the total of the amounts up to it.
+#[derive(Clone, Debug)]
+pub struct Found {
+    pub txs: Transactions<Fiat>,
+    /// The index of the account each transaction is from.
+    pub accounts: Vec<usize>,
+}
+
+impl Found {
+    /// The sum of the amounts found.
+    #[must_use]
+    pub fn total(&self) -> Decimal {
+        self.txs.total()
+    }
+}
+
+impl Query {
+    /// The transactions that match, one `Found` per currency in the order of
+    /// their symbols.
+    #[must_use]
+    pub fn run(&self, accounts: &Accounts) -> Vec<Found> {
+        let found = self.run_with(accounts, |account| {
+            Ok(account
+                .txs_1st
+                .txs
+                .iter()
+                .filter(|tx| self.matches_tx(tx))
+                .cloned()
+                .collect())
+        });
+        found.unwrap_or_default()
+    }
+
+    /// Like `run`, with `txs` giving the transactions that match of each
+    /// account that does.
+    pub(crate) fn run_with(
+        &self,
+        accounts: &Accounts,
+        mut txs: impl FnMut(&Account) -> anyhow::Result<Vec<Transaction>>,
+    ) -> anyhow::Result<Vec<Found>> {
+        let mut found: Vec<(Fiat, Vec<(usize, Transaction)>)> = Vec::new();
+        for (i, account) in accounts.inner.iter().enumerate() {
+            if !self.matches_account(accounts, i, account) {
+                continue;
+            }
+            let txs = txs(account)?.into_iter().map(|tx| (i, tx));
+            let currency = &account.txs_1st.currency;
+            match found.iter_mut().find(|(fiat, _)| fiat == currency) {
+                Some((_, found)) => found.extend(txs),
+                None => found.push((currency.clone(), txs.collect())),
+            }
+        }
+        found.retain(|(_, txs)| !txs.is_empty());
+        found.sort_by_key(|(currency, _)| currency.symbol());
+
+        Ok(found
+            .into_iter()
+            .map(|(currency, mut txs)| {
+                txs.sort_by_key(|(_, tx)| tx.date);
+                let mut found = Found {
+                    txs: Transactions::new(currency),
+                    accounts: txs.iter().map(|(i, _)| *i).collect(),
+                };
+                found.txs.txs = txs
+                    .into_iter()
+                    .map(|(_, tx)| Transaction {
+                        snapshot: false,
+                        ..tx
+                    })
+                    .collect();
+                found.txs.recompute(0);
+                found
+            })
+            .collect())
+    }
+
+    /// The dates the query searches, from the earliest start of its `date:`
+    /// terms to the latest end, `None` where there is no limit.
+    pub(crate) fn dates(&self) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
+        let dates: Vec<_> = self
+            .terms
+            .iter()
+            .filter_map(|term| match term {
+                Term::Date(start, end) => Some((*start, *end)),
+                _ => None,
+            })
+            .collect();
+        if dates.is_empty() {
+            return (None, None);
+        }
+
+        let start = dates
+            .iter()
+            .map(|(start, _)| *start)
+            .try_fold(DateTime::<Utc>::MAX_UTC, |min, start| Some(min.min(start?)));
+        let end = dates
+            .iter()
+            .map(|(_, end)| *end)
+            .try_fold(DateTime::<Utc>::MIN_UTC, |max, end| Some(max.max(end?)));
+        (start, end)
+    }
+
+    fn matches_account(&self, accounts: &Accounts, i: usize, account: &Account) -> bool {
+        self.matches_all(|term| match term {
+            Term::Account(name) => Some(account.name.eq_ignore_ascii_case(name)),
+            Term::Currency(symbol) => Some(
+                account
+                    .txs_1st
+                    .currency
+                    .symbol()
+                    .eq_ignore_ascii_case(symbol)
+                    || account
+                        .txs_2nd
+                        .as_ref()
+                        .is_some_and(|txs| txs.currency.symbol().eq_ignore_ascii_case(symbol)),
+            ),
+            Term::Group(name) => {
+                Some(accounts.groups.iter().any(|group| {
+                    group.name.eq_ignore_ascii_case(name) && group.members.contains(&i)
+                }))
+            }
+            _ => None,
+        })
+    }
+
+    pub(crate) fn matches_tx(&self, tx: &Transaction) -> bool {
+        self.matches_all(|term| match term {
+            Term::Date(start, end) => Some(
+                start.is_none_or(|start| tx.date >= start) && end.is_none_or(|end| tx.date < end),
+            ),
+            Term::Amount(comparison, value) => Some(comparison.holds(tx.amount, *value)),
+            Term::Abs(comparison, value) => Some(comparison.holds(tx.amount.abs(), *value)),
+            Term::Comment(regex) => Some(regex.is_match(&tx.comment)),
+            Term::Word(word) => Some(tx.comment.to_lowercase().contains(word)),
+            _ => None,
+        })
+    }
+
+    /// Whether, for each key, one of the terms with it matches. `matches`
+    /// returns `None` for the terms it doesn't look at.
+    fn matches_all(&self, matches: impl Fn(&Term) -> Option<bool>) -> bool {
+        let mut keys: Vec<(Discriminant<Term>, bool)> = Vec::new();
+        for term in &self.terms {
+            let Some(is_match) = matches(term) else {
+                continue;
+            };
+            let key = discriminant(term);
+            if let Some((_, any)) = keys.iter_mut().find(|(k, _)| *k == key) {
+                *any |= is_match;
+            } else {
+                keys.push((key, is_match));
+            }
+        }
+        keys.into_iter().all(|(_, any)| any)
+    }
+}
+
+impl FromStr for Query {
+    type Err = anyhow::Error;
+
+    fn from_str(query: &str) -> Result<Self, Self::Err> {
+        let terms = split(query)?
+            .iter()
+            .map(|term| Term::parse(term))
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self { terms })
+    }
+}
+
+impl Term {
+    fn parse(term: &str) -> anyhow::Result<Self> {
+        for (key, abs) in [("amount", false), ("abs", true)] {
+            if let Some(rest) = term.strip_prefix(key)
+                && let Some((comparison, value)) = Comparison::split(rest)
+            {
+                let value = value
+                    .parse()
+                    .with_context(|| format!("\"{value}\" in \"{term}\" isn't a number."))?;
+                return Ok(if abs {
+                    Self::Abs(comparison, value)
+                } else {
+                    Self::Amount(comparison, value)
+                });
+            }
+        }
+
+        match term.split_once(':') {
+            Some(("account", name)) => Ok(Self::Account(name.to_string())),
+            Some(("currency", symbol)) => Ok(Self::Currency(symbol.to_string())),
+            Some(("group", name)) => Ok(Self::Group(name.to_string())),
+            Some(("date", dates)) => parse_dates(dates)
+                .with_context(|| format!("\"{dates}\" isn't a date or a range of dates, e.g. 2025, 2025-03 or 2025-01..2025-06.")),
+            Some(("comment", regex)) => comment(regex),
+            _ if term.len() > 1 && term.starts_with('/') && term.ends_with('/') => comment(term),
+            _ => Ok(Self::Word(term.to_lowercase())),
+        }
+    }
+}
+
+impl Comparison {
+    /// Splits `>=50` into the comparison and the rest. `:` is taken as `=`.
+    fn split(text: &str) -> Option<(Self, &str)> {
+        [
+            (">=", Self::GreaterOrEqual),
+            ("<=", Self::LessOrEqual),
+            (">", Self::Greater),
+            ("<", Self::Less),
+            ("=", Self::Equal),
+            (":", Self::Equal),
+        ]
+        .into_iter()
+        .find_map(|(prefix, comparison)| Some((comparison, text.strip_prefix(prefix)?)))
+    }
+
+    fn holds(self, amount: Decimal, value: Decimal) -> bool {
+        match self {
+            Self::Less => amount < value,
+            Self::LessOrEqual => amount <= value,
+            Self::Equal => amount == value,
+            Self::GreaterOrEqual => amount >= value,
+            Self::Greater => amount > value,
+        }
+    }
+}
+
+fn comment(regex: &str) -> anyhow::Result<Term> {
+    let regex = regex
+        .strip_prefix('/')
+        .and_then(|regex| regex.strip_suffix('/'))
+        .unwrap_or(regex);
+    Ok(Term::Comment(Regex::new(regex)?))
+}
+
+/// `2025`, `2025-03`, `2025-03-15`, or two of them around `..` with either left
+/// out.
+fn parse_dates(dates: &str) -> Option<Term> {
+    let Some((start, end)) = dates.split_once("..") else {
+        let (start, end) = parse_period(dates)?;
+        return Some(Term::Date(Some(start), Some(end)));
+    };
+    let start = if start.is_empty() {
+        None
+    } else {
+        Some(parse_period(start)?.0)
+    };
+    let end = if end.is_empty() {
+        None
+    } else {
+        Some(parse_period(end)?.1)
+    };
+    Some(Term::Date(start, end))
+}
+
+/// The start of a year, month or day, and the start of the next one.
+fn parse_period(period: &str) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let parts: Vec<&str> = period.split('-').collect();
+    let year: i32 = parts.first()?.parse().ok()?;
+    let month: u32 = parts.get(1).map_or(Some(1), |month| month.parse().ok())?;
+    let day: u32 = parts.get(2).map_or(Some(1), |day| day.parse().ok())?;
+    if parts.len() > 3 {
+        return None;
+    }
+
+    let start = NaiveDate::from_ymd_opt(year, month, day)?;
+    let end = match parts.len() {
+        1 => start.checked_add_months(Months::new(12))?,
+        2 => start.checked_add_months(Months::new(1))?,
+        _ => start.succ_opt()?,
+    };
+    Some((
+        start.and_time(NaiveTime::MIN).and_utc(),
+        end.and_time(NaiveTime::MIN).and_utc(),
+    ))
+}
+
+/// Splits the query on spaces outside of double quotes, and takes the quotes
+/// out.
+fn split(query: &str) -> anyhow::Result<Vec<String>> {
+    let mut terms = Vec::new();
+    let mut term = String::new();
+    let mut quoted = false;
+    for c in query.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !term.is_empty() {
+                    terms.push(std::mem::take(&mut term));
+                }
+            }
+            c => term.push(c),
+        }
+    }
+    if quoted {
+        return Err(anyhow::Error::msg("A quote in the query isn't closed."));
+    }
+    if !term.is_empty() {
+        terms.push(term);
+    }
+    Ok(terms)
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::ledger::{parse_date, testing};
+
+    /// The account and comment of each transaction found, oldest first.
+    fn found(query: &str) -> Vec<(String, String)> {
+        let accounts = testing::accounts();
+        let query: Query = query.parse().unwrap();
+        query
+            .run(&accounts)
+            .iter()
+            .flat_map(|found| {
+                found
+                    .txs
+                    .txs
+                    .iter()
+                    .zip(&found.accounts)
+                    .map(|(tx, i)| (accounts.inner[*i].name.clone(), tx.comment.clone()))
+            })
+            .collect()
+    }
+
+    fn comments(query: &str) -> Vec<String> {
+        found(query)
+            .into_iter()
+            .map(|(_, comment)| comment)
+            .collect()
+    }
+
+    #[test]
+    fn split_keeps_quoted_spaces() {
+        assert_eq!(
+            split(r#"account:"Credit Card"  abs>50 "#).unwrap(),
+            ["account:Credit Card", "abs>50"]
+        );
+        assert!(split(r#"comment:"coffee"#).is_err());
+    }
+
+    #[test]
+    fn parse_reads_every_kind_of_term() {
+        assert!(matches!(
+            Term::parse("amount>=-25").unwrap(),
+            Term::Amount(Comparison::GreaterOrEqual, value) if value == dec!(-25)
+        ));
+        assert!(matches!(
+            Term::parse("abs:50").unwrap(),
+            Term::Abs(Comparison::Equal, value) if value == dec!(50)
+        ));
+        assert!(Term::parse("abs>fifty").is_err());
+        assert!(
+            matches!(Term::parse("account:Checking").unwrap(), Term::Account(name) if name == "Checking")
+        );
+        assert!(matches!(Term::parse("Coffee").unwrap(), Term::Word(word) if word == "coffee"));
+        assert!(
+            matches!(Term::parse("comment:/a/b/").unwrap(), Term::Comment(regex) if regex.as_str() == "a/b")
+        );
+        assert!(
+            matches!(Term::parse("/^Pay/").unwrap(), Term::Comment(regex) if regex.as_str() == "^Pay")
+        );
+        assert!(matches!(Term::parse("/").unwrap(), Term::Word(_)));
+        assert!(Term::parse("comment:(").is_err());
+        assert!(Term::parse("date:2024-13").is_err());
+    }
+
+    #[test]
+    fn dates_cover_years_months_days_and_ranges() {
+        let date = |date| Some(parse_date(date).unwrap());
+        let dates = |query: &str| query.parse::<Query>().unwrap().dates();
+
+        assert_eq!(dates("date:2024"), (date("2024-01-01"), date("2025-01-01")));
+        assert_eq!(
+            dates("date:2024-02"),
+            (date("2024-02-01"), date("2024-03-01"))
+        );
+        assert_eq!(
+            dates("date:2024-02-29"),
+            (date("2024-02-29"), date("2024-03-01"))
+        );
+        assert_eq!(
+            dates("date:2024-01..2024-03"),
+            (date("2024-01-01"), date("2024-04-01"))
+        );
+        assert_eq!(dates("date:2024-06.."), (date("2024-06-01"), None));
+        assert_eq!(dates("date:..2024"), (None, date("2025-01-01")));
+        assert_eq!(
+            dates("date:2023 date:2024-03"),
+            (date("2023-01-01"), date("2024-04-01"))
+        );
+        assert_eq!(dates("coffee"), (None, None));
+    }
+
+    #[test]
+    fn terms_with_the_same_key_match_if_any_does() {
+        assert_eq!(
+            found("account:checking account:SAVINGS date:2024-01"),
+            [
+                ("Checking".to_string(), "Pay day".to_string()),
+                ("Savings".to_string(), "opening".to_string()),
+                ("Checking".to_string(), "Coffee: beans".to_string()),
+            ]
+        );
+        assert_eq!(
+            comments("account:Checking abs>=50"),
+            ["Pay day", "statement", "GROCERIES 1234"]
+        );
+        assert_eq!(
+            comments("account:Checking amount<0 date:2024-03"),
+            ["GROCERIES 1234"]
+        );
+    }
+
+    #[test]
+    fn comments_match_words_and_regexes() {
+        assert_eq!(comments("coffee"), ["Coffee: beans"]);
+        assert_eq!(comments(r"/\d{4}$/"), ["GROCERIES 1234"]);
+        assert_eq!(comments(r#""coffee: beans""#), ["Coffee: beans"]);
+    }
+
+    #[test]
+    fn groups_and_currencies_pick_the_accounts() {
+        assert_eq!(comments("group:cash date:2024-01-10"), ["opening"]);
+        assert_eq!(found("currency:XAU").len(), 3);
+        assert!(found("currency:XAU").iter().all(|(name, _)| name == "Gold"));
+        assert_eq!(found("currency:USD").len(), 8);
+        assert!(found("account:Nowhere").is_empty());
+    }
+
+    #[test]
+    fn found_balances_add_up_the_amounts() {
+        let accounts = testing::accounts();
+        let query: Query = "group:Cash".parse().unwrap();
+        let found = query.run(&accounts);
+        assert_eq!(found.len(), 1);
+        let balances: Vec<_> = found[0].txs.txs.iter().map(|tx| tx.balance).collect();
+        assert_eq!(
+            balances,
+            [dec!(100), dec!(1100), dec!(1075), dec!(1500), dec!(1450)]
+        );
+        assert_eq!(found[0].total(), dec!(1450));
+        assert!(found[0].txs.txs.iter().all(|tx| !tx.snapshot));
+    }
+}
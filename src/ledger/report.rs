@@ -0,0 +1,269 @@
+use std::{
+    fmt::{self, Write},
+    path::Path,
+};
+
+use clap::ValueEnum;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+
+use super::{account::Account, accounts::Accounts};
+
+/// The digits kept of the week, month and year changes.
+const CHANGE_SCALE: u32 = 4;
+
+/// The change over the last week, month and year and the balance of every
+/// account, currency and group, as shown on the accounts screen.
+///
+/// The field names of `Line` are what the JSON and CSV output use, and are kept
+/// as they are.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Report {
+    pub accounts: Vec<Line>,
+    pub totals: Vec<Line>,
+    pub groups: Vec<Line>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Line {
+    pub kind: Kind,
+    /// The name of the account or group, or of the currency for totals.
+    pub name: String,
+    /// The symbol of the currency, e.g. `USD`, `None` for a group of accounts
+    /// in more than one.
+    pub currency: Option<String>,
+    /// How much the balance changed over the last week, as a fraction of what
+    /// it was before, e.g. `0.0125`.
+    pub week: Decimal,
+    pub month: Decimal,
+    pub year: Decimal,
+    pub balance: Decimal,
+    /// The price of the secondary currency, for accounts that hold one.
+    pub price: Option<Decimal>,
+    /// How much of the secondary currency the account holds.
+    pub quantity: Option<Decimal>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    Account,
+    Total,
+    Group,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Account => write!(f, "account"),
+            Self::Total => write!(f, "total"),
+            Self::Group => write!(f, "group"),
+        }
+    }
+}
+
+/// How a report is printed.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum Format {
+    /// Tab-separated lines without a header
+    #[default]
+    Text,
+    Json,
+    /// With a header line
+    Csv,
+}
+
+impl Report {
+    #[must_use]
+    pub fn new(accounts: &Accounts) -> Self {
+        let lines = accounts.inner.iter().map(account_line).collect();
+
+        let mut currencies: Vec<_> = accounts.currencies().into_iter().collect();
+        currencies.sort_by_key(super::money::Fiat::symbol);
+        let totals = currencies
+            .into_iter()
+            .map(|currency| {
+                Line::new(
+                    Kind::Total,
+                    currency.to_string(),
+                    Some(currency.symbol()),
+                    [
+                        accounts.total_for_last_week(&currency),
+                        accounts.total_for_last_month(&currency),
+                        accounts.total_for_last_year(&currency),
+                    ],
+                    accounts.balance(&currency),
+                )
+            })
+            .collect();
+
+        let groups = accounts
+            .groups
+            .iter()
+            .map(|group| {
+                let members: Vec<&Account> = group.members.iter().map(|i| &accounts[*i]).collect();
+                let mut sums = [(dec!(0), dec!(0)); 3];
+                let mut balance = dec!(0);
+                for account in &members {
+                    for (sum, (before, after)) in sums.iter_mut().zip(account_sums(account)) {
+                        sum.0 += before;
+                        sum.1 += after;
+                    }
+                    balance += account.balance_1st();
+                }
+
+                let currency = members
+                    .first()
+                    .map(|account| &account.txs_1st.currency)
+                    .filter(|currency| {
+                        members
+                            .iter()
+                            .all(|account| account.txs_1st.currency == **currency)
+                    })
+                    .map(super::money::Fiat::symbol);
+
+                Line::new(Kind::Group, group.name.clone(), currency, sums, balance)
+            })
+            .collect();
+
+        Self {
+            accounts: lines,
+            totals,
+            groups,
+        }
+    }
+
+    /// Loads the ledger read-only and reports on it.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the ledger can't be loaded.
+    pub fn load(ledger: &Path, passphrase: Option<&str>) -> anyhow::Result<Self> {
+        let (accounts, _file) =
+            Accounts::load_read_only(&mut None, ledger.to_path_buf(), passphrase)?;
+        Ok(Self::new(&accounts))
+    }
+
+    /// Every line, accounts first, then totals, then groups.
+    pub fn lines(&self) -> impl Iterator<Item = &Line> {
+        self.accounts.iter().chain(&self.totals).chain(&self.groups)
+    }
+
+    /// # Errors
+    ///
+    /// Fails if the report can't be written in the format.
+    pub fn to_format(&self, format: Format) -> anyhow::Result<String> {
+        match format {
+            Format::Text => Ok(self.to_text()),
+            Format::Json => self.to_json(),
+            Format::Csv => self.to_csv(),
+        }
+    }
+
+    /// An object with the arrays `accounts`, `totals` and `groups`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the report can't be serialized.
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// One row per line with the fields of `Line` as the header.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the report can't be serialized.
+    pub fn to_csv(&self) -> anyhow::Result<String> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        for line in self.lines() {
+            writer.serialize(line)?;
+        }
+        Ok(String::from_utf8(writer.into_inner()?)?)
+    }
+
+    /// One tab-separated row per line.
+    #[must_use]
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for line in self.lines() {
+            let _ = writeln!(
+                text,
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                line.kind,
+                line.name,
+                line.currency.as_deref().unwrap_or_default(),
+                line.week,
+                line.month,
+                line.year,
+                line.balance
+            );
+        }
+        text
+    }
+}
+
+impl Line {
+    fn new(
+        kind: Kind,
+        name: String,
+        currency: Option<String>,
+        sums: [(Decimal, Decimal); 3],
+        mut balance: Decimal,
+    ) -> Self {
+        let [week, month, year] = sums.map(|(before, after)| {
+            let mut change = div_0_ok(after, before);
+            change.rescale(CHANGE_SCALE);
+            change
+        });
+        balance.rescale(2);
+
+        Self {
+            kind,
+            name,
+            currency,
+            week,
+            month,
+            year,
+            balance,
+            price: None,
+            quantity: None,
+        }
+    }
+}
+
+fn account_sums(account: &Account) -> [(Decimal, Decimal); 3] {
+    [
+        account.sum_last_week(),
+        account.sum_last_month(),
+        account.sum_last_year(),
+    ]
+}
+
+fn account_line(account: &Account) -> Line {
+    let mut line = Line::new(
+        Kind::Account,
+        account.name.clone(),
+        Some(account.txs_1st.currency.symbol()),
+        account_sums(account),
+        account.balance_1st(),
+    );
+
+    if let Some(mut quantity) = account.balance_2nd() {
+        let mut price = div_0_ok(account.balance_1st(), quantity);
+        quantity.rescale(8);
+        price.rescale(2);
+        line.quantity = Some(quantity);
+        line.price = Some(price);
+    }
+    line
+}
+
+fn div_0_ok(dividend: Decimal, divisor: Decimal) -> Decimal {
+    if divisor.is_zero() {
+        dec!(0)
+    } else {
+        dividend / divisor
+    }
+}
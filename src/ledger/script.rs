@@ -0,0 +1,517 @@
+use std::{
+    cell::RefCell,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use anyhow::Context;
+use rhai::{
+    Array, CallFnOptions, Dynamic, Engine, EvalAltResult, INT, Map, Scope,
+    module_resolvers::DummyModuleResolver,
+};
+use rust_decimal::Decimal;
+
+use super::{
+    account::{Account, parse_date, transaction::Transaction},
+    accounts::Accounts,
+    backups,
+    history::Command,
+};
+
+/// When the scripts of a ledger are run.
+#[derive(Clone, Debug)]
+pub enum Event {
+    /// Each script is run from the top, e.g. from the configuration screen or
+    /// `run-script`.
+    Demand,
+    /// `fn on_import(accounts)` is called with the names of the accounts that
+    /// were imported into.
+    Import(Vec<String>),
+    /// `fn on_prices()` is called after prices were got.
+    Prices,
+}
+
+/// What scripts printed and alerted, and why the ones that failed did.
+#[derive(Debug, Default)]
+pub struct Output {
+    /// Each line after the name of the script that printed it.
+    pub lines: Vec<String>,
+    /// What scripts called `alert` with, shown like errors.
+    pub alerts: Vec<String>,
+    /// The changes a script made before failing are left out.
+    pub errors: Vec<anyhow::Error>,
+}
+
+impl Output {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty() && self.alerts.is_empty() && self.errors.is_empty()
+    }
+}
+
+/// A script read from its file.
+pub(crate) struct Script {
+    name: String,
+    source: String,
+}
+
+impl Script {
+    /// Reads the script, relative to the directory of the ledger unless the
+    /// path is absolute.
+    pub(crate) fn read(file_path: &Path, path: &Path) -> anyhow::Result<Self> {
+        let path = resolve(file_path, path);
+        let source = fs::read_to_string(&path)
+            .with_context(|| format!("reading the script {}", path.display()))?;
+        let name = path.file_name().map_or_else(
+            || path.display().to_string(),
+            |name| name.to_string_lossy().to_string(),
+        );
+        Ok(Self { name, source })
+    }
+}
+
+/// Where the script is, given the path of the ledger.
+pub(crate) fn resolve(file_path: &Path, path: &Path) -> PathBuf {
+    backups::directory(file_path).join(path)
+}
+
+/// A change a script asked for, made once the script has finished.
+#[derive(Clone, Debug)]
+enum Change {
+    AddTx {
+        account: usize,
+        tx: Transaction,
+    },
+    SetComment {
+        account: usize,
+        index: usize,
+        comment: String,
+    },
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// The name of the script that is running.
+    script: String,
+    changes: Vec<Change>,
+    output: Output,
+}
+
+type Shared = Rc<RefCell<State>>;
+
+type Fallible<T> = Result<T, Box<EvalAltResult>>;
+
+/// Runs the scripts for the event, one after the other, and makes the changes
+/// they asked for. Every script reads the accounts as they were before any of
+/// them ran. Returns the changes made, as one command.
+pub(crate) fn run(
+    accounts: &mut Accounts,
+    scripts: Vec<anyhow::Result<Script>>,
+    event: &Event,
+) -> (Option<Command>, Output) {
+    let state: Shared = Rc::default();
+    let engine = engine(&Rc::new(accounts.clone()), &state);
+
+    for script in scripts {
+        let script = match script {
+            Ok(script) => script,
+            Err(error) => {
+                state.borrow_mut().output.errors.push(error);
+                continue;
+            }
+        };
+        state.borrow_mut().script.clone_from(&script.name);
+        let changes = state.borrow().changes.len();
+
+        if let Err(error) = run_one(&engine, &script, event) {
+            let mut state = state.borrow_mut();
+            state.changes.truncate(changes);
+            state
+                .output
+                .errors
+                .push(anyhow::Error::msg(format!("{}: {error}", script.name)));
+        }
+    }
+
+    let State {
+        changes, output, ..
+    } = std::mem::take(&mut *state.borrow_mut());
+    (apply(accounts, changes), output)
+}
+
+fn run_one(engine: &Engine, script: &Script, event: &Event) -> Fallible<()> {
+    let ast = engine.compile(&script.source)?;
+    let (name, args) = match event {
+        Event::Demand => return engine.run_ast(&ast),
+        Event::Import(names) => (
+            "on_import",
+            vec![Dynamic::from_array(
+                names.iter().cloned().map(Dynamic::from).collect(),
+            )],
+        ),
+        Event::Prices => ("on_prices", Vec::new()),
+    };
+    if !ast.iter_functions().any(|function| function.name == name) {
+        return Ok(());
+    }
+    engine
+        .call_fn_with_options::<Dynamic>(
+            CallFnOptions::new().eval_ast(false),
+            &mut Scope::new(),
+            &ast,
+            name,
+            args,
+        )
+        .map(drop)
+}
+
+/// An engine that can't reach anything but the accounts, and gives up on
+/// scripts that run too long or grow too large.
+fn engine(accounts: &Rc<Accounts>, state: &Shared) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .disable_symbol("eval")
+        .set_max_operations(10_000_000)
+        .set_max_call_levels(64)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(1024 * 1024)
+        .set_max_array_size(100_000)
+        .set_max_map_size(10_000);
+
+    let print = state.clone();
+    engine.on_print(move |text| {
+        let mut state = print.borrow_mut();
+        let line = format!("{}: {text}", state.script);
+        state.output.lines.push(line);
+    });
+    let debug = state.clone();
+    engine.on_debug(move |text, _, position| {
+        let mut state = debug.borrow_mut();
+        let line = format!("{} {position}: {text}", state.script);
+        state.output.lines.push(line);
+    });
+    let alert = state.clone();
+    engine.register_fn("alert", move |text: &str| {
+        let mut state = alert.borrow_mut();
+        let line = format!("{}: {text}", state.script);
+        state.output.alerts.push(line);
+    });
+
+    register_reads(&mut engine, accounts);
+    register_changes(&mut engine, accounts, state);
+    engine
+}
+
+fn register_reads(engine: &mut Engine, accounts: &Rc<Accounts>) {
+    let a = accounts.clone();
+    engine.register_fn("accounts", move || -> Array {
+        a.inner
+            .iter()
+            .map(|account| Dynamic::from(account.name.clone()))
+            .collect()
+    });
+    let a = accounts.clone();
+    engine.register_fn("balance", move |name: &str| -> Fallible<Decimal> {
+        Ok(a[find(&a, name)?].balance_1st())
+    });
+    let a = accounts.clone();
+    engine.register_fn("balance_2nd", move |name: &str| -> Fallible<Decimal> {
+        a[find(&a, name)?]
+            .balance_2nd()
+            .ok_or_else(|| super::no_secondary(name).to_string().into())
+    });
+    let a = accounts.clone();
+    engine.register_fn("currency", move |name: &str| -> Fallible<String> {
+        Ok(a[find(&a, name)?].txs_1st.currency.symbol())
+    });
+    let a = accounts.clone();
+    engine.register_fn("txs", move |name: &str| -> Fallible<Array> {
+        Ok(a[find(&a, name)?]
+            .txs_1st
+            .txs
+            .iter()
+            .enumerate()
+            .map(|(index, tx)| Dynamic::from_map(tx_map(index, tx)))
+            .collect())
+    });
+    let a = accounts.clone();
+    engine.register_fn("total", move |symbol: &str| -> Decimal {
+        a.inner
+            .iter()
+            .filter(|account| {
+                account
+                    .txs_1st
+                    .currency
+                    .symbol()
+                    .eq_ignore_ascii_case(symbol)
+            })
+            .map(Account::balance_1st)
+            .sum()
+    });
+    let a = accounts.clone();
+    engine.register_fn("groups", move || -> Array {
+        a.groups
+            .iter()
+            .map(|group| Dynamic::from(group.name.clone()))
+            .collect()
+    });
+    let a = accounts.clone();
+    engine.register_fn("group", move |name: &str| -> Fallible<Array> {
+        let group = a
+            .groups
+            .iter()
+            .find(|group| group.name == name)
+            .ok_or_else(|| format!("There is no group named \"{name}\"."))?;
+        Ok(group
+            .members
+            .iter()
+            .map(|i| Dynamic::from(a[*i].name.clone()))
+            .collect())
+    });
+}
+
+fn register_changes(engine: &mut Engine, accounts: &Rc<Accounts>, state: &Shared) {
+    let (a, s) = (accounts.clone(), state.clone());
+    let add_tx = move |name: &str, amount: Decimal, comment: &str, date: &str| -> Fallible<()> {
+        let account = writable(&a, name)?;
+        let date = parse_date(date).map_err(|error| error.to_string().trim_end().to_string())?;
+        s.borrow_mut().changes.push(Change::AddTx {
+            account,
+            tx: Transaction {
+                amount,
+                balance: Decimal::ZERO,
+                comment: comment.trim().to_string(),
+                date,
+                snapshot: false,
+            },
+        });
+        Ok(())
+    };
+    let add = add_tx.clone();
+    engine.register_fn(
+        "add_tx",
+        move |name: &str, amount: Decimal, comment: &str| add(name, amount, comment, ""),
+    );
+    let add = add_tx.clone();
+    engine.register_fn("add_tx", move |name: &str, amount: INT, comment: &str| {
+        add(name, amount.into(), comment, "")
+    });
+    let add = add_tx.clone();
+    engine.register_fn(
+        "add_tx",
+        move |name: &str, amount: INT, comment: &str, date: &str| {
+            add(name, amount.into(), comment, date)
+        },
+    );
+    engine.register_fn("add_tx", add_tx);
+
+    let (a, s) = (accounts.clone(), state.clone());
+    engine.register_fn(
+        "set_comment",
+        move |name: &str, index: INT, comment: &str| -> Fallible<()> {
+            let account = writable(&a, name)?;
+            let index = usize::try_from(index)
+                .ok()
+                .filter(|index| *index < a[account].txs_1st.txs.len())
+                .ok_or_else(|| format!("\"{name}\" has no transaction {index}."))?;
+            s.borrow_mut().changes.push(Change::SetComment {
+                account,
+                index,
+                comment: comment.trim().to_string(),
+            });
+            Ok(())
+        },
+    );
+}
+
+fn find(accounts: &Accounts, name: &str) -> Fallible<usize> {
+    accounts
+        .find(name)
+        .map_err(|error| error.to_string().into())
+}
+
+/// The account, if it isn't in a file that is included read-only.
+fn writable(accounts: &Accounts, name: &str) -> Fallible<usize> {
+    let i = find(accounts, name)?;
+    if accounts.is_read_only(accounts[i].source) {
+        return Err(super::read_only().to_string().into());
+    }
+    Ok(i)
+}
+
+fn tx_map(index: usize, tx: &Transaction) -> Map {
+    let mut map = Map::new();
+    map.insert(
+        "index".into(),
+        Dynamic::from(INT::try_from(index).unwrap_or(INT::MAX)),
+    );
+    map.insert(
+        "date".into(),
+        Dynamic::from(tx.date.format("%Y-%m-%d").to_string()),
+    );
+    map.insert("amount".into(), Dynamic::from_decimal(tx.amount));
+    map.insert("balance".into(), Dynamic::from_decimal(tx.balance));
+    map.insert("comment".into(), Dynamic::from(tx.comment.clone()));
+    map.insert("snapshot".into(), Dynamic::from_bool(tx.snapshot));
+    map
+}
+
+/// Makes the changes, the comments first so the indexes the scripts saw still
+/// point at the same transactions.
+fn apply(accounts: &mut Accounts, changes: Vec<Change>) -> Option<Command> {
+    let (comments, txs): (Vec<_>, Vec<_>) = changes
+        .into_iter()
+        .partition(|change| matches!(change, Change::SetComment { .. }));
+
+    let mut commands = Vec::new();
+    for change in comments.into_iter().chain(txs) {
+        match change {
+            Change::AddTx { account, tx } => commands.push(accounts.insert_tx(account, false, tx)),
+            Change::SetComment {
+                account,
+                index,
+                comment,
+            } => {
+                let tx = &mut accounts[account].txs_1st.txs[index];
+                if tx.comment == comment {
+                    continue;
+                }
+                let before = tx.clone();
+                tx.comment = comment;
+                commands.push(Command::EditTransaction {
+                    account,
+                    secondary: false,
+                    index_before: index,
+                    index_after: index,
+                    before,
+                    after: tx.clone(),
+                });
+            }
+        }
+    }
+
+    if commands.is_empty() {
+        None
+    } else {
+        Some(Command::Batch(commands))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::ledger::testing;
+
+    fn run_source(
+        accounts: &mut Accounts,
+        source: &str,
+        event: &Event,
+    ) -> (Option<Command>, Output) {
+        let script = Script {
+            name: "test.rhai".to_string(),
+            source: source.to_string(),
+        };
+        run(accounts, vec![Ok(script)], event)
+    }
+
+    /// The script fails with the error, and the change it asked for first
+    /// isn't made.
+    fn fails(source: &str, error: &str) {
+        let mut accounts = testing::accounts();
+        let source = format!("add_tx(\"Checking\", 1, \"before\");\n{source}");
+        let (command, output) = run_source(&mut accounts, &source, &Event::Demand);
+
+        assert!(command.is_none(), "{source}");
+        assert_eq!(
+            accounts.to_string().unwrap(),
+            testing::accounts().to_string().unwrap()
+        );
+        assert_eq!(output.errors.len(), 1, "{source}");
+        let message = output.errors[0].to_string();
+        assert!(message.starts_with("test.rhai: "), "{message}");
+        assert!(message.contains(error), "{message}");
+    }
+
+    #[test]
+    fn runaway_scripts_are_stopped() {
+        fails("loop {}", "Too many operations");
+        fails(
+            "fn deeper(n) { deeper(n + 1) } deeper(0);",
+            "Stack overflow",
+        );
+        fails(
+            "let text = \"x\"; loop { text += text; }",
+            "Length of string too large",
+        );
+        fails(
+            "let list = []; loop { list.push(1); }",
+            "Size of array/BLOB too large",
+        );
+    }
+
+    #[test]
+    fn scripts_cant_reach_files_or_modules() {
+        fails("import \"std\" as std;", "Module not found");
+        fails("eval(\"1\");", "eval");
+        fails("open_file(\"ledger.ron\");", "Function not found");
+        fails("read_string(\"ledger.ron\");", "Function not found");
+    }
+
+    #[test]
+    fn alerts_output_and_changes_come_back() {
+        let mut accounts = testing::accounts();
+        let source = r#"
+            print(`${accounts().len()} accounts`);
+            if balance("Checking") < 1000 {
+                alert(`Checking is down to ${balance("Checking")}`);
+            }
+            for tx in txs("Checking") {
+                if tx.comment.starts_with("Coffee") {
+                    set_comment("Checking", tx.index, "[food] " + tx.comment);
+                }
+            }
+            add_tx("Checking", -5, " tip ", "2024-03-02");
+        "#;
+        let (command, output) = run_source(&mut accounts, source, &Event::Demand);
+
+        assert_eq!(output.lines, ["test.rhai: 3 accounts"]);
+        assert_eq!(output.alerts, ["test.rhai: Checking is down to 450"]);
+        assert!(output.errors.is_empty());
+        let Some(Command::Batch(commands)) = command else {
+            panic!("the changes are one command");
+        };
+        assert_eq!(commands.len(), 2);
+
+        let checking = &accounts[accounts.find("Checking").unwrap()];
+        assert_eq!(checking.balance_1st(), dec!(445));
+        let comments: Vec<&str> = checking
+            .txs_1st
+            .txs
+            .iter()
+            .map(|tx| tx.comment.as_str())
+            .collect();
+        assert!(comments.contains(&"[food] Coffee: beans"));
+        assert!(comments.contains(&"tip"));
+    }
+
+    #[test]
+    fn events_call_their_functions() {
+        let source = r#"
+            fn on_import(accounts) { print(`imported ${accounts}`); }
+            print("top");
+        "#;
+        let mut accounts = testing::accounts();
+        let event = Event::Import(vec!["Checking".to_string()]);
+        let (command, output) = run_source(&mut accounts, source, &event);
+        assert!(command.is_none());
+        assert_eq!(output.lines, ["test.rhai: imported [\"Checking\"]"]);
+
+        // A script without `on_prices` isn't run for prices.
+        let (_, output) = run_source(&mut accounts, source, &Event::Prices);
+        assert!(output.is_empty());
+    }
+}
@@ -0,0 +1,679 @@
+use std::{
+    collections::HashMap,
+    fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::Read,
+    path::Path,
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use rusqlite::{CachedStatement, Connection, OpenFlags, Row, params};
+use serde::de::DeserializeOwned;
+
+use super::{
+    account::{Account, transaction::Transaction, transactions::Transactions},
+    accounts::Accounts,
+    format,
+    money::{Currency, Fiat},
+    query::{Found, Query},
+};
+
+/// Files with these extensions are SQLite databases instead of RON.
+pub const EXTENSIONS: [&str; 3] = ["db", "sqlite", "sqlite3"];
+
+const MAGIC: &[u8] = b"SQLite format 3\0";
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS accounts (
+    position INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    currency TEXT NOT NULL,
+    currency_secondary TEXT,
+    import_decisions TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS transactions (
+    account TEXT NOT NULL,
+    secondary INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    date INTEGER NOT NULL,
+    amount TEXT NOT NULL,
+    balance TEXT NOT NULL,
+    comment TEXT NOT NULL,
+    snapshot INTEGER NOT NULL,
+    PRIMARY KEY (account, secondary, position)
+);
+CREATE INDEX IF NOT EXISTS transactions_by_date ON transactions (account, secondary, date);
+";
+
+pub fn is_sqlite(file_path: &Path) -> bool {
+    file_path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+/// Whether the file is a SQLite database, whatever its name.
+pub fn is_sqlite_file(file_path: &Path) -> anyhow::Result<bool> {
+    let mut magic = [0; MAGIC.len()];
+    let mut file = fs::File::open(file_path)?;
+    Ok(file.read_exact(&mut magic).is_ok() && magic == MAGIC)
+}
+
+/// Copies the database as it is between writes, which copying the file can't
+/// promise while another connection is writing to it.
+pub fn back_up(file_path: &Path, backup: &Path) -> anyhow::Result<()> {
+    let backup = backup
+        .to_str()
+        .with_context(|| format!("{} isn't valid UTF-8.", backup.display()))?;
+    let connection = Connection::open_with_flags(file_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    connection.execute("VACUUM INTO ?1", [backup])?;
+    Ok(())
+}
+
+/// A ledger kept in a SQLite database.
+///
+/// A save only writes the rows that changed since the last one: the settings
+/// and accounts that are different, and the transactions that aren't the same,
+/// so adding a transaction at the end writes one row. What was last written is
+/// kept as a hash of each transaction, not a copy of it.
+#[derive(Debug)]
+pub struct Store {
+    connection: Connection,
+    /// What is in the database, `None` until it has been read or written
+    /// through this store.
+    saved: Option<Saved>,
+}
+
+/// The rows as they were last read or written.
+#[derive(Debug, Default)]
+struct Saved {
+    settings: HashMap<String, String>,
+    accounts: Vec<AccountRow>,
+    /// A hash of each transaction, by account name and whether they are the
+    /// secondary ones.
+    transactions: HashMap<(String, bool), Vec<u64>>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct AccountRow {
+    name: String,
+    currency: String,
+    currency_secondary: Option<String>,
+    import_decisions: String,
+}
+
+impl Store {
+    /// Opens the database, creating it and its tables if needed.
+    pub fn create(file_path: &Path) -> anyhow::Result<Self> {
+        let connection = Connection::open(file_path)?;
+        connection.execute_batch(SCHEMA)?;
+
+        Ok(Self {
+            connection,
+            saved: None,
+        })
+    }
+
+    pub fn open(file_path: &Path) -> anyhow::Result<(Self, Accounts)> {
+        if !is_sqlite_file(file_path)? {
+            return Err(anyhow::Error::msg(format!(
+                "{} isn't a SQLite database.",
+                file_path.display()
+            )));
+        }
+
+        let mut store = Self::create(file_path)?;
+        let accounts = store.read()?;
+        Ok((store, accounts))
+    }
+
+    /// Opens the database to read it, without writing anything to it or
+    /// reading the transactions yet.
+    pub fn open_read_only(file_path: &Path) -> anyhow::Result<Self> {
+        let connection = Connection::open_with_flags(
+            file_path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        Ok(Self {
+            connection,
+            saved: None,
+        })
+    }
+
+    fn settings(&self) -> anyhow::Result<HashMap<String, String>> {
+        let mut statement = self.connection.prepare("SELECT key, value FROM settings")?;
+        let settings = statement
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        Ok(settings)
+    }
+
+    fn read(&mut self) -> anyhow::Result<Accounts> {
+        let (mut accounts, mut saved) = self.read_accounts()?;
+
+        for account in &mut accounts.inner {
+            account.txs_1st.txs = self.transactions(&account.name, false)?;
+            if let Some(txs_2nd) = &mut account.txs_2nd {
+                txs_2nd.txs = self.transactions(&account.name, true)?;
+            }
+        }
+
+        saved.transactions = hashes(&accounts);
+        self.saved = Some(saved);
+        Ok(accounts)
+    }
+
+    /// The settings and the accounts, without their transactions.
+    fn read_accounts(&self) -> anyhow::Result<(Accounts, Saved)> {
+        let settings = self.settings()?;
+
+        let mut accounts = Accounts::new();
+        let version = setting(&settings, "version")?.unwrap_or(format::VERSION);
+        format::check_version(version)?;
+
+        accounts.groups = setting(&settings, "groups")?.unwrap_or_default();
+        accounts.crypto = setting(&settings, "crypto")?.unwrap_or_default();
+        accounts.fiats = setting(&settings, "fiats")?.unwrap_or_default();
+        accounts.metals = setting(&settings, "metals")?.unwrap_or_default();
+        accounts.stocks_plus = setting(&settings, "stocks_plus")?.unwrap_or_default();
+        accounts.duplicate_matcher = setting(&settings, "duplicate_matcher")?.unwrap_or_default();
+        if let Some(backups) = setting(&settings, "backups")? {
+            accounts.backups = backups;
+        }
+        accounts.includes = setting(&settings, "includes")?.unwrap_or_default();
+        accounts.scripts = setting(&settings, "scripts")?.unwrap_or_default();
+
+        let mut statement = self.connection.prepare(
+            "SELECT name, currency, currency_secondary, import_decisions FROM accounts ORDER BY position",
+        )?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok(AccountRow {
+                    name: row.get(0)?,
+                    currency: row.get(1)?,
+                    currency_secondary: row.get(2)?,
+                    import_decisions: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for row in &rows {
+            let fiat: Fiat = ron::from_str(&row.currency)?;
+            let mut account = Account::new(row.name.clone(), Currency::Fiat(fiat));
+            if let Some(currency) = &row.currency_secondary {
+                let currency: Currency = ron::from_str(currency)?;
+                account.txs_2nd = Some(Transactions::new(currency));
+            }
+            account.import_decisions = ron::from_str(&row.import_decisions)?;
+            accounts.inner.push(account);
+        }
+
+        let saved = Saved {
+            settings,
+            accounts: rows,
+            transactions: HashMap::new(),
+        };
+        Ok((accounts, saved))
+    }
+
+    fn transactions(&self, account: &str, secondary: bool) -> anyhow::Result<Vec<Transaction>> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT date, amount, balance, comment, snapshot FROM transactions
+             WHERE account = ?1 AND secondary = ?2 ORDER BY position",
+        )?;
+        let rows = statement.query_map(params![account, secondary], columns)?;
+        rows.map(|row| transaction(row?)).collect()
+    }
+
+    /// The transactions of an account from `start` up to but not including
+    /// `end`, found with the `transactions_by_date` index.
+    fn transactions_between(
+        &self,
+        account: &str,
+        secondary: bool,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> anyhow::Result<Vec<Transaction>> {
+        let mut statement = self.connection.prepare_cached(
+            "SELECT date, amount, balance, comment, snapshot FROM transactions
+             WHERE account = ?1 AND secondary = ?2 AND date >= ?3 AND date < ?4
+             ORDER BY date, position",
+        )?;
+        let start = start.map_or(i64::MIN, |start| start.timestamp());
+        let end = end.map_or(i64::MAX, |end| end.timestamp());
+        let rows = statement.query_map(params![account, secondary, start, end], columns)?;
+        rows.map(|row| transaction(row?)).collect()
+    }
+
+    /// Searches the database without reading all of it: only the transactions
+    /// of the accounts the query can match, in the dates it searches, are read.
+    /// Returns `None` for a ledger that includes other files, as their accounts
+    /// aren't in the database. The accounts returned have no transactions, they
+    /// are for the indexes in `Found::accounts`.
+    pub fn query(&self, query: &Query) -> anyhow::Result<Option<(Accounts, Vec<Found>)>> {
+        let (accounts, _saved) = self.read_accounts()?;
+        if !accounts.includes.is_empty() {
+            return Ok(None);
+        }
+
+        let (start, end) = query.dates();
+        let found = query.run_with(&accounts, |account| {
+            let txs = self.transactions_between(&account.name, false, start, end)?;
+            Ok(txs.into_iter().filter(|tx| query.matches_tx(tx)).collect())
+        })?;
+        Ok(Some((accounts, found)))
+    }
+
+    /// Writes the changes since the last save in one transaction.
+    pub fn write(&mut self, accounts: &Accounts) -> anyhow::Result<()> {
+        let settings = settings(accounts)?;
+        let rows = account_rows(accounts)?;
+        let transaction = self.connection.transaction()?;
+
+        // Nothing has been read or written through this store yet, so whatever
+        // is in the database is from some other ledger.
+        let empty = Saved::default();
+        let saved = if let Some(saved) = &self.saved {
+            saved
+        } else {
+            transaction.execute_batch(
+                "DELETE FROM settings; DELETE FROM accounts; DELETE FROM transactions;",
+            )?;
+            &empty
+        };
+
+        for (key, value) in &settings {
+            if saved.settings.get(key) != Some(value) {
+                transaction.execute(
+                    "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+                    params![key, value],
+                )?;
+            }
+        }
+
+        write_accounts(&transaction, &saved.accounts, &rows)?;
+        let hashes = write_transactions(&transaction, &saved.transactions, accounts)?;
+
+        transaction.commit()?;
+        self.saved = Some(Saved {
+            settings,
+            accounts: rows,
+            transactions: hashes,
+        });
+        Ok(())
+    }
+}
+
+fn setting<T: DeserializeOwned>(
+    settings: &HashMap<String, String>,
+    key: &str,
+) -> anyhow::Result<Option<T>> {
+    settings
+        .get(key)
+        .map(|value| ron::from_str(value).with_context(|| format!("reading the setting {key}")))
+        .transpose()
+}
+
+/// The columns `Store::transactions` and `Store::transactions_between` select.
+type Columns = (i64, String, String, String, bool);
+
+fn columns(row: &Row<'_>) -> rusqlite::Result<Columns> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+    ))
+}
+
+fn transaction((date, amount, balance, comment, snapshot): Columns) -> anyhow::Result<Transaction> {
+    Ok(Transaction {
+        amount: amount.parse()?,
+        balance: balance.parse()?,
+        comment,
+        date: DateTime::from_timestamp(date, 0)
+            .with_context(|| format!("{date} isn't a valid date!"))?,
+        snapshot,
+    })
+}
+
+/// Runs an insert or update of a transaction, which both take the columns in
+/// the order of the table.
+fn execute(
+    statement: &mut CachedStatement<'_>,
+    account: &str,
+    secondary: bool,
+    position: i64,
+    tx: &Transaction,
+) -> rusqlite::Result<usize> {
+    statement.execute(params![
+        account,
+        secondary,
+        position,
+        tx.date.timestamp(),
+        tx.amount.to_string(),
+        tx.balance.to_string(),
+        tx.comment,
+        tx.snapshot,
+    ])
+}
+
+fn settings(accounts: &Accounts) -> anyhow::Result<HashMap<String, String>> {
+    let settings = [
+        ("version", ron::to_string(&format::VERSION)?),
+        ("groups", ron::to_string(&accounts.groups)?),
+        ("crypto", ron::to_string(&accounts.crypto)?),
+        ("fiats", ron::to_string(&accounts.fiats)?),
+        ("metals", ron::to_string(&accounts.metals)?),
+        ("stocks_plus", ron::to_string(&accounts.stocks_plus)?),
+        (
+            "duplicate_matcher",
+            ron::to_string(&accounts.duplicate_matcher)?,
+        ),
+        ("backups", ron::to_string(&accounts.backups)?),
+        ("includes", ron::to_string(&accounts.includes)?),
+        ("scripts", ron::to_string(&accounts.scripts)?),
+    ];
+    Ok(settings
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect())
+}
+
+fn account_rows(accounts: &Accounts) -> anyhow::Result<Vec<AccountRow>> {
+    accounts
+        .inner
+        .iter()
+        .map(|account| {
+            Ok(AccountRow {
+                name: account.name.clone(),
+                currency: ron::to_string(&account.txs_1st.currency)?,
+                currency_secondary: account
+                    .txs_2nd
+                    .as_ref()
+                    .map(|txs| ron::to_string(&txs.currency))
+                    .transpose()?,
+                import_decisions: ron::to_string(&account.import_decisions)?,
+            })
+        })
+        .collect()
+}
+
+/// Writes the accounts whose row changed. Replacing a row also deletes the
+/// one further down with the same name, which is rewritten or deleted after.
+fn write_accounts(
+    transaction: &rusqlite::Transaction<'_>,
+    saved: &[AccountRow],
+    rows: &[AccountRow],
+) -> anyhow::Result<()> {
+    let mut insert = transaction.prepare_cached(
+        "INSERT OR REPLACE INTO accounts (position, name, currency, currency_secondary, import_decisions)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+    )?;
+    for (position, row) in (0_i64..).zip(rows) {
+        let i = usize::try_from(position)?;
+        if saved.get(i) != Some(row) {
+            insert.execute(params![
+                position,
+                row.name,
+                row.currency,
+                row.currency_secondary,
+                row.import_decisions,
+            ])?;
+        }
+    }
+
+    if rows.len() < saved.len() {
+        transaction.execute(
+            "DELETE FROM accounts WHERE position >= ?1",
+            [i64::try_from(rows.len())?],
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes the transactions that changed and returns the hashes of all of them.
+/// When a list is as long as before only the rows that differ are updated,
+/// otherwise the rows from the first that differs are written again, as the
+/// positions after it moved.
+fn write_transactions(
+    transaction: &rusqlite::Transaction<'_>,
+    saved: &HashMap<(String, bool), Vec<u64>>,
+    accounts: &Accounts,
+) -> anyhow::Result<HashMap<(String, bool), Vec<u64>>> {
+    let hashes = hashes(accounts);
+    for key in saved.keys().filter(|key| !hashes.contains_key(*key)) {
+        transaction.execute(
+            "DELETE FROM transactions WHERE account = ?1 AND secondary = ?2",
+            params![key.0, key.1],
+        )?;
+    }
+
+    let mut update = transaction.prepare_cached(
+        "UPDATE transactions SET date = ?4, amount = ?5, balance = ?6, comment = ?7, snapshot = ?8
+         WHERE account = ?1 AND secondary = ?2 AND position = ?3",
+    )?;
+    let mut insert = transaction.prepare_cached(
+        "INSERT INTO transactions (account, secondary, position, date, amount, balance, comment, snapshot)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+    )?;
+    for account in &accounts.inner {
+        let lists = std::iter::once((false, &account.txs_1st.txs))
+            .chain(account.txs_2nd.as_ref().map(|txs| (true, &txs.txs)));
+        for (secondary, txs) in lists {
+            let key = (account.name.clone(), secondary);
+            let new = &hashes[&key];
+            let old = saved.get(&key).map_or(&[][..], Vec::as_slice);
+            if new.len() == old.len() {
+                for (position, ((tx, new), old)) in (0_i64..).zip(txs.iter().zip(new).zip(old)) {
+                    if new != old {
+                        execute(&mut update, &account.name, secondary, position, tx)?;
+                    }
+                }
+                continue;
+            }
+
+            let start = new.iter().zip(old).take_while(|(a, b)| a == b).count();
+            let start_i64 = i64::try_from(start)?;
+            transaction.execute(
+                "DELETE FROM transactions WHERE account = ?1 AND secondary = ?2 AND position >= ?3",
+                params![account.name, secondary, start_i64],
+            )?;
+            for (position, tx) in (start_i64..).zip(&txs[start..]) {
+                execute(&mut insert, &account.name, secondary, position, tx)?;
+            }
+        }
+    }
+    Ok(hashes)
+}
+
+/// A hash of each transaction of every account, keyed like `Saved::transactions`.
+fn hashes(accounts: &Accounts) -> HashMap<(String, bool), Vec<u64>> {
+    let mut hashes = HashMap::new();
+    for account in &accounts.inner {
+        hashes.insert(
+            (account.name.clone(), false),
+            account.txs_1st.txs.iter().map(hash).collect(),
+        );
+        if let Some(txs_2nd) = &account.txs_2nd {
+            hashes.insert(
+                (account.name.clone(), true),
+                txs_2nd.txs.iter().map(hash).collect(),
+            );
+        }
+    }
+    hashes
+}
+
+/// A hash of the columns a transaction is stored in. The serialized decimals
+/// keep the scale, so `1.0` and `1.00` aren't the same row.
+fn hash(tx: &Transaction) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    tx.date.timestamp().hash(&mut hasher);
+    tx.amount.serialize().hash(&mut hasher);
+    tx.balance.serialize().hash(&mut hasher);
+    tx.comment.hash(&mut hasher);
+    tx.snapshot.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+
+    use super::*;
+    use crate::ledger::{backups, testing};
+
+    fn tx(date: &str, amount: i64, comment: &str) -> Transaction {
+        Transaction {
+            amount: Decimal::from(amount),
+            balance: Decimal::ZERO,
+            comment: comment.to_string(),
+            date: crate::ledger::parse_date(date).unwrap(),
+            snapshot: false,
+        }
+    }
+
+    fn account(name: &str, txs: &[Transaction]) -> Account {
+        let mut account = Account::new(name.to_string(), Currency::Fiat(Fiat::Usd));
+        for tx in txs {
+            account.insert_tx(false, tx.clone());
+        }
+        account
+    }
+
+    fn ledger() -> Accounts {
+        let mut accounts = Accounts::new();
+        accounts.fiats.push(Fiat::Usd);
+        accounts.insert_account(
+            account(
+                "Checking",
+                &[
+                    tx("2024-01-01", 100, "pay"),
+                    tx("2024-02-01", -25, "coffee"),
+                    tx("2024-03-01", -50, "groceries"),
+                ],
+            ),
+            &[],
+        );
+        accounts.insert_account(account("Savings", &[tx("2024-01-15", 1000, "")]), &[]);
+        accounts
+    }
+
+    /// The names and transactions of each account.
+    fn contents(accounts: &Accounts) -> Vec<(String, Vec<Transaction>)> {
+        accounts
+            .inner
+            .iter()
+            .map(|account| (account.name.clone(), account.txs_1st.txs.clone()))
+            .collect()
+    }
+
+    fn total_changes(store: &Store) -> i64 {
+        store
+            .connection
+            .query_row("SELECT total_changes()", [], |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn saves_write_only_what_changed() {
+        let path = testing::directory("sqlite-changes").join("ledger.db");
+        let mut accounts = ledger();
+        let mut store = Store::create(&path).unwrap();
+        store.write(&accounts).unwrap();
+
+        let before = total_changes(&store);
+        store.write(&accounts).unwrap();
+        assert_eq!(total_changes(&store), before);
+
+        let i = accounts.find("Checking").unwrap();
+        accounts[i].insert_tx(false, tx("2024-04-01", -5, "stamps"));
+        store.write(&accounts).unwrap();
+        assert_eq!(total_changes(&store), before + 1);
+
+        accounts[i].txs_1st.txs[1].comment = "tea".to_string();
+        store.write(&accounts).unwrap();
+        assert_eq!(total_changes(&store), before + 2);
+    }
+
+    #[test]
+    fn saves_keep_the_ledger() {
+        let path = testing::directory("sqlite-saves").join("ledger.db");
+        let mut accounts = ledger();
+        let mut store = Store::create(&path).unwrap();
+        store.write(&accounts).unwrap();
+
+        // Backdated, so the rows after it move.
+        let checking = accounts.find("Checking").unwrap();
+        accounts[checking].insert_tx(false, tx("2023-12-01", 10, "refund"));
+        accounts.insert_account(account("Cash", &[tx("2024-01-05", 20, "")]), &[]);
+        let savings = accounts.find("Savings").unwrap();
+        accounts.remove_account(savings);
+        accounts.duplicate_matcher.days = 7;
+        store.write(&accounts).unwrap();
+        drop(store);
+
+        let (_store, read) = Store::open(&path).unwrap();
+        assert_eq!(contents(&read), contents(&accounts));
+        assert_eq!(read.duplicate_matcher.days, 7);
+    }
+
+    #[test]
+    fn queries_read_the_dates_searched_with_the_index() {
+        let path = testing::directory("sqlite-query").join("ledger.db");
+        let mut store = Store::create(&path).unwrap();
+        store.write(&ledger()).unwrap();
+
+        let store = Store::open_read_only(&path).unwrap();
+        let query: Query = "account:checking date:2024-02..2024-03".parse().unwrap();
+        let (accounts, found) = store.query(&query).unwrap().unwrap();
+        assert_eq!(found.len(), 1);
+        let comments: Vec<_> = found[0].txs.txs.iter().map(|tx| &tx.comment).collect();
+        assert_eq!(comments, ["coffee", "groceries"]);
+        assert!(
+            found[0]
+                .accounts
+                .iter()
+                .all(|i| accounts.inner[*i].name == "Checking")
+        );
+
+        let plan: String = store
+            .connection
+            .query_row(
+                "EXPLAIN QUERY PLAN SELECT date FROM transactions
+                 WHERE account = 'Checking' AND secondary = 0 AND date >= 0 AND date < 1
+                 ORDER BY date, position",
+                [],
+                |row| row.get(3),
+            )
+            .unwrap();
+        assert!(plan.contains("transactions_by_date"), "{plan}");
+    }
+
+    /// A backup has what was committed, and not a write that is under way.
+    #[test]
+    fn backups_leave_out_what_isnt_committed() {
+        let path = testing::directory("sqlite-backup").join("ledger.db");
+        let mut store = Store::create(&path).unwrap();
+        store.write(&ledger()).unwrap();
+
+        store.connection.execute_batch("BEGIN IMMEDIATE").unwrap();
+        store
+            .connection
+            .execute("DELETE FROM transactions", [])
+            .unwrap();
+        backups::create(&path, backups::DEFAULT_COUNT).unwrap();
+        store.connection.execute_batch("ROLLBACK").unwrap();
+
+        let backup = backups::list(&path).unwrap().remove(0);
+        let (_store, accounts) = Store::open(&backup).unwrap();
+        assert_eq!(contents(&accounts), contents(&ledger()));
+    }
+}
@@ -0,0 +1,81 @@
+use std::{env, fs, path::PathBuf};
+
+use rust_decimal::Decimal;
+
+use super::{
+    account::{Account, parse_date, transaction::Transaction},
+    accounts::{Accounts, Group},
+    metal::Metal,
+    money::{Currency, Fiat},
+};
+
+/// An empty directory of its own for a test, so tests can run at the same time.
+pub(crate) fn directory(test: &str) -> PathBuf {
+    let directory =
+        env::temp_dir().join(format!("financial-accounts-{}-{test}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+/// A ledger from before the file format had a version, see `format::from_0`.
+pub(crate) const FORMAT_0: &str = include_str!("testdata/format-0.ron");
+
+/// A transaction, with the amount worked out from the balance if `snapshot`.
+pub(crate) fn tx(date: &str, value: &str, comment: &str, snapshot: bool) -> Transaction {
+    let value: Decimal = value.parse().unwrap();
+    Transaction {
+        amount: if snapshot { Decimal::ZERO } else { value },
+        balance: if snapshot { value } else { Decimal::ZERO },
+        comment: comment.to_string(),
+        date: parse_date(date).unwrap(),
+        snapshot,
+    }
+}
+
+/// A small ledger: Checking and Savings in the group Cash, and Gold, which
+/// holds gold valued in dollars.
+pub(crate) fn accounts() -> Accounts {
+    let mut accounts = Accounts::new();
+    accounts.fiats.push(Fiat::Usd);
+    let gold = Metal {
+        currency: Fiat::Usd,
+        description: "Gold Troy Oz".to_string(),
+        symbol: "XAU".to_string(),
+    };
+    accounts.metals.push(gold.clone());
+    accounts.groups.push(Group {
+        name: "Cash".to_string(),
+        members: Vec::new(),
+        source: None,
+    });
+
+    let mut checking = Account::new("Checking".to_string(), Currency::Fiat(Fiat::Usd));
+    for tx in [
+        tx("2024-01-01", "100", "Pay day", false),
+        tx("2024-01-15", "-25", "Coffee: beans", false),
+        tx("2024-02-01", "500", "statement", true),
+        tx("2024-03-01", "-50", "GROCERIES 1234", false),
+    ] {
+        checking.insert_tx(false, tx);
+    }
+    accounts.insert_account(checking, &[0]);
+
+    let mut savings = Account::new("Savings".to_string(), Currency::Fiat(Fiat::Usd));
+    savings.insert_tx(false, tx("2024-01-10", "1000", "opening", false));
+    accounts.insert_account(savings, &[0]);
+
+    let mut holding = Account::new("Gold".to_string(), Currency::Metal(gold));
+    holding.insert_tx(true, tx("2024-01-01", "1", "buy", false));
+    holding.insert_tx(true, tx("2024-02-15", "0.5", "buy more", false));
+    for tx in [
+        tx("2024-01-01", "2000", "", true),
+        tx("2024-02-01", "2100", "", true),
+        tx("2024-03-01", "3450", "", true),
+    ] {
+        holding.insert_tx(false, tx);
+    }
+    accounts.insert_account(holding, &[]);
+
+    accounts
+}
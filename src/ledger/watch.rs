@@ -0,0 +1,76 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    fs,
+    hash::{DefaultHasher, Hasher},
+    io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// The ledger as it was when it was last loaded or saved, to notice when some
+/// other program changes it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Stamp {
+    modified: SystemTime,
+    len: u64,
+    /// A hash of the contents, so a file that was only touched isn't taken as
+    /// changed. SQLite files are too big to hash on every save and go by the
+    /// time and size alone.
+    hash: Option<u64>,
+}
+
+impl Stamp {
+    pub fn read(file_path: &Path, hash: bool) -> anyhow::Result<Self> {
+        let metadata = fs::metadata(file_path)?;
+        Ok(Self {
+            modified: metadata.modified()?,
+            len: metadata.len(),
+            hash: if hash {
+                Some(hash_file(file_path)?)
+            } else {
+                None
+            },
+        })
+    }
+
+    /// Whether the file is different from when it was stamped, or gone.
+    pub fn changed(&self, file_path: &Path) -> anyhow::Result<bool> {
+        let metadata = match fs::metadata(file_path) {
+            Ok(metadata) => metadata,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(true),
+            Err(error) => return Err(error.into()),
+        };
+        if metadata.modified()? == self.modified && metadata.len() == self.len {
+            return Ok(false);
+        }
+
+        match self.hash {
+            Some(hash) => Ok(metadata.len() != self.len || hash_file(file_path)? != hash),
+            None => Ok(true),
+        }
+    }
+}
+
+fn hash_file(file_path: &Path) -> anyhow::Result<u64> {
+    let mut hasher = DefaultHasher::new();
+    hasher.write(&fs::read(file_path)?);
+    Ok(hasher.finish())
+}
+
+/// Returned when saving would write over changes some other program made to
+/// the ledger.
+#[derive(Clone, Debug)]
+pub struct Changed(pub PathBuf);
+
+impl Display for Changed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "{} was changed by another program, so it wasn't saved.",
+            self.0.display()
+        )
+    }
+}
+
+impl Error for Changed {}
@@ -1,2 +1,8 @@
 /// The financial-accounts application.
+#[cfg(feature = "gui")]
 pub mod app;
+/// The command line arguments, and the commands that work without a window.
+pub mod command_line;
+/// Ledgers without the window: loading, querying, changing and saving them, and
+/// getting prices.
+pub mod ledger;